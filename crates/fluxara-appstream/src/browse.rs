@@ -0,0 +1,163 @@
+//! Browsing the catalog: categories, new and updated apps, popularity and curated collections
+
+use crate::{AppStreamManager, Component};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use fluxara_core::{AppRating, Config};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// freedesktop.org main categories, in the order the store shows them
+///
+/// `Audio` and `Video` are only valid alongside `AudioVideo` and are browsed through it.
+pub const MAIN_CATEGORIES: &[&str] = &[
+    "AudioVideo",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+/// Collections shipped by the distribution, used when the user has none
+const SYSTEM_COLLECTIONS: &str = "/usr/share/fluxara/collections.toml";
+
+/// A hand-picked list of apps, e.g. "Essentials" or "Made for GNOME"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Component ids, in display order
+    pub apps: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectionsFile {
+    #[serde(default, rename = "collection", alias = "collections")]
+    collections: Vec<Collection>,
+}
+
+impl Collection {
+    /// Load collections from TOML (`[[collection]]` tables) or JSON (a `collections` array)
+    pub fn load(path: &Path) -> Result<Vec<Collection>> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: CollectionsFile = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid collections file {}", path.display()))?
+        } else {
+            toml::from_str(&content)
+                .with_context(|| format!("Invalid collections file {}", path.display()))?
+        };
+        Ok(file.collections)
+    }
+
+    /// The user's collections next to the configuration, or else the distribution's
+    pub fn load_default() -> Result<Vec<Collection>> {
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        if let Some(dir) = Config::config_path()?.parent() {
+            candidates.push(dir.join("collections.toml"));
+            candidates.push(dir.join("collections.json"));
+        }
+        candidates.push(PathBuf::from(SYSTEM_COLLECTIONS));
+
+        match candidates.iter().find(|path| path.is_file()) {
+            Some(path) => Self::load(path),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+impl AppStreamManager {
+    /// Every app, one entry per component id, as the store lists them
+    pub fn apps(&self) -> impl Iterator<Item = &Component> {
+        let mut seen = HashSet::new();
+        self.catalog()
+            .components()
+            .iter()
+            .filter(|c| c.kind == "desktop-application")
+            .filter(move |c| seen.insert(c.app_id().to_string()))
+    }
+
+    /// Apps in a main or additional category, by name
+    pub fn browse(&self, category: &str) -> Vec<&Component> {
+        let mut apps: Vec<&Component> = self
+            .apps()
+            .filter(|c| c.categories.iter().any(|cat| cat == category))
+            .collect();
+        apps.sort_by_key(|c| c.name.to_lowercase());
+        apps
+    }
+
+    /// Number of apps in each main category, skipping empty ones
+    pub fn category_counts(&self) -> Vec<(&'static str, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for app in self.apps() {
+            for category in &app.categories {
+                *counts.entry(category.as_str()).or_default() += 1;
+            }
+        }
+        MAIN_CATEGORIES
+            .iter()
+            .filter_map(|&category| Some((category, *counts.get(category)?)))
+            .collect()
+    }
+
+    /// Apps whose first release is the most recent
+    pub fn new_apps(&self, limit: usize) -> Vec<&Component> {
+        self.newest_by(limit, |c| c.releases.iter().filter_map(|r| r.date).min())
+    }
+
+    /// Apps whose latest release is the most recent
+    pub fn recently_updated(&self, limit: usize) -> Vec<&Component> {
+        self.newest_by(limit, |c| c.releases.iter().filter_map(|r| r.date).max())
+    }
+
+    /// Apps with the most ODRS reviews, better rated first among equals
+    pub fn popular(&self, ratings: &HashMap<String, AppRating>, limit: usize) -> Vec<&Component> {
+        let mut rated: Vec<(&Component, &AppRating)> = self
+            .apps()
+            .filter_map(|c| {
+                let rating = ratings
+                    .get(&c.id)
+                    .or_else(|| ratings.get(c.app_id()))
+                    .filter(|r| r.total_reviews > 0)?;
+                Some((c, rating))
+            })
+            .collect();
+        rated.sort_by(|(_, a), (_, b)| {
+            b.total_reviews
+                .cmp(&a.total_reviews)
+                .then(b.average_rating.total_cmp(&a.average_rating))
+        });
+        rated.into_iter().take(limit).map(|(c, _)| c).collect()
+    }
+
+    /// The apps of a collection that the catalog knows, in the collection's order
+    pub fn collection_apps(&self, collection: &Collection) -> Vec<&Component> {
+        collection
+            .apps
+            .iter()
+            .filter_map(|id| self.catalog().get(id))
+            .collect()
+    }
+
+    fn newest_by(
+        &self,
+        limit: usize,
+        date: impl Fn(&Component) -> Option<NaiveDate>,
+    ) -> Vec<&Component> {
+        let mut dated: Vec<(NaiveDate, &Component)> =
+            self.apps().filter_map(|c| Some((date(c)?, c))).collect();
+        dated.sort_by(|(a, _), (b, _)| b.cmp(a));
+        dated.into_iter().take(limit).map(|(_, c)| c).collect()
+    }
+}
@@ -0,0 +1,271 @@
+use crate::component::{normalize_id, Component};
+use crate::{dep11, xml};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use fluxara_core::{IdentityResolver, PackageSource};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Collection catalogs of the distribution, each with `xml/` and `yaml/` subdirectories
+const CATALOG_DIRS: &[&str] = &[
+    "/usr/share/swcatalog",
+    "/var/lib/swcatalog",
+    "/var/lib/app-info",
+    "/var/cache/swcatalog",
+];
+
+/// Metainfo files of installed software
+const METAINFO_DIRS: &[&str] = &["/usr/share/metainfo", "/usr/share/appdata"];
+
+/// Flatpak installations, each with per-remote catalogs under `appstream/`
+const FLATPAK_SYSTEM_DIR: &str = "/var/lib/flatpak";
+
+/// In-memory index of AppStream components
+#[derive(Debug, Default)]
+pub struct Catalog {
+    components: Vec<Component>,
+    by_id: HashMap<String, Vec<usize>>,
+    by_package: HashMap<(Option<PackageSource>, String), Vec<usize>>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every catalog on the system; unreadable files are skipped
+    pub fn load_system() -> Self {
+        let mut catalog = Self::new();
+        let mut seen = HashSet::new();
+
+        for dir in CATALOG_DIRS {
+            // /var/lib/app-info is often a symlink to /var/lib/swcatalog
+            let Ok(dir) = Path::new(dir).canonicalize() else {
+                continue;
+            };
+            if !seen.insert(dir.clone()) {
+                continue;
+            }
+            for file in files(&dir.join("xml")).chain(files(&dir.join("yaml"))) {
+                match catalog.load_file(&file, None) {
+                    Ok(count) => {
+                        tracing::debug!("Loaded {} components from {}", count, file.display())
+                    }
+                    Err(e) => tracing::warn!("Skipping {}: {:#}", file.display(), e),
+                }
+            }
+        }
+
+        for (remote, file) in flatpak_catalogs() {
+            match parse_file(&file) {
+                Ok(mut components) => {
+                    // Flatpak catalogs do not name their origin; the remote does
+                    for component in &mut components {
+                        component.source = Some(PackageSource::Flatpak);
+                        component.origin.get_or_insert_with(|| remote.clone());
+                    }
+                    catalog.add(components);
+                }
+                Err(e) => tracing::warn!("Skipping {}: {:#}", file.display(), e),
+            }
+        }
+
+        // Installed metainfo only fills in what no catalog describes
+        for dir in METAINFO_DIRS {
+            for file in files(Path::new(dir)) {
+                match parse_file(&file) {
+                    Ok(components) => {
+                        let new = components
+                            .into_iter()
+                            .filter(|c| catalog.get(&c.id).is_none())
+                            .collect();
+                        catalog.add(new);
+                    }
+                    Err(e) => tracing::debug!("Skipping {}: {:#}", file.display(), e),
+                }
+            }
+        }
+
+        catalog
+    }
+
+    /// Load a catalog or metainfo file, XML or DEP-11 YAML, optionally gzip-compressed
+    pub fn load_file(&mut self, path: &Path, source: Option<PackageSource>) -> Result<usize> {
+        let mut components = parse_file(path)?;
+        for component in &mut components {
+            component.source = component.source.or(source);
+        }
+
+        let count = components.len();
+        self.add(components);
+        Ok(count)
+    }
+
+    pub fn add(&mut self, components: Vec<Component>) {
+        for component in components {
+            let index = self.components.len();
+            self.by_id
+                .entry(component.app_id().to_string())
+                .or_default()
+                .push(index);
+            if let Some(pkgname) = &component.pkgname {
+                self.by_package
+                    .entry((component.source, pkgname.clone()))
+                    .or_default()
+                    .push(index);
+            }
+            self.components.push(component);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// Look up a component by id, with or without a `.desktop` suffix
+    ///
+    /// When several catalogs describe the same app, the first loaded wins.
+    pub fn get(&self, id: &str) -> Option<&Component> {
+        self.get_all(id).next()
+    }
+
+    /// Every catalog's description of a component
+    pub fn get_all(&self, id: &str) -> impl Iterator<Item = &Component> {
+        self.by_id
+            .get(normalize_id(id))
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.components[i])
+    }
+
+    /// The component shipped by a package of a source
+    ///
+    /// When a package ships several, the first loaded wins.
+    pub fn find_by_package(&self, source: PackageSource, package_id: &str) -> Option<&Component> {
+        self.find_all_by_package(source, package_id).next()
+    }
+
+    /// Every component shipped by a package of a source
+    pub fn find_all_by_package(
+        &self,
+        source: PackageSource,
+        package_id: &str,
+    ) -> impl Iterator<Item = &Component> {
+        let source = (source == PackageSource::Flatpak).then_some(PackageSource::Flatpak);
+        self.by_package
+            .get(&(source, package_id.to_string()))
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.components[i])
+    }
+
+    /// Map packages to apps using component ids, pkgnames, launchables and provides
+    pub fn identity_resolver(&self) -> IdentityResolver {
+        let mut resolver = IdentityResolver::new();
+        for component in &self.components {
+            let app_id = component.app_id();
+            if let Some(pkgname) = &component.pkgname {
+                resolver.add_package(app_id, component.source, pkgname);
+            }
+            for desktop_id in &component.launchables {
+                resolver.add_launchable(app_id, desktop_id);
+            }
+            for provided in &component.provides {
+                resolver.add_provides(app_id, provided);
+            }
+        }
+        resolver
+    }
+}
+
+fn parse_file(path: &Path) -> Result<Vec<Component>> {
+    let content = read(path)?;
+    let name = path.to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+
+    let mut components = if name.ends_with(".yml") || name.ends_with(".yaml") {
+        dep11::parse(&content)?
+    } else {
+        xml::parse(&content)?
+    };
+    for component in &mut components {
+        component.icon_cache = icon_cache_dir(path, component.origin.as_deref());
+    }
+    Ok(components)
+}
+
+/// Where the cached icons of a catalog file live
+///
+/// System catalogs keep them in `icons/<origin>` next to their `xml` or `yaml`
+/// directory, Flatpak remotes in `icons` next to `appstream.xml`.
+fn icon_cache_dir(file: &Path, origin: Option<&str>) -> Option<PathBuf> {
+    let parent = file.parent()?;
+    let name = file.file_name()?.to_str()?;
+    match parent.file_name().and_then(|name| name.to_str()) {
+        Some("xml" | "yaml") => Some(parent.parent()?.join("icons").join(origin?)),
+        _ if name.starts_with("appstream.xml") => Some(parent.join("icons")),
+        // Metainfo files have no icon cache
+        _ => None,
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut content = String::new();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(file).read_to_string(&mut content)
+    } else {
+        std::io::BufReader::new(file).read_to_string(&mut content)
+    }
+    .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content)
+}
+
+/// Regular files of a directory, sorted so loading order is stable
+fn files(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files.into_iter()
+}
+
+/// `(remote, appstream.xml.gz)` for every remote of the system and user installations
+fn flatpak_catalogs() -> Vec<(String, PathBuf)> {
+    let mut installations = vec![PathBuf::from(FLATPAK_SYSTEM_DIR)];
+    if let Ok(home) = std::env::var("HOME") {
+        installations.push(PathBuf::from(home).join(".local/share/flatpak"));
+    }
+
+    let mut catalogs = Vec::new();
+    for installation in installations {
+        let Ok(remotes) = std::fs::read_dir(installation.join("appstream")) else {
+            continue;
+        };
+        for remote in remotes.filter_map(|entry| entry.ok()) {
+            let active = remote.path().join(std::env::consts::ARCH).join("active");
+            let file = ["appstream.xml.gz", "appstream.xml"]
+                .iter()
+                .map(|name| active.join(name))
+                .find(|path| path.is_file());
+            if let Some(file) = file {
+                catalogs.push((remote.file_name().to_string_lossy().into_owned(), file));
+            }
+        }
+    }
+    catalogs
+}
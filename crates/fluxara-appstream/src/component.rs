@@ -0,0 +1,107 @@
+use chrono::NaiveDate;
+use fluxara_core::{ContentRating, PackageSource};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// One AppStream component, as read from a catalog or a metainfo file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Component {
+    pub id: String,
+    /// Component type, e.g. `desktop-application`, `console-application`, `addon`
+    pub kind: String,
+    /// Package shipping the component: the distribution package, or the Flatpak app id
+    pub pkgname: Option<String>,
+    /// Catalog the component came from, e.g. `debian-bookworm-main` or a Flatpak remote
+    pub origin: Option<String>,
+    /// Source installing `pkgname`; `None` for the distribution package manager
+    pub source: Option<PackageSource>,
+    pub name: String,
+    pub summary: Option<String>,
+    /// Description markup rendered as plain text: paragraphs separated by blank lines
+    pub description: Option<String>,
+    pub developer: Option<String>,
+    pub project_license: Option<String>,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    pub icons: Vec<Icon>,
+    /// Directory of the catalog's cached icons, holding `<width>x<height>[@<scale>]` subdirectories
+    pub icon_cache: Option<PathBuf>,
+    pub screenshots: Vec<Screenshot>,
+    /// Newest first, as AppStream lists them
+    pub releases: Vec<Release>,
+    pub content_rating: Option<ContentRating>,
+    /// Links by type: `homepage`, `bugtracker`, `donation`, `help`, ...
+    pub urls: BTreeMap<String, String>,
+    /// Desktop ids of the `.desktop` files launching the component
+    pub launchables: Vec<String>,
+    /// Component ids this one provides, usually ids it was known by before
+    pub provides: Vec<String>,
+}
+
+impl Component {
+    /// The component id without the `.desktop` suffix older catalogs carry,
+    /// so `org.gnome.Calculator.desktop` and `org.gnome.Calculator` match
+    pub fn app_id(&self) -> &str {
+        normalize_id(&self.id)
+    }
+}
+
+pub(crate) fn normalize_id(id: &str) -> &str {
+    id.strip_suffix(".desktop").unwrap_or(id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconKind {
+    /// Icon name from the icon theme
+    Stock,
+    /// File name in the catalog's icon cache
+    Cached,
+    /// Absolute path on disk
+    Local,
+    /// URL to download
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Icon {
+    pub kind: IconKind,
+    /// Name, file name, path or URL depending on `kind`
+    pub value: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// HiDPI scale factor the icon was rendered for
+    pub scale: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Screenshot {
+    pub default: bool,
+    pub caption: Option<String>,
+    /// Full-size image
+    pub url: String,
+    /// Smallest thumbnail, when the catalog provides them
+    pub thumbnail_url: Option<String>,
+    /// Every size the catalog offers, the full-size image included
+    pub images: Vec<ScreenshotImage>,
+}
+
+/// One size variant of a screenshot
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenshotImage {
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Scaled-down copy rather than the full-size image
+    pub thumbnail: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Release {
+    pub version: String,
+    pub date: Option<NaiveDate>,
+    /// `low`, `medium`, `high` or `critical`
+    pub urgency: Option<String>,
+    pub description: Option<String>,
+}
@@ -0,0 +1,291 @@
+//! DEP-11: the YAML catalog format Debian and Ubuntu ship through APT
+
+use crate::component::{Component, Icon, IconKind, Release, Screenshot, ScreenshotImage};
+use crate::xml::{media_url, render_markup_str};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use fluxara_core::ContentRating;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+/// Every document of a DEP-11 file; the first one is a header naming the catalog
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+struct Document {
+    origin: Option<String>,
+    media_base_url: Option<String>,
+    #[serde(rename = "ID")]
+    id: Option<String>,
+    #[serde(rename = "Type")]
+    kind: Option<String>,
+    package: Option<String>,
+    name: Option<Localized<String>>,
+    summary: Option<Localized<String>>,
+    description: Option<Localized<String>>,
+    developer_name: Option<Localized<String>>,
+    developer: Option<Developer>,
+    project_license: Option<String>,
+    categories: Vec<String>,
+    keywords: Option<Localized<Vec<String>>>,
+    icon: Icons,
+    screenshots: Vec<YamlScreenshot>,
+    releases: Vec<YamlRelease>,
+    content_rating: BTreeMap<String, Option<BTreeMap<String, String>>>,
+    url: BTreeMap<String, String>,
+    launchable: BTreeMap<String, Vec<String>>,
+    provides: Provides,
+}
+
+/// Translated values keyed by locale; `C` is the untranslated one
+#[derive(Debug, Deserialize)]
+struct Localized<T> {
+    #[serde(rename = "C")]
+    c: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Developer {
+    name: Option<Localized<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Icons {
+    stock: Option<String>,
+    cached: Vec<YamlIcon>,
+    local: Vec<YamlIcon>,
+    remote: Vec<YamlIcon>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YamlIcon {
+    name: Option<String>,
+    url: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    scale: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YamlImage {
+    url: String,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YamlScreenshot {
+    #[serde(default)]
+    default: bool,
+    caption: Option<Localized<String>>,
+    #[serde(rename = "source-image")]
+    source_image: Option<YamlImage>,
+    #[serde(default)]
+    thumbnails: Vec<YamlImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YamlRelease {
+    #[serde(deserialize_with = "scalar")]
+    version: String,
+    #[serde(rename = "unix-timestamp")]
+    unix_timestamp: Option<i64>,
+    date: Option<String>,
+    urgency: Option<String>,
+    description: Option<Localized<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Provides {
+    ids: Vec<String>,
+}
+
+/// Parse a DEP-11 catalog, skipping documents that do not describe a valid component
+pub fn parse(content: &str) -> Result<Vec<Component>> {
+    let mut origin = None;
+    let mut media_base = None;
+    let mut components = Vec::new();
+
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let document = match Document::deserialize(document) {
+            Ok(document) => document,
+            Err(e) => {
+                tracing::debug!("Skipping DEP-11 document: {}", e);
+                continue;
+            }
+        };
+
+        let Some(id) = document.id.clone() else {
+            // The header precedes every component
+            origin = document.origin.or(origin);
+            media_base = document.media_base_url.or(media_base);
+            continue;
+        };
+        components.push(component(
+            id,
+            document,
+            origin.as_deref(),
+            media_base.as_deref(),
+        ));
+    }
+
+    Ok(components)
+}
+
+fn component(
+    id: String,
+    mut document: Document,
+    origin: Option<&str>,
+    media_base: Option<&str>,
+) -> Component {
+    let icons = document
+        .icon
+        .stock
+        .map(|name| Icon {
+            kind: IconKind::Stock,
+            value: name,
+            width: None,
+            height: None,
+            scale: None,
+        })
+        .into_iter()
+        .chain(icons(IconKind::Cached, document.icon.cached, None))
+        .chain(icons(IconKind::Local, document.icon.local, None))
+        .chain(icons(IconKind::Remote, document.icon.remote, media_base))
+        .collect();
+
+    Component {
+        name: c(document.name).unwrap_or_else(|| id.clone()),
+        id,
+        kind: document
+            .kind
+            .unwrap_or_else(|| "desktop-application".to_string()),
+        pkgname: document.package,
+        origin: origin.map(str::to_string),
+        source: None,
+        summary: c(document.summary),
+        description: c(document.description).and_then(|d| render_markup_str(&d)),
+        developer: document
+            .developer
+            .and_then(|d| c(d.name))
+            .or_else(|| c(document.developer_name)),
+        project_license: document.project_license,
+        categories: document.categories,
+        keywords: c(document.keywords).unwrap_or_default(),
+        icons,
+        icon_cache: None,
+        screenshots: document
+            .screenshots
+            .into_iter()
+            .filter_map(|s| screenshot(s, media_base))
+            .collect(),
+        releases: document.releases.into_iter().map(release).collect(),
+        content_rating: document
+            .content_rating
+            .into_iter()
+            .next()
+            .map(|(kind, attributes)| ContentRating {
+                kind,
+                attributes: attributes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|(id, level)| Some((id, level.parse().ok()?)))
+                    .collect(),
+            }),
+        urls: document.url,
+        launchables: document.launchable.remove("desktop-id").unwrap_or_default(),
+        provides: document.provides.ids,
+    }
+}
+
+/// Versions such as `43.0` are sometimes left unquoted and read as numbers
+fn scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match serde_yaml::Value::deserialize(deserializer)? {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Number(n) => n.to_string(),
+        other => serde_yaml::to_string(&other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    })
+}
+
+fn c<T>(localized: Option<Localized<T>>) -> Option<T> {
+    localized.and_then(|l| l.c)
+}
+
+fn icons(
+    kind: IconKind,
+    icons: Vec<YamlIcon>,
+    media_base: Option<&str>,
+) -> impl Iterator<Item = Icon> + '_ {
+    icons.into_iter().filter_map(move |icon| {
+        let value = icon.url.or(icon.name)?;
+        Some(Icon {
+            kind,
+            value: match kind {
+                IconKind::Remote => media_url(media_base, &value),
+                _ => value,
+            },
+            width: icon.width,
+            height: icon.height,
+            scale: icon.scale,
+        })
+    })
+}
+
+fn screenshot(screenshot: YamlScreenshot, media_base: Option<&str>) -> Option<Screenshot> {
+    let thumbnail = screenshot
+        .thumbnails
+        .iter()
+        .min_by_key(|t| t.width)
+        .map(|t| media_url(media_base, &t.url));
+    // Catalogs without a source image still list thumbnails, the first being the largest
+    let url = screenshot
+        .source_image
+        .as_ref()
+        .or(screenshot.thumbnails.first())
+        .map(|i| i.url.clone())?;
+
+    let image = |image: YamlImage, thumbnail: bool| ScreenshotImage {
+        url: media_url(media_base, &image.url),
+        width: image.width,
+        height: image.height,
+        thumbnail,
+    };
+    let images = screenshot
+        .source_image
+        .into_iter()
+        .map(|i| image(i, false))
+        .chain(screenshot.thumbnails.into_iter().map(|t| image(t, true)))
+        .collect();
+
+    Some(Screenshot {
+        default: screenshot.default,
+        caption: c(screenshot.caption),
+        url: media_url(media_base, &url),
+        thumbnail_url: thumbnail,
+        images,
+    })
+}
+
+fn release(release: YamlRelease) -> Release {
+    let date = release
+        .date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d.get(..10).unwrap_or(d), "%Y-%m-%d").ok())
+        .or_else(|| {
+            release
+                .unix_timestamp
+                .and_then(|t| DateTime::from_timestamp(t, 0))
+                .map(|t| t.date_naive())
+        });
+
+    Release {
+        version: release.version,
+        date,
+        urgency: release.urgency,
+        description: c(release.description).and_then(|d| render_markup_str(&d)),
+    }
+}
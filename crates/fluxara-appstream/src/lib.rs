@@ -1,90 +1,138 @@
+mod browse;
+mod catalog;
+mod component;
+pub mod dep11;
+mod odrs;
+mod parental;
+mod version;
+pub mod xml;
+
+pub use browse::{Collection, MAIN_CATEGORIES};
+pub use catalog::Catalog;
+pub use component::{Component, Icon, IconKind, Release, Screenshot, ScreenshotImage};
+pub use fluxara_core::{ContentLevel, ContentRating};
+pub use odrs::{OdrsClient, DESCRIPTION_MAX_CHARS, SUMMARY_MAX_CHARS};
+pub use version::{compare_versions, upstream_version};
+
 use anyhow::Result;
-use fluxara_core::{AppRating, AppReview};
+use fluxara_core::{IdentityResolver, UpdateInfo};
+use std::cmp::Ordering;
+use std::sync::OnceLock;
 
-/// ODRS (Open Desktop Ratings Service) client
-pub struct OdrsClient {
-    api_url: String,
+/// AppStream metadata manager
+///
+/// The system catalogs are parsed on first use and kept in memory.
+pub struct AppStreamManager {
+    catalog: OnceLock<Catalog>,
 }
 
-impl Default for OdrsClient {
+impl Default for AppStreamManager {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl OdrsClient {
+impl AppStreamManager {
     pub fn new() -> Self {
         Self {
-            api_url: "https://odrs.gnome.org/1.0/reviews/api".to_string(),
+            catalog: OnceLock::new(),
         }
     }
 
-    /// Get rating for an application (read-only stub)
-    pub async fn get_rating(&self, app_id: &str) -> Result<AppRating> {
-        // Stub implementation - would make HTTP request to ODRS
-        println!("Fetching rating for app: {}", app_id);
-
-        Ok(AppRating {
-            app_id: app_id.to_string(),
-            average_rating: 0.0,
-            total_reviews: 0,
-        })
-    }
-
-    /// Get reviews for an application (read-only stub)
-    pub async fn get_reviews(&self, app_id: &str) -> Result<Vec<AppReview>> {
-        // Stub implementation - would make HTTP request to ODRS
-        println!("Fetching reviews for app: {}", app_id);
-
-        Ok(vec![])
-    }
-
-    /// Submit a review (stub for future implementation)
-    pub async fn submit_review(&self, _review: &AppReview) -> Result<()> {
-        // TODO: Implement in future milestone
-        anyhow::bail!("Review submission not yet implemented")
-    }
-}
-
-/// AppStream metadata manager
-pub struct AppStreamManager;
-
-impl Default for AppStreamManager {
-    fn default() -> Self {
-        Self::new()
+    /// Serve lookups from an already loaded catalog instead of the system's
+    pub fn with_catalog(catalog: Catalog) -> Self {
+        Self {
+            catalog: OnceLock::from(catalog),
+        }
     }
-}
 
-impl AppStreamManager {
-    pub fn new() -> Self {
-        Self
+    pub fn catalog(&self) -> &Catalog {
+        self.catalog.get_or_init(Catalog::load_system)
     }
 
     /// Get developer information for an app
     pub fn get_developer_info(&self, app_id: &str) -> Result<DeveloperInfo> {
-        // Stub implementation - would parse AppStream XML
-        println!("Fetching developer info for app: {}", app_id);
+        let component = self.component(app_id)?;
+        let name = component
+            .developer
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No developer listed for {}", app_id))?;
 
         Ok(DeveloperInfo {
-            name: "Unknown".to_string(),
-            url: None,
+            name,
+            url: component.urls.get("homepage").cloned(),
             email: None,
         })
     }
 
     /// Get app metadata from AppStream
     pub fn get_app_metadata(&self, app_id: &str) -> Result<AppMetadata> {
-        // Stub implementation
+        let component = self.component(app_id)?;
+
         Ok(AppMetadata {
-            id: app_id.to_string(),
-            name: app_id.to_string(),
-            summary: None,
-            description: None,
-            icon: None,
-            categories: vec![],
-            screenshots: vec![],
+            id: component.id.clone(),
+            name: component.name.clone(),
+            summary: component.summary.clone(),
+            description: component.description.clone(),
+            icon: component.icons.first().map(|icon| icon.value.clone()),
+            categories: component.categories.clone(),
+            screenshots: component.screenshots.clone(),
         })
     }
+
+    /// Releases of a component, newest first
+    pub fn releases(&self, app_id: &str) -> &[Release] {
+        self.catalog()
+            .get(app_id)
+            .map_or(&[], |component| component.releases.as_slice())
+    }
+
+    /// Release notes of the versions an update brings, newest first
+    ///
+    /// Those are the releases after `current_version` up to and including
+    /// `new_version`. Without a current version only the new one is described,
+    /// without a new version every release after the current one is, and
+    /// without either the latest release is.
+    pub fn release_notes(&self, update: &UpdateInfo) -> Vec<Release> {
+        let catalog = self.catalog();
+        let Some(component) = catalog
+            .find_by_package(update.source, &update.package_id)
+            .or_else(|| catalog.get(&update.package_id))
+        else {
+            return vec![];
+        };
+
+        let current = upstream_version(&update.current_version);
+        let new = upstream_version(&update.new_version);
+        if current.is_empty() && new.is_empty() {
+            return component.releases.iter().take(1).cloned().collect();
+        }
+
+        component
+            .releases
+            .iter()
+            .filter(|release| {
+                let version = upstream_version(&release.version);
+                if current.is_empty() {
+                    return compare_versions(version, new) == Ordering::Equal;
+                }
+                compare_versions(version, current) == Ordering::Greater
+                    && (new.is_empty() || compare_versions(version, new) != Ordering::Greater)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Build the package-to-app mapping from component ids, pkgnames, launchables and provides
+    pub fn identity_resolver(&self) -> IdentityResolver {
+        self.catalog().identity_resolver()
+    }
+
+    fn component(&self, app_id: &str) -> Result<&Component> {
+        self.catalog()
+            .get(app_id)
+            .ok_or_else(|| anyhow::anyhow!("No AppStream metadata for {}", app_id))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -102,5 +150,5 @@ pub struct AppMetadata {
     pub description: Option<String>,
     pub icon: Option<String>,
     pub categories: Vec<String>,
-    pub screenshots: Vec<String>,
+    pub screenshots: Vec<Screenshot>,
 }
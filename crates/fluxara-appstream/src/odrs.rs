@@ -0,0 +1,499 @@
+//! Client for the Open Desktop Ratings Service
+//!
+//! Ratings and reviews are cached on disk so they stay available offline:
+//! copies younger than the configured TTL are served without a request, and
+//! older copies are still used when the service cannot be reached.
+
+use anyhow::{bail, Context, Result};
+use chrono::DateTime;
+use fluxara_core::config::OdrsConfig;
+use fluxara_core::{AppRating, AppReview, Config};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Number of reviews requested per application
+const REVIEWS_LIMIT: u32 = 50;
+
+const USER_AGENT: &str = concat!("fluxara/", env!("CARGO_PKG_VERSION"));
+
+/// ODRS (Open Desktop Ratings Service) client
+///
+/// Requests are blocking; async and UI callers should run them on a blocking
+/// thread, such as with `tokio::task::spawn_blocking` or `gio::spawn_blocking`.
+pub struct OdrsClient {
+    api_url: String,
+    cache_dir: Option<PathBuf>,
+    cache_ttl: Duration,
+    agent: ureq::Agent,
+}
+
+impl Default for OdrsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Star histogram of one application as returned by `/ratings`
+#[derive(Debug, Deserialize)]
+struct Stars {
+    #[serde(default)]
+    star1: u32,
+    #[serde(default)]
+    star2: u32,
+    #[serde(default)]
+    star3: u32,
+    #[serde(default)]
+    star4: u32,
+    #[serde(default)]
+    star5: u32,
+    #[serde(default)]
+    total: u32,
+}
+
+/// Review as returned by `/fetch`
+///
+/// Apps without reviews get a single placeholder entry that only carries the
+/// `user_skey` needed to submit one.
+#[derive(Debug, Deserialize)]
+struct Review {
+    #[serde(default)]
+    review_id: Option<u64>,
+    #[serde(default)]
+    app_id: Option<String>,
+    /// Rating from 0 to 100
+    #[serde(default)]
+    rating: u32,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    user_display: Option<String>,
+    #[serde(default)]
+    user_hash: Option<String>,
+    #[serde(default)]
+    user_skey: Option<String>,
+    #[serde(default)]
+    date_created: f64,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// Reply of the write endpoints
+#[derive(Debug, Deserialize)]
+struct Status {
+    #[serde(default)]
+    success: bool,
+    #[serde(default)]
+    msg: Option<String>,
+}
+
+/// Longest review summary the service accepts
+pub const SUMMARY_MAX_CHARS: usize = 70;
+
+/// Longest review description the service accepts
+pub const DESCRIPTION_MAX_CHARS: usize = 3000;
+
+impl OdrsClient {
+    /// Client for the public service with the default cache settings
+    pub fn new() -> Self {
+        Self::with_settings(&OdrsConfig::default())
+    }
+
+    /// Client using the `[odrs]` section of the configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self::with_settings(&config.odrs)
+    }
+
+    fn with_settings(settings: &OdrsConfig) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(15)))
+            .user_agent(USER_AGENT)
+            .http_status_as_error(false)
+            .build()
+            .into();
+
+        Self {
+            api_url: settings.api_url.trim_end_matches('/').to_string(),
+            cache_dir: Config::cache_dir().ok().map(|dir| dir.join("odrs")),
+            cache_ttl: Duration::from_secs(settings.cache_ttl_hours * 3600),
+            agent,
+        }
+    }
+
+    /// Use another service endpoint
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Cache responses in `dir` for `ttl`; `None` disables the disk cache
+    pub fn with_cache(mut self, dir: Option<PathBuf>, ttl: Duration) -> Self {
+        self.cache_dir = dir;
+        self.cache_ttl = ttl;
+        self
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Get rating for an application
+    ///
+    /// Applications nobody rated yet get an empty rating.
+    pub fn get_rating(&self, app_id: &str) -> Result<AppRating> {
+        let mut ratings = self.get_ratings()?;
+        Ok(ratings.remove(app_id).unwrap_or_else(|| AppRating {
+            app_id: app_id.to_string(),
+            average_rating: 0.0,
+            total_reviews: 0,
+        }))
+    }
+
+    /// Get ratings of every application, keyed by component id
+    pub fn get_ratings(&self) -> Result<HashMap<String, AppRating>> {
+        let body = self.cached("ratings.json", || {
+            let url = format!("{}/ratings", self.api_url);
+            let mut response = self.agent.get(&url).call()?;
+            if !response.status().is_success() {
+                bail!("ODRS ratings failed: {}", response.status());
+            }
+            Ok(response.body_mut().read_to_string()?)
+        })?;
+        let stars: HashMap<String, Stars> =
+            serde_json::from_str(&body).context("Invalid ODRS ratings response")?;
+
+        Ok(stars
+            .into_iter()
+            .map(|(app_id, stars)| {
+                let rating = rating_from_stars(&app_id, &stars);
+                (app_id, rating)
+            })
+            .collect())
+    }
+
+    /// Get reviews for an application, best matches for `version` first
+    pub fn get_reviews(&self, app_id: &str, version: Option<&str>) -> Result<Vec<AppReview>> {
+        let user_hash = user_hash();
+        let reviews = self.fetch(app_id, version)?;
+
+        Ok(reviews
+            .into_iter()
+            .filter_map(|review| {
+                Some(AppReview {
+                    review_id: Some(review.review_id?),
+                    app_id: review.app_id.unwrap_or_else(|| app_id.to_string()),
+                    rating: (review.rating + 10) / 20,
+                    summary: review.summary,
+                    description: review.description,
+                    reviewer: review
+                        .user_display
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or_else(|| "Anonymous".to_string()),
+                    date: DateTime::from_timestamp(review.date_created as i64, 0)
+                        .map(|date| date.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default(),
+                    version: review.version,
+                    own: review.user_hash.as_deref() == Some(user_hash.as_str()),
+                })
+            })
+            .collect())
+    }
+
+    /// Submit a review of `review.app_id` as the current user
+    ///
+    /// The rating is in stars from 1 to 5; an empty reviewer is posted as
+    /// anonymous.
+    pub fn submit_review(&self, review: &AppReview) -> Result<()> {
+        if !(1..=5).contains(&review.rating) {
+            bail!("Rating must be between 1 and 5 stars");
+        }
+        let summary = review.summary.trim();
+        if summary.is_empty() {
+            bail!("Review summary is empty");
+        }
+        if summary.chars().count() > SUMMARY_MAX_CHARS {
+            bail!(
+                "Review summary is longer than {} characters",
+                SUMMARY_MAX_CHARS
+            );
+        }
+        let description = review.description.trim();
+        if description.is_empty() {
+            bail!("Review description is empty");
+        }
+        if description.chars().count() > DESCRIPTION_MAX_CHARS {
+            bail!(
+                "Review description is longer than {} characters",
+                DESCRIPTION_MAX_CHARS
+            );
+        }
+
+        let version = review.version.as_deref();
+        let request = serde_json::json!({
+            "user_hash": user_hash(),
+            "user_skey": self.user_skey(&review.app_id, version)?,
+            "app_id": review.app_id,
+            "locale": locale(),
+            "distro": distro(),
+            "version": version.unwrap_or("unknown"),
+            "summary": summary,
+            "description": description,
+            "user_display": review.reviewer.trim(),
+            "rating": review.rating * 20,
+        });
+        self.post_action("submit", &request)?;
+        self.forget_reviews(&review.app_id);
+        Ok(())
+    }
+
+    /// Mark a review as helpful
+    pub fn upvote(&self, app_id: &str, review_id: u64) -> Result<()> {
+        self.review_action("upvote", app_id, review_id)
+    }
+
+    /// Mark a review as unhelpful
+    pub fn downvote(&self, app_id: &str, review_id: u64) -> Result<()> {
+        self.review_action("downvote", app_id, review_id)
+    }
+
+    /// Report a review as abusive for moderation
+    pub fn report(&self, app_id: &str, review_id: u64) -> Result<()> {
+        self.review_action("report", app_id, review_id)
+    }
+
+    /// Remove a review written by the current user
+    pub fn remove_review(&self, app_id: &str, review_id: u64) -> Result<()> {
+        self.review_action("remove", app_id, review_id)?;
+        self.forget_reviews(app_id);
+        Ok(())
+    }
+
+    fn review_action(&self, action: &str, app_id: &str, review_id: u64) -> Result<()> {
+        let request = serde_json::json!({
+            "user_hash": user_hash(),
+            "user_skey": self.user_skey(app_id, None)?,
+            "app_id": app_id,
+            "review_id": review_id,
+        });
+        self.post_action(action, &request)
+    }
+
+    /// Reviews of `app_id`, including the placeholder of unreviewed apps
+    fn fetch(&self, app_id: &str, version: Option<&str>) -> Result<Vec<Review>> {
+        let locale = locale();
+        let body = self.cached(&reviews_cache_name(app_id, version), || {
+            let request = serde_json::json!({
+                "user_hash": user_hash(),
+                "app_id": app_id,
+                "locale": locale,
+                "distro": distro(),
+                "version": version.unwrap_or("unknown"),
+                "limit": REVIEWS_LIMIT,
+            });
+            self.post("fetch", &request)
+        })?;
+        serde_json::from_str(&body).context("Invalid ODRS reviews response")
+    }
+
+    /// Key the service derives from the user hash and app, proving the
+    /// writes come from the same user
+    fn user_skey(&self, app_id: &str, version: Option<&str>) -> Result<String> {
+        self.fetch(app_id, version)?
+            .into_iter()
+            .find_map(|review| review.user_skey)
+            .with_context(|| format!("ODRS did not return a user key for {}", app_id))
+    }
+
+    /// Drop cached reviews of `app_id`, for every version, so a change
+    /// shows up on next fetch
+    fn forget_reviews(&self, app_id: &str) {
+        if let Some(dir) = &self.cache_dir {
+            let _ = fs::remove_dir_all(dir.join(reviews_cache_dir(app_id)));
+        }
+    }
+
+    /// Post to a write endpoint and check the service accepted it
+    fn post_action(&self, endpoint: &str, request: &serde_json::Value) -> Result<()> {
+        let body = self.post(endpoint, request)?;
+        let status: Status = serde_json::from_str(&body).context("Invalid ODRS response")?;
+        if !status.success {
+            bail!(
+                "ODRS rejected {}: {}",
+                endpoint,
+                status.msg.as_deref().unwrap_or("no reason given")
+            );
+        }
+        Ok(())
+    }
+
+    fn post(&self, endpoint: &str, request: &serde_json::Value) -> Result<String> {
+        let url = format!("{}/{}", self.api_url, endpoint);
+        let mut response = self
+            .agent
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .send(request.to_string())?;
+        let body = response.body_mut().read_to_string()?;
+        if !response.status().is_success() {
+            // Errors carry the reason in the same status object as successes
+            let reason = serde_json::from_str::<Status>(&body)
+                .ok()
+                .and_then(|status| status.msg)
+                .unwrap_or_else(|| response.status().to_string());
+            bail!("ODRS {} failed: {}", endpoint, reason);
+        }
+        Ok(body)
+    }
+
+    /// Response body for `name`, from the disk cache while fresh
+    ///
+    /// A stale copy is returned when `fetch` fails.
+    fn cached(&self, name: &str, fetch: impl FnOnce() -> Result<String>) -> Result<String> {
+        let path = self.cache_dir.as_ref().map(|dir| dir.join(name));
+
+        if let Some(path) = &path {
+            if is_fresh(path, self.cache_ttl) {
+                if let Ok(body) = fs::read_to_string(path) {
+                    return Ok(body);
+                }
+            }
+        }
+
+        match fetch() {
+            Ok(body) => {
+                if let Some(path) = &path {
+                    if let Err(e) = write_cache(path, &body) {
+                        tracing::debug!("Failed to cache {}: {}", path.display(), e);
+                    }
+                }
+                Ok(body)
+            }
+            Err(e) => {
+                let stale = path.as_ref().and_then(|path| fs::read_to_string(path).ok());
+                match stale {
+                    Some(body) => {
+                        tracing::warn!("ODRS unreachable, using cached {}: {}", name, e);
+                        Ok(body)
+                    }
+                    None => Err(e.context(format!("Failed to reach ODRS at {}", self.api_url))),
+                }
+            }
+        }
+    }
+}
+
+fn rating_from_stars(app_id: &str, stars: &Stars) -> AppRating {
+    let counts = [
+        stars.star1,
+        stars.star2,
+        stars.star3,
+        stars.star4,
+        stars.star5,
+    ];
+    let rated: u32 = counts.iter().sum();
+    let average_rating = if rated == 0 {
+        0.0
+    } else {
+        let weighted: u32 = counts
+            .iter()
+            .zip(1..)
+            .map(|(count, star)| count * star)
+            .sum();
+        weighted as f32 / rated as f32
+    };
+
+    AppRating {
+        app_id: app_id.to_string(),
+        average_rating,
+        total_reviews: stars.total.max(rated),
+    }
+}
+
+/// Cache directory of the reviews of `app_id`
+fn reviews_cache_dir(app_id: &str) -> String {
+    format!("reviews/{}", file_name(app_id))
+}
+
+/// Cache file of the reviews of `app_id` for `version` in the current
+/// locale; the service orders reviews by how well they match the version
+fn reviews_cache_name(app_id: &str, version: Option<&str>) -> String {
+    format!(
+        "{}/{}-{}.json",
+        reviews_cache_dir(app_id),
+        file_name(version.unwrap_or("unknown")),
+        file_name(&locale())
+    )
+}
+
+fn is_fresh(path: &Path, ttl: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < ttl)
+}
+
+fn write_cache(path: &Path, body: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, body)
+}
+
+/// Cache file name component with path separators removed
+fn file_name(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Anonymous, stable identifier of this user on this machine
+pub(crate) fn user_hash() -> String {
+    let machine_id = fs::read_to_string("/etc/machine-id").unwrap_or_default();
+    let user = std::env::var("USER").unwrap_or_default();
+    let digest = Sha1::digest(format!("fluxara[{}:{}]", user, machine_id.trim()));
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Message locale such as `en_US`, without encoding or modifier
+pub(crate) fn locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .split(['.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .filter(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// Distribution name from os-release
+pub(crate) fn distro() -> String {
+    fs::read_to_string("/etc/os-release")
+        .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                line.strip_prefix("NAME=")
+                    .map(|name| name.trim_matches('"').to_string())
+            })
+        })
+        .unwrap_or_else(|| "Unknown".to_string())
+}
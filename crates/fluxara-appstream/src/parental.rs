@@ -0,0 +1,47 @@
+//! Parental controls: checking packages against the OARS content rating policy
+
+use crate::AppStreamManager;
+use fluxara_core::config::ParentalConfig;
+use fluxara_core::{ContentRating, Package, PackageSource};
+
+impl AppStreamManager {
+    /// Content rating of a package, the strictest of the components it ships
+    pub fn content_rating(&self, source: PackageSource, package_id: &str) -> Option<ContentRating> {
+        let catalog = self.catalog();
+        let mut ratings = catalog
+            .find_all_by_package(source, package_id)
+            .chain(catalog.get_all(package_id))
+            .filter_map(|component| component.content_rating.as_ref());
+
+        let mut rating = ratings.next()?.clone();
+        for other in ratings {
+            for (attribute, level) in &other.attributes {
+                let strictest = rating.attributes.entry(attribute.clone()).or_default();
+                *strictest = (*strictest).max(*level);
+            }
+        }
+        Some(rating)
+    }
+
+    /// Reasons `policy` forbids a package to the users it applies to; empty
+    /// when allowed
+    pub fn content_violations(
+        &self,
+        policy: &ParentalConfig,
+        source: PackageSource,
+        package_id: &str,
+    ) -> Vec<String> {
+        policy.violations(self.content_rating(source, package_id).as_ref())
+    }
+
+    /// Drop packages `policy` forbids the current user
+    pub fn retain_allowed(&self, policy: &ParentalConfig, packages: &mut Vec<Package>) {
+        if !policy.applies() {
+            return;
+        }
+        packages.retain(|package| {
+            self.content_violations(policy, package.source, &package.id)
+                .is_empty()
+        });
+    }
+}
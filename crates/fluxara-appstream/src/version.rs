@@ -0,0 +1,68 @@
+//! Version comparison for matching package versions against AppStream releases
+
+use std::cmp::Ordering;
+
+/// The upstream part of a package version, as AppStream releases name it
+///
+/// Drops the Debian epoch (`1:`), the distribution revision (`-2`,
+/// `-1ubuntu3`), repack suffixes (`+dfsg`, `~ds1`) and a leading `v`.
+pub fn upstream_version(version: &str) -> &str {
+    let version = version.trim();
+    let version = match version.split_once(':') {
+        Some((epoch, rest)) if epoch.chars().all(|c| c.is_ascii_digit()) => rest,
+        _ => version,
+    };
+    let version = match version.rsplit_once('-') {
+        Some((upstream, revision))
+            if !upstream.is_empty() && revision.starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            upstream
+        }
+        _ => version,
+    };
+    let version = version.split(['+', '~']).next().unwrap_or(version);
+    version.strip_prefix('v').unwrap_or(version)
+}
+
+/// Compare two versions segment by segment, numbers numerically and words alphabetically
+///
+/// Separators are ignored, a number sorts after a word, and a version with
+/// more segments sorts after its prefix (`1.2` < `1.2.1`).
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a = segments(a);
+    let mut b = segments(b);
+
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Greater,
+                (Err(_), Ok(_)) => Ordering::Less,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Maximal runs of digits or of letters
+fn segments(version: &str) -> impl Iterator<Item = &str> {
+    let mut rest = version;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+        let first = rest.chars().next()?;
+        let end = rest
+            .find(|c: char| {
+                !c.is_ascii_alphanumeric() || c.is_ascii_digit() != first.is_ascii_digit()
+            })
+            .unwrap_or(rest.len());
+        let (segment, tail) = rest.split_at(end);
+        rest = tail;
+        Some(segment)
+    })
+}
@@ -0,0 +1,293 @@
+//! AppStream XML: collection catalogs (`<components>`) and metainfo files (`<component>`)
+
+use crate::component::{Component, Icon, IconKind, Release, Screenshot, ScreenshotImage};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate};
+use fluxara_core::{ContentRating, PackageSource};
+use roxmltree::{Document, Node};
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Parse a collection catalog or a single metainfo file
+pub fn parse(content: &str) -> Result<Vec<Component>> {
+    let doc = Document::parse(content).context("Invalid AppStream XML")?;
+    let root = doc.root_element();
+
+    match root.tag_name().name() {
+        "components" => {
+            let origin = root.attribute("origin");
+            let media_base = root.attribute("media_baseurl");
+            Ok(root
+                .children()
+                .filter(|n| n.has_tag_name("component"))
+                .filter_map(|n| parse_component(n, origin, media_base))
+                .collect())
+        }
+        "component" => Ok(parse_component(root, None, None).into_iter().collect()),
+        other => anyhow::bail!("Unexpected AppStream root element <{}>", other),
+    }
+}
+
+fn parse_component(
+    node: Node,
+    origin: Option<&str>,
+    media_base: Option<&str>,
+) -> Option<Component> {
+    let id = child_text(node, "id")?;
+    let mut component = Component {
+        name: localized(node, "name").unwrap_or_else(|| id.clone()),
+        id,
+        kind: node
+            .attribute("type")
+            .unwrap_or("desktop-application")
+            .to_string(),
+        pkgname: child_text(node, "pkgname"),
+        origin: origin.map(str::to_string),
+        summary: localized(node, "summary"),
+        description: unlocalized(node, "description").and_then(render_markup),
+        developer: child(node, "developer")
+            .and_then(|d| localized(d, "name"))
+            .or_else(|| localized(node, "developer_name")),
+        project_license: child_text(node, "project_license"),
+        ..Default::default()
+    };
+
+    // Flatpak catalogs name the ref to install instead of a package
+    if let Some(bundle) = children(node, "bundle").find(|b| b.attribute("type") == Some("flatpak"))
+    {
+        component.source = Some(PackageSource::Flatpak);
+        component.pkgname = text(bundle)
+            .and_then(|r| r.split('/').nth(1).map(str::to_string))
+            .or(component.pkgname);
+    }
+
+    if let Some(categories) = child(node, "categories") {
+        component.categories = children(categories, "category").filter_map(text).collect();
+    }
+    if let Some(keywords) = unlocalized(node, "keywords") {
+        component.keywords = children(keywords, "keyword")
+            .filter(|k| k.attribute((XML_NS, "lang")).is_none())
+            .filter_map(text)
+            .collect();
+    }
+
+    component.icons = children(node, "icon")
+        .filter_map(|icon| {
+            let kind = match icon.attribute("type")? {
+                "stock" => IconKind::Stock,
+                "cached" => IconKind::Cached,
+                "local" => IconKind::Local,
+                "remote" => IconKind::Remote,
+                _ => return None,
+            };
+            let value = text(icon)?;
+            Some(Icon {
+                kind,
+                value: if kind == IconKind::Remote {
+                    media_url(media_base, &value)
+                } else {
+                    value
+                },
+                width: icon.attribute("width").and_then(|w| w.parse().ok()),
+                height: icon.attribute("height").and_then(|h| h.parse().ok()),
+                scale: icon.attribute("scale").and_then(|s| s.parse().ok()),
+            })
+        })
+        .collect();
+
+    if let Some(screenshots) = child(node, "screenshots") {
+        component.screenshots = children(screenshots, "screenshot")
+            .filter_map(|s| parse_screenshot(s, media_base))
+            .collect();
+    }
+
+    if let Some(releases) = child(node, "releases") {
+        component.releases = children(releases, "release")
+            .filter_map(parse_release)
+            .collect();
+    }
+
+    component.content_rating = child(node, "content_rating").map(|rating| ContentRating {
+        kind: rating.attribute("type").unwrap_or("oars-1.0").to_string(),
+        attributes: children(rating, "content_attribute")
+            .filter_map(|a| Some((a.attribute("id")?.to_string(), text(a)?.parse().ok()?)))
+            .collect(),
+    });
+
+    component.urls = children(node, "url")
+        .filter_map(|u| Some((u.attribute("type")?.to_string(), text(u)?)))
+        .collect();
+    component.launchables = children(node, "launchable")
+        .filter(|l| l.attribute("type") == Some("desktop-id"))
+        .filter_map(text)
+        .collect();
+    if let Some(provides) = child(node, "provides") {
+        component.provides = children(provides, "id").filter_map(text).collect();
+    }
+
+    Some(component)
+}
+
+fn parse_screenshot(node: Node, media_base: Option<&str>) -> Option<Screenshot> {
+    let images: Vec<Node> = children(node, "image").collect();
+    let url = images
+        .iter()
+        .find(|i| i.attribute("type") != Some("thumbnail"))
+        .or(images.first())
+        .and_then(|i| text(*i))?;
+    // Thumbnails are listed largest first; the smallest loads fastest in a gallery strip
+    let thumbnail_url = images
+        .iter()
+        .filter(|i| i.attribute("type") == Some("thumbnail"))
+        .min_by_key(|i| i.attribute("width").and_then(|w| w.parse::<u32>().ok()))
+        .and_then(|i| text(*i));
+
+    let images = images
+        .iter()
+        .filter_map(|i| {
+            Some(ScreenshotImage {
+                url: media_url(media_base, &text(*i)?),
+                width: i.attribute("width").and_then(|w| w.parse().ok()),
+                height: i.attribute("height").and_then(|h| h.parse().ok()),
+                thumbnail: i.attribute("type") == Some("thumbnail"),
+            })
+        })
+        .collect();
+
+    Some(Screenshot {
+        default: node.attribute("type") == Some("default"),
+        caption: localized(node, "caption"),
+        url: media_url(media_base, &url),
+        thumbnail_url: thumbnail_url.map(|t| media_url(media_base, &t)),
+        images,
+    })
+}
+
+fn parse_release(node: Node) -> Option<Release> {
+    let date = node
+        .attribute("date")
+        .and_then(|d| NaiveDate::parse_from_str(d.get(..10).unwrap_or(d), "%Y-%m-%d").ok())
+        .or_else(|| {
+            node.attribute("timestamp")
+                .and_then(|t| t.parse().ok())
+                .and_then(|t| DateTime::from_timestamp(t, 0))
+                .map(|t| t.date_naive())
+        });
+
+    Some(Release {
+        version: node.attribute("version")?.to_string(),
+        date,
+        urgency: node.attribute("urgency").map(str::to_string),
+        description: unlocalized(node, "description").and_then(render_markup),
+    })
+}
+
+/// Render AppStream description markup (`<p>`, `<ul>`, `<ol>`, `<li>`) as plain text
+pub(crate) fn render_markup(node: Node) -> Option<String> {
+    let mut blocks = Vec::new();
+
+    for element in node.children().filter(|n| n.is_element()) {
+        // Old catalogs translate per paragraph instead of per description
+        if element.attribute((XML_NS, "lang")).is_some() {
+            continue;
+        }
+        match element.tag_name().name() {
+            "ul" | "ol" => {
+                let ordered = element.has_tag_name("ol");
+                let items: Vec<String> = children(element, "li")
+                    .filter(|li| li.attribute((XML_NS, "lang")).is_none())
+                    .enumerate()
+                    .map(|(i, li)| match ordered {
+                        true => format!("{}. {}", i + 1, collapse(li)),
+                        false => format!("• {}", collapse(li)),
+                    })
+                    .collect();
+                blocks.push(items.join("\n"));
+            }
+            _ => blocks.push(collapse(element)),
+        }
+    }
+
+    blocks.retain(|b| !b.is_empty());
+    (!blocks.is_empty()).then(|| blocks.join("\n\n"))
+}
+
+/// Render description markup held in a string, as DEP-11 catalogs store it
+pub(crate) fn render_markup_str(markup: &str) -> Option<String> {
+    let wrapped = format!("<description>{}</description>", markup);
+    match Document::parse(&wrapped) {
+        Ok(doc) => render_markup(doc.root_element()),
+        // HTML entities such as &nbsp; are not XML; fall back to dropping the tags
+        Err(_) => {
+            let mut text = String::new();
+            let mut in_tag = false;
+            for c in markup.chars() {
+                match c {
+                    '<' => in_tag = true,
+                    '>' => {
+                        in_tag = false;
+                        text.push(' ');
+                    }
+                    _ if !in_tag => text.push(c),
+                    _ => {}
+                }
+            }
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!text.is_empty()).then_some(text)
+        }
+    }
+}
+
+/// Resolve a catalog-relative media path against the catalog's media base URL
+pub(crate) fn media_url(base: Option<&str>, path: &str) -> String {
+    match base {
+        Some(base) if !path.contains("://") => {
+            format!(
+                "{}/{}",
+                base.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            )
+        }
+        _ => path.to_string(),
+    }
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    tag: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |n| n.has_tag_name(tag))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &'static str) -> Option<Node<'a, 'input>> {
+    children(node, tag).next()
+}
+
+/// The untranslated element among its `xml:lang` variants
+fn unlocalized<'a, 'input>(node: Node<'a, 'input>, tag: &'static str) -> Option<Node<'a, 'input>> {
+    children(node, tag).find(|n| n.attribute((XML_NS, "lang")).is_none())
+}
+
+fn localized(node: Node, tag: &'static str) -> Option<String> {
+    unlocalized(node, tag).and_then(text)
+}
+
+fn child_text(node: Node, tag: &'static str) -> Option<String> {
+    child(node, tag).and_then(text)
+}
+
+fn text(node: Node) -> Option<String> {
+    let text = collapse(node);
+    (!text.is_empty()).then_some(text)
+}
+
+/// All text below a node with whitespace runs collapsed to single spaces
+fn collapse(node: Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,121 @@
+use fluxara_appstream::{AppStreamManager, Catalog, Collection, Component};
+use fluxara_core::{AppRating, PackageSource};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+fn manager() -> AppStreamManager {
+    let mut catalog = Catalog::new();
+    catalog.load_file(&data("catalog.yml"), None).unwrap();
+    catalog
+        .load_file(&data("catalog.xml"), Some(PackageSource::Flatpak))
+        .unwrap();
+    catalog
+        .load_file(&data("org.example.Notes.metainfo.xml"), None)
+        .unwrap();
+    AppStreamManager::with_catalog(catalog)
+}
+
+fn ids<'a>(components: impl IntoIterator<Item = &'a Component>) -> Vec<&'a str> {
+    components.into_iter().map(|c| c.app_id()).collect()
+}
+
+fn rating(app_id: &str, average_rating: f32, total_reviews: u32) -> (String, AppRating) {
+    (
+        app_id.to_string(),
+        AppRating {
+            app_id: app_id.to_string(),
+            average_rating,
+            total_reviews,
+        },
+    )
+}
+
+#[test]
+fn lists_each_app_once() {
+    let manager = manager();
+    // Console applications are left out, and the calculator is in two catalogs
+    assert_eq!(
+        ids(manager.apps()),
+        [
+            "org.gnome.Calculator",
+            "org.mozilla.firefox",
+            "org.example.Notes"
+        ]
+    );
+}
+
+#[test]
+fn browses_categories() {
+    let manager = manager();
+    assert_eq!(manager.category_counts(), [("Network", 1), ("Utility", 1)]);
+    assert_eq!(ids(manager.browse("Network")), ["org.mozilla.firefox"]);
+    assert_eq!(ids(manager.browse("WebBrowser")), ["org.mozilla.firefox"]);
+    assert!(manager.browse("Game").is_empty());
+}
+
+#[test]
+fn orders_by_release_dates() {
+    let manager = manager();
+    assert_eq!(
+        ids(manager.recently_updated(10)),
+        [
+            "org.mozilla.firefox",
+            "org.example.Notes",
+            "org.gnome.Calculator"
+        ]
+    );
+    assert_eq!(
+        ids(manager.new_apps(2)),
+        ["org.mozilla.firefox", "org.example.Notes"]
+    );
+}
+
+#[test]
+fn orders_by_popularity() {
+    let manager = manager();
+    let ratings: HashMap<String, AppRating> = [
+        rating("org.gnome.Calculator.desktop", 4.0, 120),
+        rating("org.mozilla.firefox", 4.5, 120),
+        rating("org.example.Notes", 5.0, 0),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        ids(manager.popular(&ratings, 10)),
+        ["org.mozilla.firefox", "org.gnome.Calculator"]
+    );
+    assert!(manager.popular(&HashMap::new(), 10).is_empty());
+}
+
+#[test]
+fn loads_collections() {
+    let manager = manager();
+
+    let collections = Collection::load(&data("collections.toml")).unwrap();
+    assert_eq!(collections.len(), 2);
+    assert_eq!(collections[0].title, "Essentials");
+    assert_eq!(
+        collections[0].description.as_deref(),
+        Some("Apps every desktop needs")
+    );
+    // Apps the catalog does not know are skipped
+    assert_eq!(
+        ids(manager.collection_apps(&collections[0])),
+        ["org.mozilla.firefox", "org.gnome.Calculator"]
+    );
+    assert_eq!(collections[1].description, None);
+
+    let collections = Collection::load(&data("collections.json")).unwrap();
+    assert_eq!(collections.len(), 1);
+    assert_eq!(
+        ids(manager.collection_apps(&collections[0])),
+        ["org.gnome.Calculator", "org.mozilla.firefox"]
+    );
+}
@@ -0,0 +1,287 @@
+use fluxara_appstream::{dep11, xml, AppStreamManager, Catalog, IconKind};
+use fluxara_core::{ContentLevel, Package, PackageSource};
+use std::path::{Path, PathBuf};
+
+fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+fn read(name: &str) -> String {
+    std::fs::read_to_string(data(name)).unwrap()
+}
+
+fn package(id: &str, source: PackageSource) -> Package {
+    Package {
+        id: id.to_string(),
+        name: id.to_string(),
+        version: None,
+        description: None,
+        icon_url: None,
+        source,
+        installed: false,
+    }
+}
+
+#[test]
+fn parses_xml_collection() {
+    let components = xml::parse(&read("catalog.xml")).unwrap();
+    assert_eq!(components.len(), 2, "components without an id are skipped");
+
+    let firefox = &components[0];
+    assert_eq!(firefox.id, "org.mozilla.firefox");
+    assert_eq!(firefox.kind, "desktop-application");
+    assert_eq!(firefox.name, "Firefox");
+    assert_eq!(
+        firefox.summary.as_deref(),
+        Some("Fast, Private & Safe Web Browser")
+    );
+    assert_eq!(
+        firefox.description.as_deref(),
+        Some("Browse the web with speed and privacy.\n\n• Tracking protection\n• Sync across devices")
+    );
+    assert_eq!(firefox.developer.as_deref(), Some("Mozilla"));
+    assert_eq!(firefox.project_license.as_deref(), Some("MPL-2.0"));
+    assert_eq!(firefox.origin.as_deref(), Some("flathub"));
+    assert_eq!(firefox.source, Some(PackageSource::Flatpak));
+    assert_eq!(firefox.pkgname.as_deref(), Some("org.mozilla.firefox"));
+    assert_eq!(firefox.categories, ["Network", "WebBrowser"]);
+    assert_eq!(firefox.keywords, ["web", "browser"]);
+
+    assert_eq!(firefox.icons.len(), 3);
+    assert_eq!(firefox.icons[0].kind, IconKind::Stock);
+    assert_eq!(firefox.icons[1].width, Some(64));
+    assert_eq!(
+        firefox.icons[2].value,
+        "https://dl.flathub.org/media/org/mozilla/firefox/icons/128x128/org.mozilla.firefox.png"
+    );
+
+    assert_eq!(firefox.screenshots.len(), 2);
+    let main = &firefox.screenshots[0];
+    assert!(main.default);
+    assert_eq!(main.caption.as_deref(), Some("Browsing the web"));
+    assert_eq!(main.url, "https://example.org/firefox/main.png");
+    assert_eq!(
+        main.thumbnail_url.as_deref(),
+        Some("https://dl.flathub.org/media/org/mozilla/firefox/main_224x126.png")
+    );
+    let widths: Vec<_> = main.images.iter().map(|image| image.width).collect();
+    assert_eq!(widths, [Some(1920), Some(624), Some(224)]);
+    assert!(!main.images[0].thumbnail);
+    assert!(main.images[1].thumbnail);
+    assert!(!firefox.screenshots[1].default);
+
+    assert_eq!(firefox.releases.len(), 2);
+    assert_eq!(firefox.releases[0].version, "130.0");
+    assert_eq!(firefox.releases[0].date.unwrap().to_string(), "2024-09-03");
+    assert_eq!(firefox.releases[0].urgency.as_deref(), Some("high"));
+    assert_eq!(
+        firefox.releases[0].description.as_deref(),
+        Some("Security fixes.")
+    );
+    assert_eq!(firefox.releases[1].date.unwrap().to_string(), "2024-08-13");
+
+    let rating = firefox.content_rating.as_ref().unwrap();
+    assert_eq!(rating.kind, "oars-1.1");
+    assert_eq!(rating.attributes["social-chat"], ContentLevel::Intense);
+    assert_eq!(rating.attributes["money-purchasing"], ContentLevel::Mild);
+
+    assert_eq!(firefox.urls["homepage"], "https://www.mozilla.org/firefox/");
+    assert_eq!(firefox.launchables, ["org.mozilla.firefox.desktop"]);
+    assert_eq!(firefox.provides, ["firefox.desktop"]);
+
+    let calculator = &components[1];
+    assert_eq!(calculator.pkgname.as_deref(), Some("gnome-calculator"));
+    assert_eq!(calculator.developer.as_deref(), Some("The GNOME Project"));
+    assert_eq!(calculator.app_id(), "org.gnome.Calculator");
+    assert_eq!(calculator.source, None);
+}
+
+#[test]
+fn parses_dep11_catalog() {
+    let components = dep11::parse(&read("catalog.yml")).unwrap();
+    assert_eq!(components.len(), 2, "documents without an id are skipped");
+
+    let calculator = &components[0];
+    assert_eq!(calculator.id, "org.gnome.Calculator.desktop");
+    assert_eq!(calculator.pkgname.as_deref(), Some("gnome-calculator"));
+    assert_eq!(calculator.origin.as_deref(), Some("debian-bookworm-main"));
+    assert_eq!(calculator.name, "Calculator");
+    assert_eq!(
+        calculator.description.as_deref(),
+        Some("Calculator is an application that solves mathematical equations.\n\n1. Basic mode\n2. Advanced mode")
+    );
+    assert_eq!(calculator.developer.as_deref(), Some("The GNOME Project"));
+    assert_eq!(calculator.project_license.as_deref(), Some("GPL-3.0+"));
+    assert_eq!(calculator.categories, ["Utility", "Calculator"]);
+    assert_eq!(calculator.keywords, ["calculation", "arithmetic"]);
+    assert_eq!(
+        calculator.urls["homepage"],
+        "https://wiki.gnome.org/Apps/Calculator"
+    );
+
+    assert_eq!(calculator.icons.len(), 2);
+    assert_eq!(calculator.icons[0].kind, IconKind::Cached);
+    assert_eq!(
+        calculator.icons[0].value,
+        "gnome-calculator_org.gnome.Calculator.png"
+    );
+    assert_eq!(calculator.icons[1].kind, IconKind::Remote);
+    assert!(calculator.icons[1]
+        .value
+        .starts_with("https://appstream.debian.org/media/bookworm/org/gnome/"));
+
+    let screenshot = &calculator.screenshots[0];
+    assert!(screenshot.default);
+    assert_eq!(screenshot.caption.as_deref(), Some("Basic mode"));
+    assert!(screenshot.url.ends_with("/screenshots/image-1_orig.png"));
+    assert!(screenshot
+        .thumbnail_url
+        .as_deref()
+        .unwrap()
+        .ends_with("image-1_224x126.png"));
+    assert_eq!(screenshot.images.len(), 3);
+    assert!(screenshot
+        .images
+        .iter()
+        .any(|image| !image.thumbnail && image.width == Some(1200)));
+
+    assert_eq!(calculator.releases.len(), 2);
+    assert_eq!(calculator.releases[0].version, "43.0.1");
+    assert_eq!(
+        calculator.releases[0].date.unwrap().to_string(),
+        "2022-10-23"
+    );
+    assert_eq!(
+        calculator.releases[0].description.as_deref(),
+        Some("Fixed appdata versioning")
+    );
+    assert_eq!(calculator.releases[1].version, "43.0");
+
+    assert_eq!(calculator.launchables, ["org.gnome.Calculator.desktop"]);
+    assert_eq!(calculator.provides, ["gcalctool.desktop"]);
+    let rating = calculator.content_rating.as_ref().unwrap();
+    assert_eq!(rating.kind, "oars-1.1");
+    assert!(rating.attributes.is_empty());
+
+    let cli = &components[1];
+    assert_eq!(cli.kind, "console-application");
+    assert_eq!(
+        cli.content_rating.as_ref().unwrap().attributes["violence-cartoon"],
+        ContentLevel::Mild
+    );
+}
+
+#[test]
+fn parses_metainfo_file() {
+    let components = xml::parse(&read("org.example.Notes.metainfo.xml")).unwrap();
+    assert_eq!(components.len(), 1);
+
+    let notes = &components[0];
+    assert_eq!(notes.id, "org.example.Notes");
+    assert_eq!(notes.pkgname, None);
+    assert_eq!(
+        notes.description.as_deref(),
+        Some("A small note taking app.")
+    );
+    assert_eq!(notes.releases[0].date.unwrap().to_string(), "2024-01-15");
+}
+
+#[test]
+fn rejects_unknown_xml() {
+    assert!(xml::parse("<html></html>").is_err());
+    assert!(xml::parse("not xml").is_err());
+}
+
+fn sample_catalog() -> Catalog {
+    let mut catalog = Catalog::new();
+    catalog.load_file(&data("catalog.yml"), None).unwrap();
+    catalog
+        .load_file(&data("catalog.xml"), Some(PackageSource::Flatpak))
+        .unwrap();
+    catalog
+        .load_file(&data("org.example.Notes.metainfo.xml"), None)
+        .unwrap();
+    catalog
+}
+
+#[test]
+fn catalog_lookups() {
+    let catalog = sample_catalog();
+    assert_eq!(catalog.len(), 5);
+
+    // The first catalog describing an app wins, with or without the .desktop suffix
+    let calculator = catalog.get("org.gnome.Calculator").unwrap();
+    assert_eq!(calculator.origin.as_deref(), Some("debian-bookworm-main"));
+    assert_eq!(
+        catalog.get("org.gnome.Calculator.desktop").unwrap().id,
+        calculator.id
+    );
+    assert_eq!(catalog.get_all("org.gnome.Calculator").count(), 2);
+
+    assert_eq!(
+        catalog
+            .find_by_package(PackageSource::Apt, "gnome-calculator")
+            .unwrap()
+            .id,
+        "org.gnome.Calculator.desktop"
+    );
+    assert_eq!(
+        catalog
+            .find_by_package(PackageSource::Flatpak, "org.mozilla.firefox")
+            .unwrap()
+            .name,
+        "Firefox"
+    );
+    assert!(catalog
+        .find_by_package(PackageSource::Apt, "firefox")
+        .is_none());
+    assert!(catalog.get("org.example.Missing").is_none());
+}
+
+#[test]
+fn manager_serves_metadata_from_catalog() {
+    let manager = AppStreamManager::with_catalog(sample_catalog());
+
+    let metadata = manager.get_app_metadata("org.mozilla.firefox").unwrap();
+    assert_eq!(metadata.name, "Firefox");
+    assert_eq!(metadata.icon.as_deref(), Some("org.mozilla.firefox"));
+    assert_eq!(metadata.screenshots.len(), 2);
+
+    let developer = manager.get_developer_info("org.gnome.Calculator").unwrap();
+    assert_eq!(developer.name, "The GNOME Project");
+    assert_eq!(
+        developer.url.as_deref(),
+        Some("https://wiki.gnome.org/Apps/Calculator")
+    );
+
+    assert!(manager.get_app_metadata("org.example.Missing").is_err());
+    assert!(manager.get_developer_info("org.example.Notes").is_err());
+}
+
+#[test]
+fn resolver_groups_packages_by_component() {
+    let manager = AppStreamManager::with_catalog(sample_catalog());
+    let apps = manager.identity_resolver().group(vec![
+        package("gnome-calculator", PackageSource::Apt),
+        package("org.mozilla.firefox", PackageSource::Flatpak),
+        package("firefox", PackageSource::Snap),
+        package("org.gnome.Calculator", PackageSource::Flatpak),
+        package("notes", PackageSource::Pacman),
+        package("vim", PackageSource::Apt),
+    ]);
+
+    let ids: Vec<&str> = apps.iter().map(|app| app.id.as_str()).collect();
+    assert_eq!(
+        ids,
+        [
+            "org.gnome.Calculator",
+            "org.mozilla.firefox",
+            "org.example.Notes",
+            "vim"
+        ]
+    );
+    assert_eq!(apps[0].packages.len(), 2);
+    assert_eq!(apps[1].packages.len(), 2, "firefox.desktop is provided");
+}
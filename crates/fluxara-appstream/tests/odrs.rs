@@ -0,0 +1,401 @@
+use fluxara_appstream::OdrsClient;
+use fluxara_core::AppReview;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+/// Request seen by the stand-in server
+struct Request {
+    url: String,
+    body: String,
+}
+
+/// Stand-in ODRS server answering `count` requests with the canned fixtures
+fn serve(count: usize) -> (String, JoinHandle<Vec<Request>>) {
+    serve_with(count, canned)
+}
+
+/// Stand-in ODRS server answering `count` requests with `respond(url, body)`
+fn serve_with(
+    count: usize,
+    respond: fn(&str, &str) -> (u16, String),
+) -> (String, JoinHandle<Vec<Request>>) {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let api_url = format!(
+        "http://{}/1.0/reviews/api",
+        server.server_addr().to_ip().unwrap()
+    );
+
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for mut request in server.incoming_requests().take(count) {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let (status, reply) = respond(request.url(), &body);
+            requests.push(Request {
+                url: request.url().to_string(),
+                body,
+            });
+            let response = tiny_http::Response::from_string(reply).with_status_code(status);
+            request.respond(response).unwrap();
+        }
+        requests
+    });
+
+    (api_url, handle)
+}
+
+/// Fixtures for reads, where the first review is by the requesting user,
+/// and success for writes
+fn canned(url: &str, body: &str) -> (u16, String) {
+    if url.ends_with("/ratings") {
+        return (200, fs::read_to_string(data("odrs-ratings.json")).unwrap());
+    }
+    if url.ends_with("/fetch") {
+        let request: serde_json::Value = serde_json::from_str(body).unwrap();
+        let mut reviews: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(data("odrs-reviews.json")).unwrap()).unwrap();
+        reviews[0]["user_hash"] = request["user_hash"].clone();
+        return (200, reviews.to_string());
+    }
+    (200, r#"{"success": true, "msg": "done"}"#.to_string())
+}
+
+/// Placeholder the service returns for apps nobody reviewed yet
+fn unreviewed(url: &str, _body: &str) -> (u16, String) {
+    if url.ends_with("/fetch") {
+        let placeholder = r#"[{"app_id": "org.example.Paint", "user_skey": "f00d", "score": 0}]"#;
+        return (200, placeholder.to_string());
+    }
+    (200, r#"{"success": true, "msg": "done"}"#.to_string())
+}
+
+fn rejecting(url: &str, body: &str) -> (u16, String) {
+    if url.ends_with("/fetch") {
+        return canned(url, body);
+    }
+    (
+        400,
+        r#"{"success": false, "msg": "already reviewed this app"}"#.to_string(),
+    )
+}
+
+fn json(request: &Request) -> serde_json::Value {
+    serde_json::from_str(&request.body).unwrap()
+}
+
+fn review(app_id: &str, rating: u32) -> AppReview {
+    AppReview {
+        review_id: None,
+        app_id: app_id.to_string(),
+        rating,
+        summary: "Reliable".to_string(),
+        description: "Starts quickly and never lost a note.".to_string(),
+        reviewer: "Grace".to_string(),
+        date: String::new(),
+        version: Some("2.1.0".to_string()),
+        own: false,
+    }
+}
+
+/// Endpoint nothing listens on
+fn offline_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}/1.0/reviews/api", listener.local_addr().unwrap())
+}
+
+fn client(api_url: &str, cache: &Path, ttl: Duration) -> OdrsClient {
+    OdrsClient::new()
+        .with_api_url(api_url)
+        .with_cache(Some(cache.to_path_buf()), ttl)
+}
+
+#[test]
+fn ratings_are_averaged_over_stars() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(1);
+
+    let ratings = client(&api_url, cache.path(), DAY).get_ratings().unwrap();
+
+    let notes = &ratings["org.example.Notes"];
+    assert_eq!(notes.total_reviews, 5);
+    assert!((notes.average_rating - 4.25).abs() < 0.001);
+    assert_eq!(ratings["org.example.Paint"].average_rating, 1.0);
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0].url, "/1.0/reviews/api/ratings");
+}
+
+#[test]
+fn unrated_app_gets_empty_rating() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(1);
+
+    let rating = client(&api_url, cache.path(), DAY)
+        .get_rating("org.example.Unknown")
+        .unwrap();
+
+    assert_eq!(rating.app_id, "org.example.Unknown");
+    assert_eq!(rating.total_reviews, 0);
+    server.join().unwrap();
+}
+
+#[test]
+fn fresh_cache_is_served_without_request() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(1);
+    client(&api_url, cache.path(), DAY).get_ratings().unwrap();
+    server.join().unwrap();
+
+    let ratings = client(&offline_url(), cache.path(), DAY)
+        .get_ratings()
+        .unwrap();
+    assert_eq!(ratings["org.example.Notes"].total_reviews, 5);
+    assert!(cache.path().join("ratings.json").exists());
+}
+
+#[test]
+fn stale_cache_is_used_offline() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(1);
+    client(&api_url, cache.path(), Duration::ZERO)
+        .get_ratings()
+        .unwrap();
+    server.join().unwrap();
+
+    let ratings = client(&offline_url(), cache.path(), Duration::ZERO)
+        .get_ratings()
+        .unwrap();
+    assert_eq!(ratings.len(), 2);
+}
+
+#[test]
+fn expired_cache_is_refreshed() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(2);
+    let client = client(&api_url, cache.path(), Duration::ZERO);
+
+    client.get_ratings().unwrap();
+    client.get_ratings().unwrap();
+
+    assert_eq!(server.join().unwrap().len(), 2);
+}
+
+#[test]
+fn unreachable_service_without_cache_fails() {
+    let cache = tempfile::tempdir().unwrap();
+
+    let result = client(&offline_url(), cache.path(), DAY).get_ratings();
+    assert!(result.is_err());
+}
+
+#[test]
+fn reviews_are_fetched_for_version() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(1);
+
+    let reviews = client(&api_url, cache.path(), DAY)
+        .get_reviews("org.example.Notes", Some("2.1.0"))
+        .unwrap();
+
+    assert_eq!(reviews.len(), 2);
+    assert_eq!(reviews[0].review_id, Some(4211));
+    assert_eq!(reviews[0].rating, 5);
+    assert_eq!(reviews[0].reviewer, "Ada");
+    assert_eq!(reviews[0].date, "2023-11-14");
+    assert_eq!(reviews[0].version.as_deref(), Some("2.1.0"));
+    assert!(reviews[0].own);
+    assert_eq!(reviews[1].rating, 3);
+    assert_eq!(reviews[1].reviewer, "Anonymous");
+    assert!(!reviews[1].own);
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0].url, "/1.0/reviews/api/fetch");
+    let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+    assert_eq!(body["app_id"], "org.example.Notes");
+    assert_eq!(body["version"], "2.1.0");
+    assert!(body["locale"].is_string());
+    assert!(body["distro"].is_string());
+    assert_eq!(body["user_hash"].as_str().unwrap().len(), 40);
+}
+
+#[test]
+fn reviews_are_cached_per_version() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(2);
+    let client = client(&api_url, cache.path(), DAY);
+
+    client
+        .get_reviews("org.example.Notes", Some("2.0.0"))
+        .unwrap();
+    client
+        .get_reviews("org.example.Notes", Some("2.1.0"))
+        .unwrap();
+
+    let versions: Vec<_> = server
+        .join()
+        .unwrap()
+        .iter()
+        .map(|request| json(request)["version"].clone())
+        .collect();
+    assert_eq!(versions, ["2.0.0", "2.1.0"]);
+    let cached = fs::read_dir(cache.path().join("reviews/org.example.Notes"))
+        .unwrap()
+        .count();
+    assert_eq!(cached, 2);
+}
+
+#[test]
+fn placeholder_of_unreviewed_app_is_not_a_review() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve_with(1, unreviewed);
+
+    let reviews = client(&api_url, cache.path(), DAY)
+        .get_reviews("org.example.Paint", None)
+        .unwrap();
+
+    assert!(reviews.is_empty());
+    server.join().unwrap();
+}
+
+#[test]
+fn review_is_submitted_with_user_key() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve_with(2, unreviewed);
+
+    client(&api_url, cache.path(), DAY)
+        .submit_review(&review("org.example.Paint", 4))
+        .unwrap();
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[1].url, "/1.0/reviews/api/submit");
+    let body = json(&requests[1]);
+    assert_eq!(body["user_skey"], "f00d");
+    assert_eq!(body["user_hash"], json(&requests[0])["user_hash"]);
+    assert_eq!(body["app_id"], "org.example.Paint");
+    assert_eq!(body["rating"], 80);
+    assert_eq!(body["summary"], "Reliable");
+    assert_eq!(body["user_display"], "Grace");
+    assert_eq!(body["version"], "2.1.0");
+    assert!(body["locale"].is_string());
+    assert!(body["distro"].is_string());
+}
+
+#[test]
+fn submitting_drops_cached_reviews() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve_with(3, unreviewed);
+    let client = client(&api_url, cache.path(), DAY);
+
+    client
+        .get_reviews("org.example.Paint", Some("2.1.0"))
+        .unwrap();
+    client
+        .submit_review(&review("org.example.Paint", 5))
+        .unwrap();
+    client
+        .get_reviews("org.example.Paint", Some("2.1.0"))
+        .unwrap();
+
+    let urls: Vec<_> = server
+        .join()
+        .unwrap()
+        .into_iter()
+        .map(|request| request.url)
+        .collect();
+    assert_eq!(
+        urls,
+        [
+            "/1.0/reviews/api/fetch",
+            "/1.0/reviews/api/submit",
+            "/1.0/reviews/api/fetch"
+        ]
+    );
+}
+
+#[test]
+fn invalid_review_is_not_sent() {
+    let cache = tempfile::tempdir().unwrap();
+    let client = client(&offline_url(), cache.path(), DAY);
+
+    assert!(client
+        .submit_review(&review("org.example.Paint", 0))
+        .is_err());
+    assert!(client
+        .submit_review(&review("org.example.Paint", 6))
+        .is_err());
+
+    let mut empty = review("org.example.Paint", 3);
+    empty.summary = "  ".to_string();
+    let error = client.submit_review(&empty).unwrap_err();
+    assert!(error.to_string().contains("summary"));
+}
+
+#[test]
+fn votes_and_reports_name_the_review() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(4);
+    let client = client(&api_url, cache.path(), DAY);
+
+    client.upvote("org.example.Notes", 4187).unwrap();
+    client.downvote("org.example.Notes", 4187).unwrap();
+    client.report("org.example.Notes", 4187).unwrap();
+
+    let requests = server.join().unwrap();
+    // The user key is fetched once, then served from the cache
+    assert_eq!(requests[0].url, "/1.0/reviews/api/fetch");
+    for (request, action) in requests[1..].iter().zip(["upvote", "downvote", "report"]) {
+        assert_eq!(request.url, format!("/1.0/reviews/api/{}", action));
+        let body = json(request);
+        assert_eq!(body["review_id"], 4187);
+        assert_eq!(body["app_id"], "org.example.Notes");
+        assert_eq!(body["user_skey"], "a1b2c3");
+    }
+}
+
+#[test]
+fn own_review_is_removed() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve(2);
+
+    client(&api_url, cache.path(), DAY)
+        .remove_review("org.example.Notes", 4211)
+        .unwrap();
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[1].url, "/1.0/reviews/api/remove");
+    assert_eq!(json(&requests[1])["review_id"], 4211);
+    assert!(!cache
+        .path()
+        .join("reviews")
+        .read_dir()
+        .unwrap()
+        .any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .contains("Notes")));
+}
+
+#[test]
+fn rejection_reason_is_reported() {
+    let cache = tempfile::tempdir().unwrap();
+    let (api_url, server) = serve_with(2, rejecting);
+
+    let error = client(&api_url, cache.path(), DAY)
+        .submit_review(&review("org.example.Notes", 2))
+        .unwrap_err();
+
+    assert!(error.to_string().contains("already reviewed this app"));
+    server.join().unwrap();
+}
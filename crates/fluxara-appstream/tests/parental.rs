@@ -0,0 +1,169 @@
+use fluxara_appstream::{AppStreamManager, Catalog};
+use fluxara_core::config::ParentalConfig;
+use fluxara_core::{ContentLevel, Package, PackageSource};
+use std::path::{Path, PathBuf};
+
+fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+fn manager() -> AppStreamManager {
+    let mut catalog = Catalog::new();
+    catalog.load_file(&data("catalog.yml"), None).unwrap();
+    catalog
+        .load_file(&data("catalog.xml"), Some(PackageSource::Flatpak))
+        .unwrap();
+    AppStreamManager::with_catalog(catalog)
+}
+
+fn package(id: &str, source: PackageSource) -> Package {
+    Package {
+        id: id.to_string(),
+        name: id.to_string(),
+        version: None,
+        description: None,
+        icon_url: None,
+        source,
+        installed: false,
+    }
+}
+
+fn policy() -> ParentalConfig {
+    ParentalConfig {
+        enabled: true,
+        ..ParentalConfig::default()
+    }
+}
+
+#[test]
+fn content_rating_is_found_by_package() {
+    let manager = manager();
+
+    let firefox = manager
+        .content_rating(PackageSource::Flatpak, "org.mozilla.firefox")
+        .unwrap();
+    // social-chat intense outweighs money-purchasing mild
+    assert_eq!(firefox.minimum_age(), 13);
+
+    let cli = manager
+        .content_rating(PackageSource::Apt, "appstream")
+        .unwrap();
+    assert_eq!(cli.level("violence-cartoon"), ContentLevel::Mild);
+    assert_eq!(cli.level("drugs-alcohol"), ContentLevel::None);
+    assert_eq!(cli.minimum_age(), 3);
+
+    let calculator = manager
+        .content_rating(PackageSource::Apt, "gnome-calculator")
+        .unwrap();
+    assert_eq!(calculator.minimum_age(), 0);
+
+    assert!(manager.content_rating(PackageSource::Apt, "bash").is_none());
+}
+
+#[test]
+fn max_age_blocks_older_content() {
+    let manager = manager();
+    let policy = ParentalConfig {
+        max_age: Some(12),
+        ..policy()
+    };
+
+    let violations =
+        manager.content_violations(&policy, PackageSource::Flatpak, "org.mozilla.firefox");
+    assert_eq!(violations, ["rated for ages 13+, limit is 12"]);
+    assert!(manager
+        .content_violations(&policy, PackageSource::Apt, "gnome-calculator")
+        .is_empty());
+    assert!(manager
+        .content_violations(&policy, PackageSource::Apt, "appstream")
+        .is_empty());
+}
+
+#[test]
+fn attribute_limits_block_more_intense_content() {
+    let manager = manager();
+    let policy = ParentalConfig {
+        limits: [("violence-cartoon".to_string(), ContentLevel::None)].into(),
+        ..policy()
+    };
+
+    let violations = manager.content_violations(&policy, PackageSource::Apt, "appstream");
+    assert_eq!(violations, ["violence-cartoon is mild, limit is none"]);
+    assert!(manager
+        .content_violations(&policy, PackageSource::Flatpak, "org.mozilla.firefox")
+        .is_empty());
+}
+
+#[test]
+fn unrated_packages_follow_policy() {
+    let manager = manager();
+
+    assert!(manager
+        .content_violations(&policy(), PackageSource::Apt, "bash")
+        .is_empty());
+
+    let strict = ParentalConfig {
+        allow_unrated: false,
+        ..policy()
+    };
+    assert_eq!(
+        manager.content_violations(&strict, PackageSource::Apt, "bash"),
+        ["no content rating"]
+    );
+}
+
+#[test]
+fn search_results_are_filtered_for_restricted_users_only() {
+    let manager = manager();
+    let packages = vec![
+        package("org.mozilla.firefox", PackageSource::Flatpak),
+        package("gnome-calculator", PackageSource::Apt),
+        package("bash", PackageSource::Apt),
+    ];
+    let policy = ParentalConfig {
+        max_age: Some(7),
+        ..policy()
+    };
+
+    let mut allowed = packages.clone();
+    manager.retain_allowed(&policy, &mut allowed);
+    let ids: Vec<_> = allowed.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, ["gnome-calculator", "bash"]);
+
+    let other_user = ParentalConfig {
+        restricted_users: vec!["no-such-user-for-fluxara-tests".to_string()],
+        ..policy.clone()
+    };
+    let mut unrestricted = packages.clone();
+    manager.retain_allowed(&other_user, &mut unrestricted);
+    assert_eq!(unrestricted.len(), 3);
+
+    let disabled = ParentalConfig {
+        enabled: false,
+        ..policy
+    };
+    let mut unrestricted = packages;
+    manager.retain_allowed(&disabled, &mut unrestricted);
+    assert_eq!(unrestricted.len(), 3);
+}
+
+#[test]
+fn package_is_rated_by_its_strictest_component() {
+    let mut catalog = Catalog::new();
+    catalog.load_file(&data("games.yml"), None).unwrap();
+    let manager = AppStreamManager::with_catalog(catalog);
+
+    let rating = manager.content_rating(PackageSource::Apt, "arena").unwrap();
+    assert_eq!(rating.level("violence-realistic"), ContentLevel::Intense);
+    assert_eq!(rating.minimum_age(), 14);
+
+    let policy = ParentalConfig {
+        max_age: Some(12),
+        ..policy()
+    };
+    assert!(!manager
+        .content_violations(&policy, PackageSource::Apt, "arena")
+        .is_empty());
+}
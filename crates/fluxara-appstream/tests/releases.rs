@@ -0,0 +1,106 @@
+use fluxara_appstream::{compare_versions, upstream_version, AppStreamManager, Catalog};
+use fluxara_core::{PackageSource, UpdateInfo};
+use std::cmp::Ordering;
+use std::path::Path;
+
+fn manager() -> AppStreamManager {
+    let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let mut catalog = Catalog::new();
+    catalog.load_file(&data.join("catalog.yml"), None).unwrap();
+    catalog
+        .load_file(&data.join("catalog.xml"), Some(PackageSource::Flatpak))
+        .unwrap();
+    AppStreamManager::with_catalog(catalog)
+}
+
+fn update(package_id: &str, source: PackageSource, current: &str, new: &str) -> UpdateInfo {
+    UpdateInfo {
+        package_id: package_id.to_string(),
+        current_version: current.to_string(),
+        new_version: new.to_string(),
+        source,
+        size_bytes: None,
+        security: false,
+    }
+}
+
+fn versions(manager: &AppStreamManager, update: &UpdateInfo) -> Vec<String> {
+    manager
+        .release_notes(update)
+        .into_iter()
+        .map(|release| release.version)
+        .collect()
+}
+
+#[test]
+fn strips_packaging_from_versions() {
+    assert_eq!(upstream_version("1:43.0.1-2"), "43.0.1");
+    assert_eq!(upstream_version("2:9.0.1378-2+deb12u2"), "9.0.1378");
+    assert_eq!(upstream_version("115.3.0esr-1~deb12u1"), "115.3.0esr");
+    assert_eq!(upstream_version("1.4+dfsg-1ubuntu3"), "1.4");
+    assert_eq!(upstream_version("v2.1"), "2.1");
+    assert_eq!(upstream_version("130.0"), "130.0");
+    assert_eq!(upstream_version("20240115-1"), "20240115");
+}
+
+#[test]
+fn compares_versions_by_segment() {
+    assert_eq!(compare_versions("43.0.1", "43.0"), Ordering::Greater);
+    assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+    assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Less);
+    assert_eq!(compare_versions("2.0", "2.0"), Ordering::Equal);
+    assert_eq!(compare_versions("1.0beta", "1.0.1"), Ordering::Less);
+    assert_eq!(compare_versions("1.0_rc1", "1.0.rc1"), Ordering::Equal);
+}
+
+#[test]
+fn release_notes_between_versions() {
+    let manager = manager();
+
+    let calculator = update(
+        "gnome-calculator",
+        PackageSource::Apt,
+        "1:43.0-1",
+        "1:43.0.1-2",
+    );
+    assert_eq!(versions(&manager, &calculator), ["43.0.1"]);
+    let notes = manager.release_notes(&calculator);
+    assert_eq!(
+        notes[0].description.as_deref(),
+        Some("Fixed appdata versioning")
+    );
+
+    let firefox = update(
+        "org.mozilla.firefox",
+        PackageSource::Flatpak,
+        "128.0",
+        "130.0",
+    );
+    assert_eq!(versions(&manager, &firefox), ["130.0", "129.0.2"]);
+}
+
+#[test]
+fn release_notes_with_unknown_versions() {
+    let manager = manager();
+
+    let unknown_new = update("org.mozilla.firefox", PackageSource::Flatpak, "129.0.2", "");
+    assert_eq!(versions(&manager, &unknown_new), ["130.0"]);
+
+    let unknown_current = update("org.mozilla.firefox", PackageSource::Flatpak, "", "129.0.2");
+    assert_eq!(versions(&manager, &unknown_current), ["129.0.2"]);
+
+    let unknown_both = update("org.mozilla.firefox", PackageSource::Flatpak, "", "");
+    assert_eq!(versions(&manager, &unknown_both), ["130.0"]);
+}
+
+#[test]
+fn release_notes_for_unknown_packages_are_empty() {
+    let manager = manager();
+    let vim = update("vim", PackageSource::Apt, "9.0", "9.1");
+    assert!(manager.release_notes(&vim).is_empty());
+
+    // Distribution components are not matched against Flatpak packages
+    let calculator = update("gnome-calculator", PackageSource::Flatpak, "43.0", "43.0.1");
+    assert!(manager.release_notes(&calculator).is_empty());
+    assert_eq!(manager.releases("org.gnome.Calculator").len(), 2);
+}
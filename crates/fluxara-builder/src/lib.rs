@@ -90,7 +90,11 @@ impl SourceBuilder {
             BuildSystem::Autotools => "./configure && make",
         };
 
-        println!("Would run in Podman: {}", build_cmd);
+        println!(
+            "Would run in Podman ({}): {}",
+            source_dir.display(),
+            build_cmd
+        );
 
         // TODO: Actually execute in Podman container
         Ok(())
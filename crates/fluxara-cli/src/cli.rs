@@ -0,0 +1,354 @@
+use crate::output::OutputFormat;
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+use fluxara_core::PackageSource;
+use std::path::PathBuf;
+
+/// Fluxara CLI - Universal Linux Package Manager
+#[derive(Debug, Parser)]
+#[command(name = "fluxara", version, about, propagate_version = true)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalOpts,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Flags shared by every subcommand
+#[derive(Debug, Clone, Args)]
+pub struct GlobalOpts {
+    /// Restrict the operation to a single package source
+    #[arg(long, short = 's', global = true, value_name = "SOURCE")]
+    pub source: Option<PackageSource>,
+
+    /// Answer yes to every confirmation prompt
+    #[arg(long, short = 'y', global = true)]
+    pub yes: bool,
+
+    /// Show what would be done without changing the system
+    #[arg(long, short = 'n', global = true)]
+    pub dry_run: bool,
+
+    /// Output format for command results
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    /// Shorthand for --output json
+    #[arg(long, global = true, conflicts_with = "output")]
+    pub json: bool,
+
+    /// Only print errors
+    #[arg(long, short = 'q', global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print additional progress information
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+}
+
+impl GlobalOpts {
+    /// Effective output format, honouring the `--json` shorthand
+    pub fn format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.output
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Search for packages
+    Search {
+        /// Search query
+        query: String,
+        /// Query the backends directly instead of the local package index
+        #[arg(long)]
+        live: bool,
+        /// List every package separately instead of one entry per app
+        #[arg(long)]
+        ungrouped: bool,
+    },
+    /// Show details about a package and compare the sources offering it
+    Info {
+        /// Package id or name
+        package: String,
+    },
+    /// Install packages, choosing each source by preference or by asking
+    Install {
+        /// Package ids or names
+        #[arg(required = true)]
+        packages: Vec<String>,
+        /// Install even if the app is already installed from another source
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove packages
+    Remove {
+        /// Package ids
+        #[arg(required = true)]
+        packages: Vec<String>,
+    },
+    /// Update the given packages, or list available updates when none are given
+    Update {
+        /// Package ids
+        packages: Vec<String>,
+        /// Show the release notes of pending updates instead of applying them
+        #[arg(long)]
+        changelog: bool,
+    },
+    /// List installed packages
+    List,
+    /// Update every installed package
+    Upgrade,
+    /// Hold packages at their installed version, or list held packages when none are given
+    Hold {
+        /// Package ids
+        packages: Vec<String>,
+    },
+    /// Allow held packages to be updated again
+    Unhold {
+        /// Package ids
+        #[arg(required = true)]
+        packages: Vec<String>,
+    },
+    /// Write a manifest of the explicitly installed packages
+    Export {
+        /// Write to this file instead of standard output
+        #[arg(long, short = 'f')]
+        file: Option<PathBuf>,
+        /// Record the installed version of every package
+        #[arg(long)]
+        pin: bool,
+    },
+    /// Install what a manifest lists and is missing on this machine
+    Apply {
+        /// Manifest written by `fluxara export`
+        manifest: PathBuf,
+        /// Also remove explicitly installed packages the manifest does not list
+        #[arg(long)]
+        remove_extras: bool,
+    },
+    /// Show or undo past transactions
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Manage the local package index used for searching
+    #[command(subcommand)]
+    Index(IndexCommand),
+    /// Manage repositories and mirrors
+    #[command(subcommand)]
+    Repo(RepoCommand),
+    /// Detect hardware and manage drivers
+    #[command(subcommand)]
+    Driver(DriverCommand),
+    /// System maintenance tasks
+    #[command(subcommand)]
+    Maintenance(MaintenanceCommand),
+    /// Show or verify the log of privileged operations
+    #[command(subcommand)]
+    Audit(AuditCommand),
+    /// Convert a foreign package with alien
+    Convert {
+        /// Path to the package file
+        path: PathBuf,
+        /// Target package format (deb, rpm, tgz)
+        #[arg(long, short = 't', default_value = "deb")]
+        to: String,
+    },
+    /// Build a package from source in a Podman sandbox
+    Build {
+        /// Source archive or repository URL
+        url: String,
+    },
+    /// Show or change the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Generate shell completions
+    Completions {
+        /// Target shell
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// List recorded transactions
+    List,
+    /// Show the operations of one transaction
+    Show {
+        /// Transaction id
+        id: u64,
+    },
+    /// Revert a transaction by replaying its inverse operations
+    Undo {
+        /// Transaction id
+        id: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum IndexCommand {
+    /// Rebuild the index from every package source
+    Refresh,
+    /// Show when each source was last indexed
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RepoCommand {
+    /// List configured repositories
+    List,
+    /// Test mirror speeds and report the fastest one
+    Mirrors,
+    /// Add a Flatpak remote
+    AddRemote {
+        /// Remote name, e.g. flathub-beta
+        name: String,
+        /// Repository URL or .flatpakrepo file
+        url: String,
+    },
+    /// Remove a Flatpak remote
+    RemoveRemote {
+        /// Remote name
+        name: String,
+    },
+    /// Add a one-line APT source
+    AddSource {
+        /// Source entry, e.g. "deb https://example.org/debian stable main"
+        entry: String,
+    },
+    /// Remove an APT source added with add-source
+    RemoveSource {
+        /// Source entry as it was added
+        entry: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DriverCommand {
+    /// List detected hardware devices
+    Detect,
+    /// List available drivers for this machine
+    List,
+    /// Install a driver listed for this machine
+    Install {
+        /// Driver name as shown by `driver list`
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MaintenanceCommand {
+    /// Clean package caches
+    CleanCache,
+    /// Remove orphaned packages
+    Orphans,
+    /// Detect package conflicts
+    Conflicts,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// List privileged operations, newest first
+    List,
+    /// Check that no entry was modified or removed
+    Verify,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the current configuration
+    Show,
+    /// Print the path of the configuration file
+    Path,
+    /// Print a single configuration value
+    Get {
+        /// Dotted key, e.g. ui.tray_enabled
+        key: String,
+    },
+    /// Change a single configuration value
+    Set {
+        /// Dotted key, e.g. ui.tray_enabled
+        key: String,
+        /// New value, parsed as TOML when possible
+        value: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("fluxara").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn command_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn global_flags_are_accepted_after_the_subcommand() {
+        let cli = parse(&["install", "vim", "--source", "apt", "--yes", "--dry-run"]).unwrap();
+        assert_eq!(cli.global.source, Some(PackageSource::Apt));
+        assert!(cli.global.yes);
+        assert!(cli.global.dry_run);
+
+        let cli = parse(&["-s", "flatpak", "-y", "-n", "remove", "org.gnome.Maps"]).unwrap();
+        assert_eq!(cli.global.source, Some(PackageSource::Flatpak));
+        assert!(cli.global.yes && cli.global.dry_run);
+
+        let cli = parse(&["list"]).unwrap();
+        assert_eq!(cli.global.source, None);
+        assert!(!cli.global.yes && !cli.global.dry_run);
+        assert_eq!(cli.global.format(), OutputFormat::Table);
+    }
+
+    #[test]
+    fn unknown_source_is_a_usage_error() {
+        let error = parse(&["list", "--source", "rpm"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        assert_eq!(
+            error.exit_code(),
+            i32::from(crate::output::exit_code::USAGE)
+        );
+    }
+
+    #[test]
+    fn json_is_shorthand_for_output_json_and_excludes_it() {
+        assert_eq!(
+            parse(&["list", "--json"]).unwrap().global.format(),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse(&["list", "-o", "jsonl"]).unwrap().global.format(),
+            OutputFormat::Jsonl
+        );
+
+        let error = parse(&["list", "--json", "--output", "plain"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn completions_are_generated_for_every_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let Command::Completions { shell: parsed } =
+                parse(&["completions", &shell.to_string()]).unwrap().command
+            else {
+                panic!("completions not parsed");
+            };
+            assert_eq!(parsed, shell);
+
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Cli::command(), "fluxara", &mut script);
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains("fluxara"), "{} completions empty", shell);
+            assert!(script.contains("install"), "{} misses subcommands", shell);
+        }
+    }
+}
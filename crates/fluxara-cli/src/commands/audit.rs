@@ -0,0 +1,36 @@
+use super::Context;
+use crate::cli::AuditCommand;
+use anyhow::{anyhow, Result};
+use fluxara_polkit_agent::{AuditEntry, AuditLog};
+
+pub fn run(ctx: &Context, command: AuditCommand) -> Result<()> {
+    let log = AuditLog::open();
+
+    match command {
+        AuditCommand::List => {
+            let entries: Vec<AuditEntry> = log.entries()?.into_iter().rev().collect();
+            ctx.emit_list(&entries, "No privileged operations recorded.")
+        }
+        AuditCommand::Verify => {
+            let verification = log.verify()?;
+            if ctx.format().is_machine() {
+                ctx.emit_value(&verification)?;
+            }
+            match verification.broken {
+                None => {
+                    ctx.status(&format!(
+                        "Audit log intact: {} entries checked.",
+                        verification.entries
+                    ));
+                    Ok(())
+                }
+                Some(broken) => Err(anyhow!(
+                    "Audit log {} is broken at line {}: {}",
+                    log.path().display(),
+                    broken.line,
+                    broken.reason
+                )),
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use super::Context;
+use crate::output::OutputFormat;
+use anyhow::Result;
+use fluxara_builder::SourceBuilder;
+
+pub async fn run(ctx: &Context, url: &str) -> Result<()> {
+    if ctx.opts.dry_run {
+        ctx.status(&format!("Would build {} in a Podman sandbox", url));
+        return Ok(());
+    }
+
+    let result = SourceBuilder::new().build_from_url(url).await?;
+
+    match ctx.format() {
+        OutputFormat::Table | OutputFormat::Plain => println!("{}", result),
+        _ => ctx.emit_value(&serde_json::json!({ "result": result }))?,
+    }
+    Ok(())
+}
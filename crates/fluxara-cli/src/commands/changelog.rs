@@ -0,0 +1,100 @@
+use super::{packages, Context};
+use crate::output::{CliError, OutputFormat, Tabular};
+use anyhow::Result;
+use fluxara_appstream::{AppStreamManager, Release};
+use fluxara_core::PackageSource;
+use serde::Serialize;
+
+/// What a pending update changes, from the component's AppStream releases
+#[derive(Debug, Serialize)]
+pub struct Changelog {
+    pub package_id: String,
+    pub source: PackageSource,
+    pub current_version: String,
+    pub new_version: String,
+    /// Newest first; empty when AppStream does not describe the package
+    pub releases: Vec<Release>,
+}
+
+impl Tabular for Changelog {
+    const HEADERS: &'static [&'static str] = &["ID", "SOURCE", "CURRENT", "NEW", "RELEASES"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.package_id.clone(),
+            self.source.to_string(),
+            self.current_version.clone(),
+            self.new_version.clone(),
+            self.releases
+                .iter()
+                .map(|r| r.version.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]
+    }
+}
+
+/// Show the release notes of pending updates, all of them or those of `package_ids`
+pub async fn run(ctx: &Context, package_ids: &[String]) -> Result<()> {
+    let mut updates = packages::collect_updates(ctx).await?;
+    if !package_ids.is_empty() {
+        if let Some(missing) = package_ids
+            .iter()
+            .find(|id| !updates.iter().any(|u| &u.package_id == *id))
+        {
+            return Err(CliError::NotFound(format!("No pending update for {}", missing)).into());
+        }
+        updates.retain(|u| package_ids.contains(&u.package_id));
+    }
+
+    let appstream = AppStreamManager::new();
+    let changelogs: Vec<Changelog> = updates
+        .into_iter()
+        .map(|update| Changelog {
+            releases: appstream.release_notes(&update),
+            package_id: update.package_id,
+            source: update.source,
+            current_version: update.current_version,
+            new_version: update.new_version,
+        })
+        .collect();
+
+    match ctx.format() {
+        OutputFormat::Table if changelogs.is_empty() => println!("All packages are up to date."),
+        OutputFormat::Table => print_changelogs(&changelogs),
+        _ => ctx.emit_list(&changelogs, "")?,
+    }
+
+    Ok(())
+}
+
+fn print_changelogs(changelogs: &[Changelog]) {
+    for (i, changelog) in changelogs.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!(
+            "{} {} → {} ({})",
+            changelog.package_id,
+            changelog.current_version,
+            changelog.new_version,
+            changelog.source
+        );
+        if changelog.releases.is_empty() {
+            println!("  No release notes available");
+        }
+        for release in &changelog.releases {
+            let mut heading = release.version.clone();
+            if let Some(date) = release.date {
+                heading.push_str(&format!(" ({})", date));
+            }
+            if let Some(urgency) = release.urgency.as_deref().filter(|u| *u != "low") {
+                heading.push_str(&format!(" [{} urgency]", urgency));
+            }
+            println!("  {}", heading);
+            for line in release.description.as_deref().unwrap_or("").lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+}
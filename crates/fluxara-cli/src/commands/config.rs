@@ -0,0 +1,74 @@
+use super::Context;
+use crate::cli::ConfigCommand;
+use crate::output::CliError;
+use anyhow::{Context as _, Result};
+use fluxara_core::Config;
+
+pub fn run(ctx: &Context, command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Show => {
+            if ctx.format().is_machine() {
+                return ctx.emit_value(&ctx.config);
+            }
+            print!("{}", toml::to_string_pretty(&ctx.config)?);
+        }
+        ConfigCommand::Path => {
+            let path = Config::config_path()?;
+            if ctx.format().is_machine() {
+                return ctx.emit_value(&serde_json::json!({ "path": path }));
+            }
+            println!("{}", path.display());
+        }
+        ConfigCommand::Get { key } => {
+            let root = toml::Value::try_from(&ctx.config)?;
+            let value = lookup(&root, &key)
+                .ok_or_else(|| CliError::NotFound(format!("Unknown configuration key: {}", key)))?;
+            if ctx.format().is_machine() {
+                return ctx.emit_value(value);
+            }
+            match value {
+                toml::Value::String(s) => println!("{}", s),
+                toml::Value::Table(_) => print!("{}", toml::to_string_pretty(value)?),
+                other => println!("{}", other),
+            }
+        }
+        ConfigCommand::Set { key, value } => {
+            let mut root = toml::Value::try_from(&ctx.config)?;
+            let slot = lookup_mut(&mut root, &key)
+                .ok_or_else(|| CliError::NotFound(format!("Unknown configuration key: {}", key)))?;
+            *slot = parse_value(&value);
+
+            // Round-trip through Config so invalid values are rejected before saving
+            let config: Config = root
+                .try_into()
+                .with_context(|| format!("Invalid value for {}: {}", key, value))?;
+
+            if ctx.opts.dry_run {
+                ctx.status(&format!("Would set {} = {}", key, value));
+                return Ok(());
+            }
+
+            config.save()?;
+            ctx.status(&format!("Set {} = {}", key, value));
+        }
+    }
+
+    Ok(())
+}
+
+fn lookup<'a>(root: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(root, |value, part| value.get(part))
+}
+
+fn lookup_mut<'a>(root: &'a mut toml::Value, key: &str) -> Option<&'a mut toml::Value> {
+    key.split('.')
+        .try_fold(root, |value, part| value.get_mut(part))
+}
+
+/// Interpret a command-line value as TOML, falling back to a plain string
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
@@ -0,0 +1,38 @@
+use super::Context;
+use crate::output::{CliError, OutputFormat};
+use anyhow::Result;
+use fluxara_converter::{ConversionError, PackageConverter};
+use std::path::Path;
+
+pub fn run(ctx: &Context, path: &Path, target_format: &str) -> Result<()> {
+    if ctx.opts.dry_run {
+        ctx.status(&format!(
+            "Would convert {} to {}",
+            path.display(),
+            target_format
+        ));
+        return Ok(());
+    }
+
+    ctx.status(&format!(
+        "Converting {} to {}",
+        path.display(),
+        target_format
+    ));
+
+    let converter = PackageConverter::new(ctx.config.clone());
+    let output = converter
+        .convert_package(&path.to_string_lossy(), target_format)
+        .map_err(|e| match e.downcast_ref::<ConversionError>() {
+            Some(blocked @ ConversionError::SecurityBlocked(_)) => {
+                CliError::PermissionDenied(blocked.to_string()).into()
+            }
+            _ => e,
+        })?;
+
+    match ctx.format() {
+        OutputFormat::Table | OutputFormat::Plain => print!("{}", output),
+        _ => ctx.emit_value(&serde_json::json!({ "output": output.trim() }))?,
+    }
+    Ok(())
+}
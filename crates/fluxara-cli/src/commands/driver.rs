@@ -0,0 +1,69 @@
+use super::{helper_error, packages, Context};
+use crate::cli::DriverCommand;
+use crate::output::CliError;
+use anyhow::Result;
+use fluxara_core::{OperationKind, PackageSource, Transaction};
+use fluxara_drivers::DriverManager;
+
+pub async fn run(ctx: &Context, command: DriverCommand) -> Result<()> {
+    let manager = DriverManager::new();
+
+    match command {
+        DriverCommand::Detect => {
+            ctx.status("Detecting hardware...");
+            let devices = manager.detect_hardware()?;
+            ctx.emit_list(&devices, "No hardware detected.")
+        }
+        DriverCommand::List => {
+            let drivers = manager.check_proprietary_drivers()?;
+            ctx.emit_list(&drivers, "No additional drivers available.")
+        }
+        DriverCommand::Install { name } => install(ctx, &manager, &name).await,
+    }
+}
+
+async fn install(ctx: &Context, manager: &DriverManager, name: &str) -> Result<()> {
+    let driver = manager
+        .check_proprietary_drivers()?
+        .into_iter()
+        .find(|driver| driver.name == name)
+        .ok_or_else(|| CliError::NotFound(format!("No driver {} for this system", name)))?;
+
+    if ctx.opts.dry_run {
+        ctx.status(&format!(
+            "Would install {}: {}",
+            driver.name,
+            driver.packages.join(" ")
+        ));
+        return Ok(());
+    }
+
+    // The helper checks the driver against the hardware again before
+    // installing its packages as root
+    if !fluxara_polkit_agent::is_privileged() {
+        ctx.status(&format!("Installing {}...", driver.description));
+        ctx.helper()
+            .await?
+            .install_driver(&driver.name, ctx.helper_progress())
+            .await
+            .map_err(helper_error)?;
+        ctx.status(&format!("Installed {}", driver.name));
+        return Ok(());
+    }
+
+    if driver.packages.is_empty() {
+        anyhow::bail!(
+            "No packages of {} are known for this distribution",
+            driver.name
+        );
+    }
+    let source = [PackageSource::Apt, PackageSource::Pacman]
+        .into_iter()
+        .find(|source| ctx.registry.get(*source).is_some())
+        .ok_or_else(|| CliError::Unavailable("No native package manager available".to_string()))?;
+    let mut transaction = Transaction::new();
+    for package in &driver.packages {
+        transaction.add(OperationKind::Install, package, source);
+    }
+    packages::execute(ctx, &transaction, None).await
+}
@@ -0,0 +1,56 @@
+use super::{packages, Context};
+use crate::cli::HistoryCommand;
+use crate::output::CliError;
+use anyhow::Result;
+use fluxara_core::{History, HistoryEntry};
+
+pub async fn run(ctx: &Context, command: HistoryCommand) -> Result<()> {
+    let history = History::open()?;
+
+    match command {
+        HistoryCommand::List => {
+            let entries: Vec<HistoryEntry> = history.entries()?.into_iter().rev().collect();
+            ctx.emit_list(&entries, "No transaction history recorded.")
+        }
+        HistoryCommand::Show { id } => {
+            let entry = find(&history, id)?;
+            if ctx.format().is_machine() {
+                return ctx.emit_value(&entry);
+            }
+            ctx.emit_list(&entry.operations, "")
+        }
+        HistoryCommand::Undo { id } => undo(ctx, &history, id).await,
+    }
+}
+
+async fn undo(ctx: &Context, history: &History, id: u64) -> Result<()> {
+    let entry = find(history, id)?;
+    let (transaction, irreversible) = entry.inverse();
+
+    for outcome in &irreversible {
+        ctx.warn(&format!(
+            "Cannot undo {} of {}: the previous version is unknown",
+            outcome.operation.kind, outcome.operation.package_id
+        ));
+    }
+    if transaction.is_empty() {
+        return Err(anyhow::anyhow!("Transaction {} has nothing to undo", id));
+    }
+
+    if ctx.opts.dry_run {
+        return ctx.emit_list(&packages::dry_run(&transaction), "");
+    }
+
+    ctx.confirm(&format!(
+        "Undo transaction {} ({})?",
+        id,
+        packages::describe(&transaction)
+    ))?;
+    packages::execute(ctx, &transaction, Some(id)).await
+}
+
+fn find(history: &History, id: u64) -> Result<HistoryEntry> {
+    history
+        .get(id)?
+        .ok_or_else(|| CliError::NotFound(format!("No transaction with id {}", id)).into())
+}
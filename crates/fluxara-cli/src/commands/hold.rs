@@ -0,0 +1,123 @@
+use super::{packages, Context};
+use crate::output::Tabular;
+use anyhow::Result;
+use serde::Serialize;
+
+/// A package that is kept from updating, and what keeps it there
+#[derive(Debug, Serialize)]
+pub struct Hold {
+    pub package_id: String,
+    /// `fluxara` for holds in the configuration, otherwise the backend holding it
+    pub held_by: String,
+}
+
+impl Tabular for Hold {
+    const HEADERS: &'static [&'static str] = &["ID", "HELD_BY"];
+
+    fn row(&self) -> Vec<String> {
+        vec![self.package_id.clone(), self.held_by.clone()]
+    }
+}
+
+pub async fn hold(ctx: &Context, package_ids: &[String]) -> Result<()> {
+    if package_ids.is_empty() {
+        return list(ctx).await;
+    }
+
+    let mut config = ctx.config.clone();
+    for package_id in package_ids {
+        if !config.updates.is_held(package_id) {
+            config.updates.holds.push(package_id.clone());
+        }
+
+        // Also hold it in the backend, so that updates run outside Fluxara respect it
+        let Ok(provider) = packages::find_installed(ctx, package_id).await else {
+            let verb = if ctx.opts.dry_run {
+                "Would hold"
+            } else {
+                "Held"
+            };
+            ctx.status(&format!("{} {}", verb, package_id));
+            continue;
+        };
+        if ctx.opts.dry_run {
+            ctx.status(&format!(
+                "Would hold {} ({})",
+                package_id,
+                provider.source()
+            ));
+            continue;
+        }
+        match provider.hold(package_id).await {
+            Ok(()) => ctx.status(&format!("Held {} ({})", package_id, provider.source())),
+            Err(e) => ctx.warn(&format!(
+                "{} is held by Fluxara only; {} hold failed: {:#}",
+                package_id,
+                provider.source(),
+                e
+            )),
+        }
+    }
+
+    if !ctx.opts.dry_run {
+        config.save()?;
+    }
+    Ok(())
+}
+
+pub async fn unhold(ctx: &Context, package_ids: &[String]) -> Result<()> {
+    let mut config = ctx.config.clone();
+    config
+        .updates
+        .holds
+        .retain(|hold| !package_ids.contains(hold));
+
+    for package_id in package_ids {
+        for provider in ctx.providers()? {
+            let held = provider.list_holds().await.unwrap_or_default();
+            if !held.contains(package_id) {
+                continue;
+            }
+            if ctx.opts.dry_run {
+                ctx.status(&format!(
+                    "Would release {} ({})",
+                    package_id,
+                    provider.source()
+                ));
+                continue;
+            }
+            provider.unhold(package_id).await?;
+        }
+        ctx.status(&format!("Released {}", package_id));
+    }
+
+    if !ctx.opts.dry_run {
+        config.save()?;
+    }
+    Ok(())
+}
+
+async fn list(ctx: &Context) -> Result<()> {
+    let mut holds: Vec<Hold> = ctx
+        .config
+        .updates
+        .holds
+        .iter()
+        .map(|package_id| Hold {
+            package_id: package_id.clone(),
+            held_by: "fluxara".to_string(),
+        })
+        .collect();
+
+    for provider in ctx.providers()? {
+        match provider.list_holds().await {
+            Ok(held) => holds.extend(held.into_iter().map(|package_id| Hold {
+                package_id,
+                held_by: provider.source().to_string(),
+            })),
+            Err(e) => ctx.warn(&format!("{} hold listing failed: {}", provider.source(), e)),
+        }
+    }
+
+    ctx.emit_list(&holds, "No packages are held.")
+}
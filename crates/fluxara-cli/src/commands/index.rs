@@ -0,0 +1,26 @@
+use super::Context;
+use crate::cli::IndexCommand;
+use anyhow::Result;
+use fluxara_index::PackageIndex;
+
+pub async fn run(ctx: &Context, command: IndexCommand) -> Result<()> {
+    let mut index = PackageIndex::open()?;
+
+    match command {
+        IndexCommand::Refresh => {
+            if ctx.opts.dry_run {
+                for provider in ctx.providers()? {
+                    ctx.status(&format!("Would index {}", provider.source()));
+                }
+                return Ok(());
+            }
+
+            ctx.status("Refreshing package index...");
+            for (source, count) in index.refresh(&ctx.registry, ctx.opts.source).await? {
+                ctx.verbose(&format!("Indexed {} {} packages", count, source));
+            }
+            ctx.emit_list(&index.sources()?, "No package source could be indexed.")
+        }
+        IndexCommand::Status => ctx.emit_list(&index.sources()?, "The package index is empty."),
+    }
+}
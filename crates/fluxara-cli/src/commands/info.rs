@@ -0,0 +1,141 @@
+use super::Context;
+use crate::output::{self, CliError, OutputFormat};
+use anyhow::Result;
+use fluxara_appstream::{AppStreamManager, OdrsClient};
+use fluxara_core::{units, AppRating, PackageDetails, PackageSource};
+use serde::Serialize;
+
+/// Everything known about one application across all sources
+#[derive(Debug, Serialize)]
+pub struct PackageComparison {
+    pub query: String,
+    pub name: String,
+    pub summary: Option<String>,
+    pub developer: Option<String>,
+    pub rating: Option<AppRating>,
+    pub sources: Vec<PackageDetails>,
+}
+
+pub async fn run(ctx: &Context, query: &str) -> Result<()> {
+    let mut sources = Vec::new();
+
+    for provider in ctx.providers()? {
+        ctx.verbose(&format!("Looking up {} in {}", query, provider.source()));
+
+        let mut details = provider.details(query).await.unwrap_or(None);
+
+        // The query may be a display name rather than this source's package id
+        if details.is_none() {
+            if let Ok(found) = provider.search(query).await {
+                if let Some(package) = found
+                    .iter()
+                    .find(|p| p.id == query || p.name.eq_ignore_ascii_case(query))
+                {
+                    details = provider.details(&package.id).await.unwrap_or(None);
+                }
+            }
+        }
+
+        sources.extend(details);
+    }
+
+    if sources.is_empty() {
+        return Err(CliError::NotFound(format!("Package not found: {}", query)).into());
+    }
+
+    // AppStream and ODRS key applications by their component id: the Flatpak id,
+    // or the component a distribution package ships
+    let appstream = AppStreamManager::new();
+    let app_id = sources
+        .iter()
+        .find(|d| d.source == PackageSource::Flatpak)
+        .map(|d| d.id.clone())
+        .or_else(|| {
+            sources.iter().find_map(|d| {
+                let component = appstream.catalog().find_by_package(d.source, &d.id)?;
+                Some(component.id.clone())
+            })
+        })
+        .unwrap_or_else(|| query.to_string());
+
+    let metadata = appstream.get_app_metadata(&app_id).ok();
+    let developer = appstream
+        .get_developer_info(&app_id)
+        .ok()
+        .map(|info| info.name);
+    // The ODRS client blocks on network requests
+    let odrs = OdrsClient::from_config(&ctx.config);
+    let rating_app_id = app_id.clone();
+    let rating = tokio::task::spawn_blocking(move || odrs.get_rating(&rating_app_id))
+        .await?
+        .ok()
+        .filter(|rating| rating.total_reviews > 0);
+
+    let comparison = PackageComparison {
+        query: query.to_string(),
+        name: metadata
+            .as_ref()
+            .map(|m| m.name.clone())
+            .unwrap_or_else(|| sources[0].name.clone()),
+        summary: metadata.and_then(|m| m.summary),
+        developer,
+        rating,
+        sources,
+    };
+
+    match ctx.format() {
+        OutputFormat::Table => print_comparison(&comparison),
+        OutputFormat::Plain => ctx.emit_list(&comparison.sources, "")?,
+        OutputFormat::Json | OutputFormat::Jsonl => ctx.emit_value(&comparison)?,
+    }
+
+    Ok(())
+}
+
+fn print_comparison(comparison: &PackageComparison) {
+    match &comparison.summary {
+        Some(summary) => println!("{} - {}", comparison.name, summary),
+        None => println!("{}", comparison.name),
+    }
+    if let Some(developer) = &comparison.developer {
+        println!("Developer: {}", developer);
+    }
+    match &comparison.rating {
+        Some(rating) => println!(
+            "Rating:    {:.1}/5 ({} reviews)",
+            rating.average_rating, rating.total_reviews
+        ),
+        None => println!("Rating:    no reviews"),
+    }
+    println!();
+
+    let sources = &comparison.sources;
+    let mut headers = vec!["FIELD".to_string()];
+    headers.extend(sources.iter().map(|d| d.source.as_str().to_uppercase()));
+
+    let field = |label: &str, value: &dyn Fn(&PackageDetails) -> String| {
+        let mut row = vec![label.to_string()];
+        row.extend(sources.iter().map(value));
+        row
+    };
+
+    let rows = vec![
+        field("ID", &|d| d.id.clone()),
+        field("Version", &|d| {
+            d.version.clone().unwrap_or_else(|| "unknown".to_string())
+        }),
+        field("Size", &|d| {
+            d.size_bytes.map(units::format_size).unwrap_or_default()
+        }),
+        field("License", &|d| d.license.clone().unwrap_or_default()),
+        field("Confinement", &|d| d.confinement.as_str().to_string()),
+        field("Permissions", &|d| d.permissions.join(", ")),
+        field("Channel", &|d| d.channel.clone().unwrap_or_default()),
+        field("Installed", &|d| {
+            if d.installed { "yes" } else { "no" }.to_string()
+        }),
+    ];
+
+    let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+    output::print_table(&headers, rows);
+}
@@ -0,0 +1,57 @@
+use super::{helper_error, Context};
+use crate::cli::MaintenanceCommand;
+use crate::output::OutputFormat;
+use anyhow::Result;
+use fluxara_maintenance::MaintenanceManager;
+
+pub async fn run(ctx: &Context, command: MaintenanceCommand) -> Result<()> {
+    let manager = MaintenanceManager::new();
+
+    match command {
+        MaintenanceCommand::CleanCache => {
+            if ctx.opts.dry_run {
+                ctx.status("Would clean package caches");
+                return Ok(());
+            }
+            // Package caches belong to root; ask the helper unless we are root
+            let freed = if fluxara_polkit_agent::is_privileged() {
+                manager.clean_cache()?
+            } else {
+                ctx.helper()
+                    .await?
+                    .clean_cache(ctx.helper_progress())
+                    .await
+                    .map_err(helper_error)?
+            };
+            match ctx.format() {
+                OutputFormat::Table => println!("Freed {} bytes", freed),
+                OutputFormat::Plain => println!("{}", freed),
+                _ => ctx.emit_value(&serde_json::json!({ "bytes_freed": freed }))?,
+            }
+            Ok(())
+        }
+        MaintenanceCommand::Orphans => {
+            if ctx.opts.dry_run {
+                ctx.status("Would remove orphaned packages");
+                return Ok(());
+            }
+            let removed = manager.remove_orphans()?;
+            match ctx.format() {
+                OutputFormat::Table if removed.is_empty() => {
+                    println!("No orphaned packages found.")
+                }
+                OutputFormat::Table | OutputFormat::Plain => {
+                    for package in &removed {
+                        println!("{}", package);
+                    }
+                }
+                _ => ctx.emit_value(&removed)?,
+            }
+            Ok(())
+        }
+        MaintenanceCommand::Conflicts => {
+            let conflicts = manager.detect_conflicts()?;
+            ctx.emit_list(&conflicts, "No conflicts detected.")
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::{packages, repo, Context};
+use crate::output::Tabular;
+use anyhow::{Context as _, Result};
+use fluxara_core::manifest::{ManifestPackage, ManifestRemote};
+use fluxara_core::{Manifest, OperationKind, PackageSource, Transaction};
+use fluxara_polkit_agent::RepoEdit;
+use fluxara_provider_apt::AptProvider;
+use fluxara_provider_flatpak::FlatpakProvider;
+use serde::Serialize;
+use std::path::Path;
+
+/// One step needed to converge the machine with a manifest
+#[derive(Debug, Serialize)]
+pub struct ManifestChange {
+    pub action: &'static str,
+    pub source: PackageSource,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl Tabular for ManifestChange {
+    const HEADERS: &'static [&'static str] = &["ACTION", "SOURCE", "TARGET", "VERSION"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.action.to_string(),
+            self.source.to_string(),
+            self.target.clone(),
+            self.version.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+pub async fn export(ctx: &Context, file: Option<&Path>, pin: bool) -> Result<()> {
+    let mut manifest = Manifest::default();
+
+    for provider in ctx.providers()? {
+        let source = provider.source();
+        ctx.verbose(&format!("Exporting {} packages", source));
+
+        let installed = match provider.list_explicit().await {
+            Ok(installed) if !installed.is_empty() => installed,
+            Ok(_) => continue,
+            Err(e) => {
+                ctx.warn(&format!("{} listing failed: {}", source, e));
+                continue;
+            }
+        };
+
+        let section = manifest.section_mut(source);
+        section.packages = installed
+            .into_iter()
+            .map(|p| match (pin, p.version) {
+                (true, Some(version)) => ManifestPackage::Pinned { id: p.id, version },
+                _ => ManifestPackage::Id(p.id),
+            })
+            .collect();
+
+        match source {
+            PackageSource::Flatpak => {
+                section.remotes = FlatpakProvider::with_scope(ctx.config.install.flatpak_scope)
+                    .list_remotes()?
+                    .into_iter()
+                    .filter(|remote| remote.enabled)
+                    .map(|remote| ManifestRemote {
+                        name: remote.name,
+                        url: remote.url,
+                    })
+                    .collect();
+            }
+            PackageSource::Apt => section.repositories = AptProvider::new().list_repositories()?,
+            _ => {}
+        }
+    }
+
+    match file {
+        Some(path) if ctx.opts.dry_run => {
+            ctx.status(&format!("Would write manifest to {}", path.display()));
+        }
+        Some(path) => {
+            std::fs::write(path, manifest.to_toml()?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            ctx.status(&format!("Manifest written to {}", path.display()));
+        }
+        None if ctx.format().is_machine() => ctx.emit_value(&manifest)?,
+        None => print!("{}", manifest.to_toml()?),
+    }
+
+    Ok(())
+}
+
+pub async fn apply(ctx: &Context, path: &Path, remove_extras: bool) -> Result<()> {
+    let manifest = Manifest::load(path)?;
+
+    let mut remotes: Vec<ManifestRemote> = Vec::new();
+    let mut repositories: Vec<String> = Vec::new();
+    let mut transaction = Transaction::new();
+
+    for source in PackageSource::ALL.iter().copied() {
+        let Some(section) = manifest.section(source) else {
+            continue;
+        };
+        if ctx.opts.source.is_some_and(|s| s != source) {
+            continue;
+        }
+        let Some(provider) = ctx.registry.get(source) else {
+            ctx.warn(&format!(
+                "Package source {} is not available; skipping {} packages",
+                source,
+                section.packages.len()
+            ));
+            continue;
+        };
+
+        match source {
+            PackageSource::Flatpak => {
+                let existing =
+                    FlatpakProvider::with_scope(ctx.config.install.flatpak_scope).list_remotes()?;
+                remotes.extend(
+                    section
+                        .remotes
+                        .iter()
+                        .filter(|remote| !existing.iter().any(|r| r.name == remote.name))
+                        .cloned(),
+                );
+            }
+            PackageSource::Apt => {
+                let existing = AptProvider::new().list_repositories()?;
+                repositories.extend(
+                    section
+                        .repositories
+                        .iter()
+                        .filter(|entry| !existing.contains(entry))
+                        .cloned(),
+                );
+            }
+            _ => {}
+        }
+
+        ctx.verbose(&format!("Comparing {} packages", source));
+        let installed = provider.list_installed().await?;
+        let explicit = match remove_extras {
+            true => Some(provider.list_explicit().await?),
+            false => None,
+        };
+        manifest.plan(source, &installed, explicit.as_deref(), &mut transaction);
+    }
+
+    let mut changes: Vec<ManifestChange> = Vec::new();
+    changes.extend(remotes.iter().map(|remote| ManifestChange {
+        action: "add-remote",
+        source: PackageSource::Flatpak,
+        target: format!("{} {}", remote.name, remote.url),
+        version: None,
+    }));
+    changes.extend(repositories.iter().map(|entry| ManifestChange {
+        action: "add-repository",
+        source: PackageSource::Apt,
+        target: entry.clone(),
+        version: None,
+    }));
+    changes.extend(transaction.operations().iter().map(|op| ManifestChange {
+        action: match op.kind {
+            OperationKind::Downgrade => "install-version",
+            kind => kind.as_str(),
+        },
+        source: op.source,
+        target: op.package_id.clone(),
+        version: op.version.clone(),
+    }));
+
+    if changes.is_empty() || ctx.opts.dry_run {
+        return ctx.emit_list(&changes, "This machine already matches the manifest.");
+    }
+
+    ctx.confirm(&format!(
+        "Apply {} changes from {}?",
+        changes.len(),
+        path.display()
+    ))?;
+
+    // System repositories are changed by the helper unless we run as root
+    for remote in remotes {
+        let ManifestRemote { name, url } = remote;
+        repo::edit(ctx, &RepoEdit::AddFlatpakRemote { name, url }).await?;
+    }
+    for entry in repositories {
+        repo::edit(ctx, &RepoEdit::AddAptSource(entry)).await?;
+    }
+
+    if transaction.is_empty() {
+        return Ok(());
+    }
+    packages::execute(ctx, &transaction, None).await
+}
@@ -0,0 +1,247 @@
+mod audit;
+mod build;
+mod changelog;
+mod config;
+mod convert;
+mod driver;
+mod history;
+mod hold;
+mod index;
+mod info;
+mod maintenance;
+mod manifest;
+mod packages;
+mod repo;
+
+use crate::cli::{Cli, Command, GlobalOpts};
+use crate::output::{self, CliError, OutputFormat, Tabular};
+use anyhow::{Context as _, Result};
+use clap::CommandFactory;
+use fluxara_core::config::ParentalConfig;
+use fluxara_core::{Config, PackageManager, ProviderRegistry};
+use fluxara_polkit_agent::{HelperClient, HelperError, Progress};
+use fluxara_provider_apt::AptProvider;
+use fluxara_provider_flatpak::FlatpakProvider;
+use fluxara_provider_pacman::PacmanProvider;
+use fluxara_provider_snap::SnapProvider;
+use serde::Serialize;
+use std::io::{BufRead, IsTerminal, Write};
+
+/// State shared by every command: global flags, configuration and providers
+pub struct Context {
+    pub opts: GlobalOpts,
+    pub config: Config,
+    /// System-wide parental controls policy
+    pub parental: ParentalConfig,
+    pub registry: ProviderRegistry,
+}
+
+impl Context {
+    pub fn new(opts: GlobalOpts) -> Result<Self> {
+        let config = Config::load().context("Failed to load configuration")?;
+        let parental =
+            ParentalConfig::system().context("Failed to load the parental controls policy")?;
+
+        // Changes needing root go through the privileged helper unless
+        // fluxara itself runs as root
+        let scope = config.install.flatpak_scope;
+        let providers: [Box<dyn PackageManager>; 4] = [
+            Box::new(FlatpakProvider::with_scope(scope)),
+            Box::new(AptProvider::new()),
+            Box::new(PacmanProvider::new()),
+            Box::new(SnapProvider::new()),
+        ];
+        let mut registry = ProviderRegistry::new();
+        for provider in providers {
+            registry.register(fluxara_polkit_agent::route(provider, scope));
+        }
+
+        Ok(Self {
+            opts,
+            config,
+            parental,
+            registry,
+        })
+    }
+
+    /// Available providers, restricted by `--source` when given
+    pub fn providers(&self) -> Result<Vec<&dyn PackageManager>> {
+        let providers = self.registry.select(self.opts.source);
+        if providers.is_empty() {
+            let message = match self.opts.source {
+                Some(source) => format!("Package source {} is not available", source),
+                None => "No supported package manager found".to_string(),
+            };
+            return Err(CliError::Unavailable(message).into());
+        }
+        Ok(providers)
+    }
+
+    /// Client of the privileged helper, for changes fluxara cannot make itself
+    pub async fn helper(&self) -> Result<HelperClient> {
+        HelperClient::system().await.map_err(helper_error)
+    }
+
+    /// Show helper progress with `--verbose`
+    pub fn helper_progress(&self) -> impl FnMut(Progress) + '_ {
+        |progress| self.verbose(&progress.message)
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.opts.format()
+    }
+
+    /// Print a progress message in `table` output unless `--quiet` is set
+    pub fn status(&self, message: &str) {
+        if !self.opts.quiet && self.format() == OutputFormat::Table {
+            println!("{}", message);
+        }
+    }
+
+    /// Print a message only with `--verbose`
+    pub fn verbose(&self, message: &str) {
+        if self.opts.verbose {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Print a non-fatal problem to stderr unless `--quiet` is set
+    pub fn warn(&self, message: &str) {
+        if !self.opts.quiet {
+            eprintln!("Warning: {}", message);
+        }
+    }
+
+    pub fn emit<T: Serialize + Tabular>(&self, item: &T) -> Result<()> {
+        output::emit(self.format(), item)
+    }
+
+    pub fn emit_list<T: Serialize + Tabular>(
+        &self,
+        items: &[T],
+        empty_message: &str,
+    ) -> Result<()> {
+        output::emit_list(self.format(), items, empty_message)
+    }
+
+    pub fn emit_value<T: Serialize + ?Sized>(&self, value: &T) -> Result<()> {
+        output::emit_value(self.format(), value)
+    }
+
+    /// Whether the user can be asked questions on this terminal
+    pub fn is_interactive(&self) -> bool {
+        !self.opts.yes && self.format() == OutputFormat::Table && std::io::stdin().is_terminal()
+    }
+
+    /// Let the user pick one of several options; returns its index
+    pub fn choose(&self, prompt: &str, options: &[String]) -> Result<usize> {
+        eprintln!("{}", prompt);
+        for (index, option) in options.iter().enumerate() {
+            eprintln!("  {}) {}", index + 1, option);
+        }
+
+        loop {
+            eprint!("Select [1-{}] (default 1, q to cancel): ", options.len());
+            std::io::stderr().flush()?;
+
+            let mut answer = String::new();
+            if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+                return Err(CliError::Cancelled("Cancelled by user".to_string()).into());
+            }
+
+            match answer.trim() {
+                "" => return Ok(0),
+                "q" | "Q" => {
+                    return Err(CliError::Cancelled("Cancelled by user".to_string()).into())
+                }
+                choice => match choice.parse::<usize>() {
+                    Ok(n) if (1..=options.len()).contains(&n) => return Ok(n - 1),
+                    _ => eprintln!("Invalid choice: {}", choice),
+                },
+            }
+        }
+    }
+
+    /// Ask the user to confirm an action; `--yes` always confirms
+    pub fn confirm(&self, prompt: &str) -> Result<()> {
+        if self.opts.yes {
+            return Ok(());
+        }
+
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::Cancelled(
+                "Refusing to prompt without a terminal, pass --yes to confirm".to_string(),
+            )
+            .into());
+        }
+
+        eprint!("{} [y/N] ", prompt);
+        std::io::stderr().flush()?;
+
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+        if matches!(answer.trim(), "y" | "Y" | "yes" | "Yes") {
+            Ok(())
+        } else {
+            Err(CliError::Cancelled("Cancelled by user".to_string()).into())
+        }
+    }
+}
+
+/// A helper failure, with denials and an unreachable helper mapped to their
+/// exit codes
+pub fn helper_error(error: HelperError) -> anyhow::Error {
+    match error {
+        HelperError::Denied(message) => CliError::PermissionDenied(message).into(),
+        HelperError::Unavailable(_) => CliError::Unavailable(error.to_string()).into(),
+        other => other.into(),
+    }
+}
+
+pub async fn run(cli: Cli) -> Result<()> {
+    if let Command::Completions { shell } = cli.command {
+        let mut command = Cli::command();
+        clap_complete::generate(shell, &mut command, "fluxara", &mut std::io::stdout());
+        return Ok(());
+    }
+
+    let ctx = Context::new(cli.global)?;
+
+    match cli.command {
+        Command::Search {
+            query,
+            live,
+            ungrouped,
+        } => packages::search(&ctx, &query, live, ungrouped).await,
+        Command::Info { package } => info::run(&ctx, &package).await,
+        Command::Install { packages, force } => packages::install(&ctx, &packages, force).await,
+        Command::Remove { packages } => packages::remove(&ctx, &packages).await,
+        Command::Update {
+            packages,
+            changelog: true,
+        } => changelog::run(&ctx, &packages).await,
+        Command::Update { packages, .. } if packages.is_empty() => {
+            packages::list_updates(&ctx).await
+        }
+        Command::Update { packages, .. } => packages::update(&ctx, &packages).await,
+        Command::List => packages::list(&ctx).await,
+        Command::Upgrade => packages::upgrade(&ctx).await,
+        Command::Hold { packages } => hold::hold(&ctx, &packages).await,
+        Command::Unhold { packages } => hold::unhold(&ctx, &packages).await,
+        Command::Export { file, pin } => manifest::export(&ctx, file.as_deref(), pin).await,
+        Command::Apply {
+            manifest,
+            remove_extras,
+        } => manifest::apply(&ctx, &manifest, remove_extras).await,
+        Command::History(command) => history::run(&ctx, command).await,
+        Command::Index(command) => index::run(&ctx, command).await,
+        Command::Repo(command) => repo::run(&ctx, command).await,
+        Command::Driver(command) => driver::run(&ctx, command).await,
+        Command::Maintenance(command) => maintenance::run(&ctx, command).await,
+        Command::Audit(command) => audit::run(&ctx, command),
+        Command::Convert { path, to } => convert::run(&ctx, &path, &to),
+        Command::Build { url } => build::run(&ctx, &url).await,
+        Command::Config(command) => config::run(&ctx, command),
+        Command::Completions { .. } => unreachable!("handled above"),
+    }
+}
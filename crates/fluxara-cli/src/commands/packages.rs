@@ -0,0 +1,664 @@
+use super::Context;
+use crate::output::{CliError, OperationResult, OperationStatus};
+use anyhow::Result;
+use fluxara_appstream::AppStreamManager;
+use fluxara_core::{
+    History, OperationKind, Origin, Package, PackageManager, PackageSource, Transaction, UpdateInfo,
+};
+use fluxara_index::PackageIndex;
+use fluxara_media::MediaCache;
+
+/// Most results returned from the local index
+const INDEX_SEARCH_LIMIT: usize = 100;
+
+pub async fn search(ctx: &Context, query: &str, live: bool, ungrouped: bool) -> Result<()> {
+    ctx.status(&format!("Searching for: {}", query));
+
+    let mut providers = ctx.providers()?;
+    let mut packages = Vec::new();
+    let mut resolver = None;
+
+    if ctx.config.index.enabled && !live {
+        match PackageIndex::open() {
+            Ok(index) => {
+                let indexed = index_sources(ctx, &index, &providers)?;
+                if !indexed.is_empty() {
+                    packages = index.search(query, &indexed, INDEX_SEARCH_LIMIT)?;
+                    providers.retain(|p| !indexed.contains(&p.source()));
+                    // Saves parsing the AppStream catalogs on every search
+                    resolver = Some(index.identity_resolver()?);
+                }
+            }
+            Err(e) => ctx.verbose(&format!("Package index unavailable: {:#}", e)),
+        }
+    }
+
+    // Sources that are not indexed are searched live
+    for provider in providers {
+        ctx.verbose(&format!("Searching {}", provider.source()));
+        match provider.search(query).await {
+            Ok(found) => packages.extend(found),
+            Err(e) => ctx.warn(&format!("{} search failed: {}", provider.source(), e)),
+        }
+    }
+
+    let appstream = AppStreamManager::new();
+    if ctx.parental.applies() {
+        appstream.retain_allowed(&ctx.parental, &mut packages);
+    }
+
+    // Icons come from the AppStream catalogs, which index searches skip parsing
+    if resolver.is_none() {
+        match MediaCache::from_config(&ctx.config) {
+            Ok(media) => media.fill_icon_urls(
+                appstream.catalog(),
+                &mut packages,
+                ctx.config.media.icon_size,
+            ),
+            Err(e) => ctx.verbose(&format!("Media cache unavailable: {:#}", e)),
+        }
+    }
+
+    if ungrouped {
+        return ctx.emit_list(&packages, "No packages found.");
+    }
+
+    // The same app is often offered by several sources under different ids
+    let resolver = resolver.unwrap_or_else(|| appstream.identity_resolver());
+    let apps = resolver.group(packages);
+    ctx.emit_list(&apps, "No packages found.")
+}
+
+/// Sources of `providers` that the index can answer for
+fn index_sources(
+    ctx: &Context,
+    index: &PackageIndex,
+    providers: &[&dyn PackageManager],
+) -> Result<Vec<PackageSource>> {
+    let max_age = chrono::Duration::hours(ctx.config.index.max_age_hours as i64);
+    let mut indexed = Vec::new();
+
+    for provider in providers {
+        let Some(status) = index.status(provider.source())? else {
+            continue;
+        };
+        if chrono::Utc::now() - status.refreshed_at > max_age {
+            ctx.verbose(&format!(
+                "{} index is from {}; run `fluxara index refresh` to update it",
+                status.source,
+                status.refreshed_at.format("%Y-%m-%d %H:%M")
+            ));
+        }
+        indexed.push(status.source);
+    }
+
+    Ok(indexed)
+}
+
+pub async fn install(ctx: &Context, names: &[String], force: bool) -> Result<()> {
+    let mut transaction = Transaction::new();
+    for name in names {
+        let candidates = find_candidates(ctx, name).await?;
+        let package = choose_source(ctx, name, &candidates)?;
+        ensure_single_source(ctx, name, &package, &candidates, force).await?;
+        transaction.add(OperationKind::Install, &package.id, package.source);
+    }
+    check_content(ctx, &transaction)?;
+
+    if ctx.opts.dry_run {
+        let mut plans = Vec::new();
+        for operation in transaction.operations() {
+            if let Some(provider) = ctx.registry.get(operation.source) {
+                plans.push(provider.get_install_plan(&operation.package_id).await?);
+            }
+        }
+        return ctx.emit_list(&plans, "");
+    }
+
+    ctx.confirm(&format!("Install {}?", describe(&transaction)))?;
+    execute(ctx, &transaction, None).await
+}
+
+pub async fn remove(ctx: &Context, package_ids: &[String]) -> Result<()> {
+    let transaction = resolve_installed(ctx, OperationKind::Remove, package_ids).await?;
+
+    if ctx.opts.dry_run {
+        return ctx.emit_list(&dry_run(&transaction), "");
+    }
+
+    ctx.confirm(&format!("Remove {}?", describe(&transaction)))?;
+    execute(ctx, &transaction, None).await
+}
+
+pub async fn update(ctx: &Context, package_ids: &[String]) -> Result<()> {
+    let (held, package_ids): (Vec<String>, Vec<String>) = package_ids
+        .iter()
+        .cloned()
+        .partition(|id| ctx.config.updates.is_held(id));
+    for id in &held {
+        ctx.warn(&format!(
+            "{} is held; run `fluxara unhold {}` to update it",
+            id, id
+        ));
+    }
+    if package_ids.is_empty() {
+        return Err(CliError::Conflict("All requested packages are held".to_string()).into());
+    }
+
+    let transaction = resolve_installed(ctx, OperationKind::Update, &package_ids).await?;
+
+    if ctx.opts.dry_run {
+        return ctx.emit_list(&dry_run(&transaction), "");
+    }
+
+    execute(ctx, &transaction, None).await
+}
+
+pub async fn list_updates(ctx: &Context) -> Result<()> {
+    let updates = collect_updates(ctx).await?;
+    ctx.emit_list(&updates, "All packages are up to date.")
+}
+
+pub async fn upgrade(ctx: &Context) -> Result<()> {
+    let updates = collect_updates(ctx).await?;
+
+    if updates.is_empty() || ctx.opts.dry_run {
+        return ctx.emit_list(&updates, "All packages are up to date.");
+    }
+
+    ctx.confirm(&format!("Apply {} updates?", updates.len()))?;
+
+    let mut transaction = Transaction::new();
+    for update in &updates {
+        transaction.add(OperationKind::Update, &update.package_id, update.source);
+    }
+    execute(ctx, &transaction, None).await
+}
+
+pub async fn list(ctx: &Context) -> Result<()> {
+    ctx.status("Listing installed packages...");
+
+    let mut packages = Vec::new();
+    for provider in ctx.providers()? {
+        match provider.list_installed().await {
+            Ok(installed) => packages.extend(installed),
+            Err(e) => ctx.warn(&format!("{} listing failed: {}", provider.source(), e)),
+        }
+    }
+
+    ctx.emit_list(&packages, "No packages installed.")
+}
+
+pub async fn collect_updates(ctx: &Context) -> Result<Vec<UpdateInfo>> {
+    let mut updates = Vec::new();
+    for provider in ctx.providers()? {
+        ctx.verbose(&format!("Checking {} for updates", provider.source()));
+        match provider.list_updates().await {
+            Ok(found) => updates.extend(found),
+            Err(e) => ctx.warn(&format!("{} update check failed: {}", provider.source(), e)),
+        }
+    }
+    ctx.config.updates.retain_unheld(&mut updates);
+    Ok(updates)
+}
+
+/// Packages matching `name` exactly by id or display name, at most one per source
+async fn find_candidates(ctx: &Context, name: &str) -> Result<Vec<Package>> {
+    let mut candidates: Vec<Package> = Vec::new();
+
+    for provider in ctx.providers()? {
+        ctx.verbose(&format!("Resolving {} in {}", name, provider.source()));
+        let Ok(found) = provider.search(name).await else {
+            continue;
+        };
+        if let Some(package) = found
+            .into_iter()
+            .find(|p| p.id == name || p.name.eq_ignore_ascii_case(name))
+        {
+            candidates.push(package);
+        }
+    }
+
+    // An explicit --source is trusted even when its search does not list the id
+    if let (true, Some(source)) = (candidates.is_empty(), ctx.opts.source) {
+        candidates.push(Package {
+            id: name.to_string(),
+            name: name.to_string(),
+            version: None,
+            description: None,
+            icon_url: None,
+            source,
+            installed: false,
+        });
+    }
+
+    if candidates.is_empty() {
+        return Err(CliError::NotFound(format!("Package not found: {}", name)).into());
+    }
+
+    Ok(candidates)
+}
+
+/// Pick the source to install from, using the configured policy or asking the user
+fn choose_source(ctx: &Context, name: &str, candidates: &[Package]) -> Result<Package> {
+    let policy = &ctx.config.install;
+
+    let mut ranked: Vec<&Package> = candidates.iter().collect();
+    ranked.sort_by_key(|p| {
+        policy
+            .rank(name, p.source)
+            .min(policy.rank(&p.id, p.source))
+    });
+
+    let forced = policy
+        .override_for(name)
+        .or_else(|| candidates.iter().find_map(|p| policy.override_for(&p.id)));
+    if let Some(preference) = forced {
+        ranked.retain(|p| preference.matches(p.source));
+        if ranked.is_empty() {
+            return Err(CliError::NotFound(format!(
+                "{} is pinned to {:?} in the configuration, but that source does not offer it",
+                name, preference
+            ))
+            .into());
+        }
+    }
+
+    if ranked.len() > 1 && policy.prompt && ctx.is_interactive() {
+        let options: Vec<String> = ranked
+            .iter()
+            .map(|p| {
+                format!(
+                    "{:<8} {} ({})",
+                    p.source,
+                    p.id,
+                    p.version.as_deref().unwrap_or("unknown")
+                )
+            })
+            .collect();
+        let choice = ctx.choose(
+            &format!("{} is available from several sources:", name),
+            &options,
+        )?;
+        return Ok(ranked[choice].clone());
+    }
+
+    Ok(ranked[0].clone())
+}
+
+/// Refuse to install an app that is already installed from a different source
+async fn ensure_single_source(
+    ctx: &Context,
+    name: &str,
+    package: &Package,
+    candidates: &[Package],
+    force: bool,
+) -> Result<()> {
+    for provider in ctx.registry.available() {
+        if provider.source() == package.source {
+            continue;
+        }
+        let Ok(installed) = provider.list_installed().await else {
+            continue;
+        };
+        let existing = installed.iter().find(|p| {
+            p.id == name
+                || p.name.eq_ignore_ascii_case(name)
+                || candidates
+                    .iter()
+                    .any(|c| c.source == p.source && c.id == p.id)
+        });
+
+        if let Some(existing) = existing {
+            let message = format!(
+                "{} is already installed from {} as {}",
+                name, existing.source, existing.id
+            );
+            if !force {
+                return Err(CliError::Conflict(format!(
+                    "{}; pass --force to install it from {} as well",
+                    message, package.source
+                ))
+                .into());
+            }
+            ctx.warn(&message);
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the provider that has this package installed
+pub async fn find_installed<'a>(
+    ctx: &'a Context,
+    package_id: &str,
+) -> Result<&'a dyn PackageManager> {
+    for provider in ctx.providers()? {
+        if let Ok(installed) = provider.list_installed().await {
+            if installed.iter().any(|p| p.id == package_id) {
+                return Ok(provider);
+            }
+        }
+    }
+
+    Err(CliError::NotFound(format!("Package is not installed: {}", package_id)).into())
+}
+
+/// Build a transaction acting on installed packages, each from the source it was installed from
+async fn resolve_installed(
+    ctx: &Context,
+    kind: OperationKind,
+    package_ids: &[String],
+) -> Result<Transaction> {
+    let mut transaction = Transaction::new();
+    for package_id in package_ids {
+        let provider = find_installed(ctx, package_id).await?;
+        transaction.add(kind, package_id, provider.source());
+    }
+    Ok(transaction)
+}
+
+/// Summarise a transaction for a confirmation prompt, e.g. `a, b from flatpak and c from apt`
+pub fn describe(transaction: &Transaction) -> String {
+    transaction
+        .batches()
+        .iter()
+        .map(|batch| format!("{} from {}", batch.package_ids.join(", "), batch.source))
+        .collect::<Vec<_>>()
+        .join(" and ")
+}
+
+pub fn dry_run(transaction: &Transaction) -> Vec<OperationResult> {
+    transaction
+        .operations()
+        .iter()
+        .map(|operation| OperationResult {
+            action: operation.kind.as_str(),
+            package_id: operation.package_id.clone(),
+            source: operation.source,
+            status: OperationStatus::DryRun,
+            error: None,
+        })
+        .collect()
+}
+
+/// Refuse installs of apps whose content rating breaks the parental controls policy
+///
+/// The helper enforces the policy on installs needing root either way; this
+/// reports every blocked app up front, before anything is changed.
+fn check_content(ctx: &Context, transaction: &Transaction) -> Result<()> {
+    let policy = &ctx.parental;
+    if !policy.applies() {
+        return Ok(());
+    }
+
+    let appstream = AppStreamManager::new();
+    let blocked: Vec<String> = transaction
+        .operations()
+        .iter()
+        .filter(|op| matches!(op.kind, OperationKind::Install | OperationKind::Downgrade))
+        .filter_map(|op| {
+            let violations = appstream.content_violations(policy, op.source, &op.package_id);
+            (!violations.is_empty())
+                .then(|| format!("{} ({})", op.package_id, violations.join("; ")))
+        })
+        .collect();
+
+    if blocked.is_empty() {
+        return Ok(());
+    }
+    Err(CliError::PermissionDenied(format!(
+        "Blocked by parental controls: {}",
+        blocked.join(", ")
+    ))
+    .into())
+}
+
+/// Execute a transaction, record it in the history and report every operation
+///
+/// Fails the command if any operation failed or parental controls forbid an install.
+pub async fn execute(ctx: &Context, transaction: &Transaction, undo_of: Option<u64>) -> Result<()> {
+    check_content(ctx, transaction)?;
+
+    for batch in transaction.batches() {
+        let verb = match batch.kind {
+            OperationKind::Install => "Installing",
+            OperationKind::Remove => "Removing",
+            OperationKind::Update => "Updating",
+            OperationKind::Downgrade => "Downgrading",
+        };
+        ctx.status(&format!(
+            "{} from {}: {}",
+            verb,
+            batch.source,
+            batch.package_ids.join(", ")
+        ));
+    }
+
+    let history = History::open()?;
+    let origin = undo_of.map_or(Origin::User, Origin::Undo);
+    let (outcomes, entry) = transaction
+        .execute_recorded(&ctx.registry, &history, origin)
+        .await;
+    match entry {
+        Ok(entry) => ctx.verbose(&format!("Recorded as transaction {}", entry.id)),
+        Err(e) => ctx.warn(&format!("Failed to record transaction history: {:#}", e)),
+    }
+    // Searches show what is installed without waiting for the next refresh
+    if let Err(e) = PackageIndex::open().and_then(|mut index| index.record_outcomes(&outcomes)) {
+        ctx.verbose(&format!("Package index not updated: {:#}", e));
+    }
+
+    let results: Vec<OperationResult> = outcomes
+        .into_iter()
+        .map(|outcome| OperationResult {
+            action: outcome.operation.kind.as_str(),
+            package_id: outcome.operation.package_id,
+            source: outcome.operation.source,
+            status: match outcome.error {
+                Some(_) => OperationStatus::Failed,
+                None => OperationStatus::Ok,
+            },
+            error: outcome.error,
+        })
+        .collect();
+
+    if ctx.format().is_machine() {
+        ctx.emit_list(&results, "")?;
+    } else {
+        for result in &results {
+            match &result.error {
+                None => {
+                    let done = match result.action {
+                        "install" => "installed",
+                        "remove" => "removed",
+                        "downgrade" => "downgraded",
+                        _ => "updated",
+                    };
+                    ctx.status(&format!("Successfully {} {}", done, result.package_id));
+                }
+                Some(error) if results.len() > 1 => {
+                    ctx.warn(&format!("{} failed: {}", result.package_id, error));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    let failed: Vec<&OperationResult> = results
+        .iter()
+        .filter(|r| r.status == OperationStatus::Failed)
+        .collect();
+    match failed.as_slice() {
+        [] => Ok(()),
+        [only] if results.len() == 1 => {
+            Err(anyhow::anyhow!(only.error.clone().unwrap_or_default()))
+        }
+        _ => Err(CliError::PartialFailure {
+            failed: failed.len(),
+            total: results.len(),
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::GlobalOpts;
+    use crate::output::OutputFormat;
+    use async_trait::async_trait;
+    use fluxara_core::config::{ParentalConfig, SourcePreference};
+    use fluxara_core::{Config, InstallPlan, PackageDetails, ProviderRegistry};
+
+    /// Provider of `source` with `installed` as its only package
+    struct MockProvider {
+        source: PackageSource,
+        installed: Vec<Package>,
+    }
+
+    #[async_trait]
+    impl PackageManager for MockProvider {
+        fn source(&self) -> PackageSource {
+            self.source
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn search(&self, _query: &str) -> Result<Vec<Package>> {
+            Ok(vec![])
+        }
+
+        async fn install(&self, _package_id: &str) -> Result<()> {
+            anyhow::bail!("not needed")
+        }
+
+        async fn remove(&self, _package_id: &str) -> Result<()> {
+            anyhow::bail!("not needed")
+        }
+
+        async fn update(&self, _package_id: &str) -> Result<()> {
+            anyhow::bail!("not needed")
+        }
+
+        async fn list_installed(&self) -> Result<Vec<Package>> {
+            Ok(self.installed.clone())
+        }
+
+        async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
+            Ok(vec![])
+        }
+
+        async fn get_install_plan(&self, _package_id: &str) -> Result<InstallPlan> {
+            anyhow::bail!("not needed")
+        }
+
+        async fn details(&self, _package_id: &str) -> Result<Option<PackageDetails>> {
+            Ok(None)
+        }
+    }
+
+    fn package(id: &str, name: &str, source: PackageSource) -> Package {
+        Package {
+            id: id.to_string(),
+            name: name.to_string(),
+            version: Some("1.0".to_string()),
+            description: None,
+            icon_url: None,
+            source,
+            installed: false,
+        }
+    }
+
+    /// Context answering every prompt with yes, so nothing is asked
+    fn context(config: Config, providers: Vec<MockProvider>) -> Context {
+        let mut registry = ProviderRegistry::new();
+        for provider in providers {
+            registry.register(Box::new(provider));
+        }
+        Context {
+            opts: GlobalOpts {
+                source: None,
+                yes: true,
+                dry_run: false,
+                output: OutputFormat::Table,
+                json: false,
+                quiet: true,
+                verbose: false,
+            },
+            config,
+            parental: ParentalConfig::default(),
+            registry,
+        }
+    }
+
+    fn gimp() -> Vec<Package> {
+        vec![
+            package("gimp", "gimp", PackageSource::Snap),
+            package("gimp", "gimp", PackageSource::Apt),
+            package("org.gimp.GIMP", "GIMP", PackageSource::Flatpak),
+        ]
+    }
+
+    #[test]
+    fn source_follows_the_preference_order_without_asking() {
+        let ctx = context(Config::default(), vec![]);
+        let chosen = choose_source(&ctx, "gimp", &gimp()).unwrap();
+        assert_eq!(chosen.source, PackageSource::Flatpak);
+    }
+
+    #[test]
+    fn pinned_source_wins_or_fails() {
+        let mut config = Config::default();
+        config
+            .install
+            .overrides
+            .insert("gimp".to_string(), SourcePreference::Snap);
+        let ctx = context(config.clone(), vec![]);
+        let chosen = choose_source(&ctx, "gimp", &gimp()).unwrap();
+        assert_eq!(chosen.source, PackageSource::Snap);
+
+        config
+            .install
+            .overrides
+            .insert("gimp".to_string(), SourcePreference::Aur);
+        let ctx = context(config, vec![]);
+        let error = choose_source(&ctx, "gimp", &gimp()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<CliError>(),
+            Some(CliError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn installing_from_a_second_source_is_refused() {
+        let ctx = context(
+            Config::default(),
+            vec![MockProvider {
+                source: PackageSource::Flatpak,
+                installed: vec![package("org.gimp.GIMP", "GIMP", PackageSource::Flatpak)],
+            }],
+        );
+        let apt = package("gimp", "gimp", PackageSource::Apt);
+
+        let error = ensure_single_source(&ctx, "gimp", &apt, &gimp(), false)
+            .await
+            .unwrap_err();
+        match error.downcast_ref::<CliError>() {
+            Some(CliError::Conflict(message)) => {
+                assert!(message.contains("already installed from flatpak as org.gimp.GIMP"))
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+        ensure_single_source(&ctx, "gimp", &apt, &gimp(), true)
+            .await
+            .unwrap();
+        // Reinstalling from the same source is no conflict
+        let flatpak = package("org.gimp.GIMP", "GIMP", PackageSource::Flatpak);
+        ensure_single_source(&ctx, "gimp", &flatpak, &gimp(), false)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use super::{helper_error, Context};
+use crate::cli::RepoCommand;
+use crate::output::OutputFormat;
+use anyhow::Result;
+use fluxara_core::{PackageSource, RepoInfo};
+use fluxara_maintenance::MaintenanceManager;
+use fluxara_polkit_agent::RepoEdit;
+use fluxara_provider_apt::AptProvider;
+use fluxara_provider_flatpak::FlatpakProvider;
+
+pub async fn run(ctx: &Context, command: RepoCommand) -> Result<()> {
+    match command {
+        RepoCommand::List => list(ctx),
+        RepoCommand::Mirrors => mirrors(ctx).await,
+        RepoCommand::AddRemote { name, url } => {
+            edit(ctx, &RepoEdit::AddFlatpakRemote { name, url }).await
+        }
+        RepoCommand::RemoveRemote { name } => edit(ctx, &RepoEdit::RemoveFlatpakRemote(name)).await,
+        RepoCommand::AddSource { entry } => edit(ctx, &RepoEdit::AddAptSource(entry)).await,
+        RepoCommand::RemoveSource { entry } => edit(ctx, &RepoEdit::RemoveAptSource(entry)).await,
+    }
+}
+
+/// Apply a repository change, through the helper when it needs root
+pub async fn edit(ctx: &Context, edit: &RepoEdit) -> Result<()> {
+    let (source, description) = match edit {
+        RepoEdit::AddAptSource(entry) => {
+            (PackageSource::Apt, format!("Adding APT source {}", entry))
+        }
+        RepoEdit::RemoveAptSource(entry) => {
+            (PackageSource::Apt, format!("Removing APT source {}", entry))
+        }
+        RepoEdit::AddFlatpakRemote { name, .. } => (
+            PackageSource::Flatpak,
+            format!("Adding Flatpak remote {}", name),
+        ),
+        RepoEdit::RemoveFlatpakRemote(name) => (
+            PackageSource::Flatpak,
+            format!("Removing Flatpak remote {}", name),
+        ),
+    };
+    if ctx.opts.dry_run {
+        ctx.status(&format!("Would {}", description.to_lowercase()));
+        return Ok(());
+    }
+    ctx.status(&description);
+
+    let scope = ctx.config.install.flatpak_scope;
+    if fluxara_polkit_agent::requires_elevation(source, scope)
+        && !fluxara_polkit_agent::is_privileged()
+    {
+        return ctx
+            .helper()
+            .await?
+            .edit_repo(edit, ctx.helper_progress())
+            .await
+            .map_err(helper_error);
+    }
+
+    match edit {
+        RepoEdit::AddAptSource(entry) => AptProvider::new().add_repository(entry),
+        RepoEdit::RemoveAptSource(entry) => AptProvider::new().remove_repository(entry),
+        RepoEdit::AddFlatpakRemote { name, url } => {
+            FlatpakProvider::with_scope(scope).add_remote(name, url)
+        }
+        RepoEdit::RemoveFlatpakRemote(name) => {
+            FlatpakProvider::with_scope(scope).remove_remote(name)
+        }
+    }
+}
+
+fn list(ctx: &Context) -> Result<()> {
+    let mut repos: Vec<RepoInfo> = Vec::new();
+
+    if ctx.registry.get(PackageSource::Flatpak).is_some() {
+        repos.extend(FlatpakProvider::with_scope(ctx.config.install.flatpak_scope).list_remotes()?);
+    }
+
+    ctx.emit_list(&repos, "No repositories configured.")?;
+
+    if ctx.format() == OutputFormat::Table {
+        println!();
+        println!(
+            "Flathub beta: {}",
+            if ctx.config.repos.flathub.beta_enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        println!(
+            "AUR: {}",
+            if ctx.config.repos.aur.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+
+    Ok(())
+}
+
+async fn mirrors(ctx: &Context) -> Result<()> {
+    ctx.status("Testing mirror speeds...");
+
+    let best = MaintenanceManager::new().select_best_mirror().await?;
+    let mirror = RepoInfo {
+        name: "best".to_string(),
+        url: best,
+        enabled: true,
+        speed_mbps: None,
+    };
+
+    ctx.emit(&mirror)
+}
@@ -0,0 +1,791 @@
+//! Output formats for command results
+//!
+//! Every command hands its results to [`emit`] or [`emit_list`] so that the
+//! same data can be printed for humans (`table`, `plain`) or for scripts
+//! (`json`, `jsonl`). The JSON shapes are the serde representation of the
+//! `fluxara-core` models and are documented in `docs/cli-output.md`.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use fluxara_core::{
+    App, DriverInfo, DriverType, HistoryEntry, InstallPlan, OperationOutcome, Package,
+    PackageDetails, RepoInfo, UpdateInfo,
+};
+use fluxara_drivers::{DeviceType, HardwareDevice};
+use fluxara_index::SourceStatus;
+use fluxara_maintenance::PackageConflict;
+use fluxara_polkit_agent::AuditEntry;
+use serde::Serialize;
+use std::process::ExitCode;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns with a header row
+    #[default]
+    Table,
+    /// Tab-separated values without a header
+    Plain,
+    /// A single pretty-printed JSON document
+    Json,
+    /// One compact JSON object per line
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub fn is_machine(&self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Jsonl)
+    }
+}
+
+/// Errors with a dedicated process exit code
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Unavailable(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{failed} of {total} operations failed")]
+    PartialFailure { failed: usize, total: usize },
+    #[error("{0}")]
+    Cancelled(String),
+    #[error("{0}")]
+    Conflict(String),
+}
+
+/// Process exit codes; part of the documented CLI contract
+pub mod exit_code {
+    pub const SUCCESS: u8 = 0;
+    pub const FAILURE: u8 = 1;
+    pub const USAGE: u8 = 2;
+    pub const NOT_FOUND: u8 = 3;
+    pub const PERMISSION_DENIED: u8 = 4;
+    pub const PARTIAL_FAILURE: u8 = 5;
+    pub const CANCELLED: u8 = 6;
+    pub const UNAVAILABLE: u8 = 7;
+    pub const CONFLICT: u8 = 8;
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::NotFound(_) => exit_code::NOT_FOUND,
+            CliError::Unavailable(_) => exit_code::UNAVAILABLE,
+            CliError::PermissionDenied(_) => exit_code::PERMISSION_DENIED,
+            CliError::PartialFailure { .. } => exit_code::PARTIAL_FAILURE,
+            CliError::Cancelled(_) => exit_code::CANCELLED,
+            CliError::Conflict(_) => exit_code::CONFLICT,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            CliError::NotFound(_) => "not_found",
+            CliError::Unavailable(_) => "unavailable",
+            CliError::PermissionDenied(_) => "permission_denied",
+            CliError::PartialFailure { .. } => "partial_failure",
+            CliError::Cancelled(_) => "cancelled",
+            CliError::Conflict(_) => "conflict",
+        }
+    }
+}
+
+/// Print a command error to stderr and map it to an exit code
+pub fn report_error(format: OutputFormat, error: &anyhow::Error) -> ExitCode {
+    if format.is_machine() {
+        eprintln!("{}", error_json(error));
+    } else {
+        eprintln!("Error: {:#}", error);
+    }
+
+    ExitCode::from(error_exit_code(error))
+}
+
+/// Exit code of a failed command: the [`CliError`]'s own, or [`exit_code::FAILURE`]
+fn error_exit_code(error: &anyhow::Error) -> u8 {
+    error
+        .downcast_ref::<CliError>()
+        .map_or(exit_code::FAILURE, CliError::exit_code)
+}
+
+/// The error object printed in `json` and `jsonl` mode
+fn error_json(error: &anyhow::Error) -> serde_json::Value {
+    let kind = error
+        .downcast_ref::<CliError>()
+        .map_or("failure", CliError::kind);
+    serde_json::json!({
+        "error": {
+            "kind": kind,
+            "message": format!("{:#}", error),
+            "exit_code": error_exit_code(error),
+        }
+    })
+}
+
+/// Types that can be printed as a row in `table` and `plain` output
+pub trait Tabular {
+    const HEADERS: &'static [&'static str];
+
+    fn row(&self) -> Vec<String>;
+}
+
+/// Outcome of a single install/remove/update operation
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationResult {
+    pub action: &'static str,
+    pub package_id: String,
+    pub source: fluxara_core::PackageSource,
+    pub status: OperationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Ok,
+    Failed,
+    DryRun,
+}
+
+pub fn emit_list<T: Serialize + Tabular>(
+    format: OutputFormat,
+    items: &[T],
+    empty_message: &str,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Jsonl => {
+            for item in items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+        OutputFormat::Plain => {
+            for item in items {
+                println!("{}", item.row().join("\t"));
+            }
+        }
+        OutputFormat::Table => {
+            if items.is_empty() {
+                println!("{}", empty_message);
+            } else {
+                print_table(T::HEADERS, items.iter().map(Tabular::row).collect());
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn emit<T: Serialize + Tabular>(format: OutputFormat, item: &T) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Jsonl => println!("{}", serde_json::to_string(item)?),
+        OutputFormat::Plain => println!("{}", item.row().join("\t")),
+        OutputFormat::Table => {
+            let width = T::HEADERS.iter().map(|h| h.len()).max().unwrap_or(0);
+            for (header, value) in T::HEADERS.iter().zip(item.row()) {
+                println!("{:<width$}  {}", header, value, width = width);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Serialize an arbitrary value; `table` and `plain` fall back to `json`
+pub fn emit_value<T: Serialize + ?Sized>(format: OutputFormat, value: &T) -> Result<()> {
+    match format {
+        OutputFormat::Jsonl => println!("{}", serde_json::to_string(value)?),
+        _ => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+    Ok(())
+}
+
+pub fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!(
+        "{}",
+        format_row(headers.iter().map(|h| h.to_string()).collect())
+    );
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
+
+fn or_unknown(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "unknown".to_string())
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+impl Tabular for Package {
+    const HEADERS: &'static [&'static str] = &["ID", "NAME", "VERSION", "SOURCE", "INSTALLED"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            or_unknown(&self.version),
+            self.source.to_string(),
+            yes_no(self.installed),
+        ]
+    }
+}
+
+impl Tabular for App {
+    const HEADERS: &'static [&'static str] = &["ID", "NAME", "SOURCES", "INSTALLED"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.sources()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            yes_no(self.installed()),
+        ]
+    }
+}
+
+impl Tabular for PackageDetails {
+    const HEADERS: &'static [&'static str] = &[
+        "SOURCE",
+        "ID",
+        "VERSION",
+        "SIZE",
+        "LICENSE",
+        "CONFINEMENT",
+        "CHANNEL",
+        "INSTALLED",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.source.to_string(),
+            self.id.clone(),
+            or_unknown(&self.version),
+            self.size_bytes
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            self.license.clone().unwrap_or_default(),
+            self.confinement.as_str().to_string(),
+            self.channel.clone().unwrap_or_default(),
+            yes_no(self.installed),
+        ]
+    }
+}
+
+impl Tabular for UpdateInfo {
+    const HEADERS: &'static [&'static str] = &["ID", "CURRENT", "NEW", "SOURCE", "SIZE"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.package_id.clone(),
+            self.current_version.clone(),
+            self.new_version.clone(),
+            self.source.to_string(),
+            self.size_bytes
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl Tabular for InstallPlan {
+    const HEADERS: &'static [&'static str] =
+        &["ID", "SOURCE", "VERSION", "DEPENDENCIES", "REQUIRES_ROOT"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.package_id.clone(),
+            self.source.to_string(),
+            or_unknown(&self.version),
+            self.dependencies.join(","),
+            yes_no(self.requires_root),
+        ]
+    }
+}
+
+impl Tabular for RepoInfo {
+    const HEADERS: &'static [&'static str] = &["NAME", "URL", "ENABLED", "SPEED_MBPS"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.url.clone(),
+            yes_no(self.enabled),
+            self.speed_mbps
+                .map(|speed| format!("{:.2}", speed))
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl Tabular for DriverInfo {
+    const HEADERS: &'static [&'static str] = &["NAME", "TYPE", "RECOMMENDED", "DESCRIPTION"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            match self.driver_type {
+                DriverType::Open => "open",
+                DriverType::Proprietary => "proprietary",
+            }
+            .to_string(),
+            yes_no(self.recommended),
+            self.description.clone(),
+        ]
+    }
+}
+
+impl Tabular for HardwareDevice {
+    const HEADERS: &'static [&'static str] = &["TYPE", "DEVICE"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            match self.device_type {
+                DeviceType::Pci => "pci",
+                DeviceType::Usb => "usb",
+            }
+            .to_string(),
+            self.name.clone(),
+        ]
+    }
+}
+
+impl Tabular for PackageConflict {
+    const HEADERS: &'static [&'static str] = &["PACKAGE", "CONFLICTS_WITH", "REASON"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.package1.clone(),
+            self.package2.clone(),
+            self.reason.clone(),
+        ]
+    }
+}
+
+impl Tabular for OperationResult {
+    const HEADERS: &'static [&'static str] = &["ACTION", "ID", "SOURCE", "STATUS", "ERROR"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.action.to_string(),
+            self.package_id.clone(),
+            self.source.to_string(),
+            match self.status {
+                OperationStatus::Ok => "ok",
+                OperationStatus::Failed => "failed",
+                OperationStatus::DryRun => "dry-run",
+            }
+            .to_string(),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Tabular for HistoryEntry {
+    const HEADERS: &'static [&'static str] =
+        &["ID", "DATE", "USER", "ACTIONS", "PACKAGES", "STATUS"];
+
+    fn row(&self) -> Vec<String> {
+        let mut actions: Vec<String> = Vec::new();
+        for outcome in &self.operations {
+            let kind = outcome.operation.kind.as_str();
+            if !actions.iter().any(|a| a == kind) {
+                actions.push(kind.to_string());
+            }
+        }
+        let actions = actions.join(",");
+
+        let failed = self.failed();
+        vec![
+            self.id.to_string(),
+            self.timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            self.user.clone(),
+            match self.undo_of {
+                Some(id) => format!("undo {} ({})", id, actions),
+                None if self.unattended => format!("{} (unattended)", actions),
+                None => actions,
+            },
+            self.operations
+                .iter()
+                .map(|o| o.operation.package_id.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            match failed {
+                0 => "ok".to_string(),
+                n if n == self.operations.len() => "failed".to_string(),
+                n => format!("{} of {} failed", n, self.operations.len()),
+            },
+        ]
+    }
+}
+
+impl Tabular for OperationOutcome {
+    const HEADERS: &'static [&'static str] = &[
+        "ACTION",
+        "ID",
+        "SOURCE",
+        "OLD_VERSION",
+        "NEW_VERSION",
+        "STATUS",
+        "ERROR",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.operation.kind.to_string(),
+            self.operation.package_id.clone(),
+            self.operation.source.to_string(),
+            self.old_version.clone().unwrap_or_default(),
+            self.new_version.clone().unwrap_or_default(),
+            if self.succeeded() { "ok" } else { "failed" }.to_string(),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Tabular for SourceStatus {
+    const HEADERS: &'static [&'static str] = &["SOURCE", "PACKAGES", "REFRESHED"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.source.to_string(),
+            self.packages.to_string(),
+            self.refreshed_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+        ]
+    }
+}
+
+impl Tabular for AuditEntry {
+    const HEADERS: &'static [&'static str] = &[
+        "SEQ",
+        "DATE",
+        "UID",
+        "ACTION",
+        "OPERATION",
+        "ARGUMENTS",
+        "STATUS",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.seq.to_string(),
+            self.timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            self.uid.map(|uid| uid.to_string()).unwrap_or_default(),
+            self.action
+                .strip_prefix("com.fluxara.Store.")
+                .unwrap_or(&self.action)
+                .to_string(),
+            self.operation.clone(),
+            self.arguments.join(" "),
+            self.status.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use fluxara_core::{Confinement, Operation, OperationKind, PackageSource};
+    use fluxara_polkit_agent::AuditStatus;
+    use serde_json::{json, Value};
+
+    const SCHEMA: &str = include_str!("../../../docs/cli-output.md");
+
+    /// Field names of the table under `### {name}` in `docs/cli-output.md`
+    fn documented_fields(name: &str) -> Vec<String> {
+        let heading = format!("### {}\n", name);
+        let start = SCHEMA.find(&heading).expect("type is documented") + heading.len();
+        let section = &SCHEMA[start..];
+        let section = &section[..section.find("\n##").unwrap_or(section.len())];
+        let mut fields: Vec<String> = section
+            .lines()
+            .filter_map(|line| line.strip_prefix("| `"))
+            .filter_map(|line| line.split_once('`'))
+            .map(|(field, _)| field.to_string())
+            .collect();
+        fields.sort();
+        fields
+    }
+
+    fn serialized_fields(value: &Value) -> Vec<String> {
+        let mut fields: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        fields.sort();
+        fields
+    }
+
+    /// Serialize `value`, check its fields against the documentation and
+    /// return it for checks of the values
+    fn assert_documented(name: &str, value: impl Serialize) -> Value {
+        let value = serde_json::to_value(value).unwrap();
+        assert_eq!(
+            serialized_fields(&value),
+            documented_fields(name),
+            "{} does not match docs/cli-output.md",
+            name
+        );
+        value
+    }
+
+    fn package() -> Package {
+        Package {
+            id: "firefox".to_string(),
+            name: "Firefox".to_string(),
+            version: Some("128.0".to_string()),
+            description: None,
+            icon_url: None,
+            source: PackageSource::Apt,
+            installed: true,
+        }
+    }
+
+    #[test]
+    fn documented_types_serialize_as_documented() {
+        let value = assert_documented("Package", package());
+        assert_eq!(value["source"], "Apt");
+
+        assert_documented(
+            "App",
+            App {
+                id: "org.mozilla.firefox".to_string(),
+                name: "Firefox".to_string(),
+                summary: None,
+                icon_url: None,
+                packages: vec![package()],
+            },
+        );
+        assert_documented(
+            "UpdateInfo",
+            UpdateInfo {
+                package_id: "firefox".to_string(),
+                current_version: "127.0".to_string(),
+                new_version: "128.0".to_string(),
+                source: PackageSource::Flatpak,
+                size_bytes: Some(1024),
+                security: true,
+            },
+        );
+        assert_documented(
+            "InstallPlan",
+            InstallPlan {
+                package_id: "firefox".to_string(),
+                source: PackageSource::Apt,
+                version: None,
+                dependencies: vec!["libgtk-3-0".to_string()],
+                requires_root: true,
+            },
+        );
+        let value = assert_documented(
+            "OperationResult",
+            OperationResult {
+                action: "install",
+                package_id: "firefox".to_string(),
+                source: PackageSource::Snap,
+                status: OperationStatus::DryRun,
+                error: Some("failed".to_string()),
+            },
+        );
+        assert_eq!(value["status"], "dry_run");
+        assert_documented(
+            "SourceStatus",
+            SourceStatus {
+                source: PackageSource::Pacman,
+                refreshed_at: Utc::now(),
+                packages: 12,
+            },
+        );
+        let value = assert_documented(
+            "HistoryEntry",
+            HistoryEntry {
+                id: 1,
+                timestamp: Utc::now(),
+                user: "alice".to_string(),
+                undo_of: Some(0),
+                unattended: true,
+                operations: vec![OperationOutcome {
+                    operation: Operation {
+                        kind: OperationKind::Downgrade,
+                        package_id: "vim".to_string(),
+                        source: PackageSource::Apt,
+                        version: Some("9.0".to_string()),
+                    },
+                    old_version: Some("9.1".to_string()),
+                    new_version: Some("9.0".to_string()),
+                    error: None,
+                }],
+            },
+        );
+        assert_eq!(
+            serialized_fields(&value["operations"][0]),
+            ["error", "new_version", "old_version", "operation"]
+        );
+        assert_eq!(
+            serialized_fields(&value["operations"][0]["operation"]),
+            ["kind", "package_id", "source", "version"]
+        );
+        let value = assert_documented(
+            "AuditEntry",
+            AuditEntry {
+                seq: 1,
+                timestamp: Utc::now(),
+                uid: Some(1000),
+                caller: ":1.42".to_string(),
+                action: "com.fluxara.Store.install".to_string(),
+                operation: "InstallPackages".to_string(),
+                arguments: vec!["apt".to_string(), "vim".to_string()],
+                status: AuditStatus::Denied,
+                message: Some("Not authorized".to_string()),
+                prev_hash: String::new(),
+                hash: String::new(),
+            },
+        );
+        assert_eq!(value["status"], "denied");
+        assert_documented(
+            "RepoInfo",
+            RepoInfo {
+                name: "flathub".to_string(),
+                url: "https://dl.flathub.org/repo/".to_string(),
+                enabled: true,
+                speed_mbps: None,
+            },
+        );
+        let value = assert_documented(
+            "DriverInfo",
+            DriverInfo {
+                name: "nvidia".to_string(),
+                description: "NVIDIA driver".to_string(),
+                device_ids: vec!["10de:2684".to_string()],
+                driver_type: DriverType::Proprietary,
+                recommended: true,
+                packages: vec!["nvidia-driver".to_string()],
+            },
+        );
+        assert_eq!(value["driver_type"], "Proprietary");
+
+        let details = serde_json::to_value(PackageDetails {
+            id: "firefox".to_string(),
+            name: "Firefox".to_string(),
+            source: PackageSource::Snap,
+            version: None,
+            size_bytes: None,
+            license: None,
+            confinement: Confinement::Strict,
+            permissions: vec![],
+            channel: Some("latest/stable".to_string()),
+            installed: false,
+        })
+        .unwrap();
+        assert_eq!(details["confinement"], "strict");
+        let device = serde_json::to_value(HardwareDevice {
+            id: "10de:2684".to_string(),
+            name: "GeForce RTX 4090".to_string(),
+            device_type: DeviceType::Pci,
+        })
+        .unwrap();
+        assert_eq!(serialized_fields(&device), ["device_type", "id", "name"]);
+        assert_eq!(device["device_type"], "pci");
+    }
+
+    #[test]
+    fn exit_codes_follow_the_documented_table() {
+        let codes = [
+            exit_code::SUCCESS,
+            exit_code::FAILURE,
+            exit_code::USAGE,
+            exit_code::NOT_FOUND,
+            exit_code::PERMISSION_DENIED,
+            exit_code::PARTIAL_FAILURE,
+            exit_code::CANCELLED,
+            exit_code::UNAVAILABLE,
+            exit_code::CONFLICT,
+        ];
+        assert_eq!(codes, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let errors = [
+            (CliError::NotFound(String::new()), 3, "not_found"),
+            (
+                CliError::PermissionDenied(String::new()),
+                4,
+                "permission_denied",
+            ),
+            (
+                CliError::PartialFailure {
+                    failed: 1,
+                    total: 2,
+                },
+                5,
+                "partial_failure",
+            ),
+            (CliError::Cancelled(String::new()), 6, "cancelled"),
+            (CliError::Unavailable(String::new()), 7, "unavailable"),
+            (CliError::Conflict(String::new()), 8, "conflict"),
+        ];
+        for (error, code, kind) in errors {
+            assert_eq!(error.exit_code(), code);
+            assert_eq!(error.kind(), kind);
+            let documented = format!("| {}    | `{}`", code, kind);
+            assert!(SCHEMA.contains(&documented), "{} undocumented", kind);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported_with_their_kind_and_code() {
+        let error = anyhow::Error::new(CliError::NotFound("Package not found: foo".to_string()));
+        assert_eq!(error_exit_code(&error), 3);
+        assert_eq!(
+            error_json(&error),
+            json!({"error": {
+                "kind": "not_found",
+                "message": "Package not found: foo",
+                "exit_code": 3,
+            }})
+        );
+
+        // Context added on the way up keeps the kind
+        let error = anyhow::Error::new(CliError::Conflict("already installed".to_string()))
+            .context("Failed to install gimp");
+        assert_eq!(error_exit_code(&error), 8);
+        assert_eq!(error_json(&error)["error"]["kind"], "conflict");
+        assert_eq!(
+            error_json(&error)["error"]["message"],
+            "Failed to install gimp: already installed"
+        );
+
+        let error = anyhow::anyhow!("disk full");
+        assert_eq!(error_exit_code(&error), 1);
+        assert_eq!(error_json(&error)["error"]["kind"], "failure");
+    }
+}
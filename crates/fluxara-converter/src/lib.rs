@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use fluxara_core::config::ConversionPolicy;
 use fluxara_core::Config;
 use std::process::Command;
 use thiserror::Error;
@@ -40,12 +41,27 @@ impl PackageConverter {
         }
 
         // Security check
-        if self.is_blocked_package(package_path) {
-            return Err(ConversionError::SecurityBlocked(format!(
-                "Package {} contains blocked keywords",
-                package_path
-            ))
-            .into());
+        match self.config.security.conversion_policy {
+            ConversionPolicy::Strict => {
+                return Err(ConversionError::SecurityBlocked(
+                    "Conversions are disabled by the strict policy".to_string(),
+                )
+                .into());
+            }
+            ConversionPolicy::Safe if self.is_blocked_package(package_path) => {
+                return Err(ConversionError::SecurityBlocked(format!(
+                    "Package {} contains blocked keywords",
+                    package_path
+                ))
+                .into());
+            }
+            ConversionPolicy::Permissive if self.is_blocked_package(package_path) => {
+                eprintln!(
+                    "Warning: {} contains blocked keywords, converting anyway (permissive policy)",
+                    package_path
+                );
+            }
+            _ => {}
         }
 
         // Run alien conversion
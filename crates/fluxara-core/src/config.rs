@@ -1,4 +1,13 @@
+use crate::content::{ContentLevel, ContentRating};
+use crate::models::{InstallScope, PackageSource, UpdateInfo};
+use anyhow::Context as _;
+use chrono::NaiveTime;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -6,6 +15,18 @@ pub struct Config {
     pub repos: ReposConfig,
     pub security: SecurityConfig,
     pub telemetry: TelemetryConfig,
+    #[serde(default)]
+    pub install: InstallConfig,
+    #[serde(default)]
+    pub updates: UpdatesConfig,
+    #[serde(default)]
+    pub index: IndexConfig,
+    #[serde(default)]
+    pub odrs: OdrsConfig,
+    #[serde(default)]
+    pub media: MediaConfig,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +68,448 @@ pub struct TelemetryConfig {
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallConfig {
+    /// Sources to install from, most preferred first
+    pub preferred_sources: Vec<SourcePreference>,
+    /// Ask which source to use when several offer the same app
+    pub prompt: bool,
+    /// Per-package source, keyed by package id or name
+    #[serde(default)]
+    pub overrides: BTreeMap<String, SourcePreference>,
+    /// Flatpak installation to manage; `user` needs no administrator rights
+    #[serde(default)]
+    pub flatpak_scope: InstallScope,
+}
+
+impl Default for InstallConfig {
+    fn default() -> Self {
+        Self {
+            preferred_sources: vec![
+                SourcePreference::Flatpak,
+                SourcePreference::Native,
+                SourcePreference::Snap,
+            ],
+            prompt: true,
+            overrides: BTreeMap::new(),
+            flatpak_scope: InstallScope::default(),
+        }
+    }
+}
+
+impl InstallConfig {
+    /// Position of a source in the preference order; unlisted sources sort last
+    pub fn rank(&self, package: &str, source: PackageSource) -> usize {
+        if self
+            .override_for(package)
+            .is_some_and(|o| o.matches(source))
+        {
+            return 0;
+        }
+
+        self.preferred_sources
+            .iter()
+            .position(|preference| preference.matches(source))
+            .map_or(usize::MAX, |position| position + 1)
+    }
+
+    pub fn override_for(&self, package: &str) -> Option<SourcePreference> {
+        self.overrides.get(package).copied()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdatesConfig {
+    /// Package ids that are never updated, whatever their source
+    #[serde(default)]
+    pub holds: Vec<String>,
+    /// What the daemon does on its own with the updates of each source
+    #[serde(default)]
+    pub policy: UpdatePolicies,
+    /// Install only APT updates from security suites unattended; the others
+    /// are downloaded and notified
+    #[serde(default)]
+    pub apt_security_only: bool,
+}
+
+impl UpdatesConfig {
+    pub fn is_held(&self, package_id: &str) -> bool {
+        self.holds.iter().any(|hold| hold == package_id)
+    }
+
+    /// What the daemon does with `update` on its own, taking
+    /// `apt_security_only` into account
+    pub fn policy_for(&self, update: &UpdateInfo) -> UpdatePolicy {
+        match self.policy.get(update.source) {
+            UpdatePolicy::Install
+                if update.source == PackageSource::Apt
+                    && self.apt_security_only
+                    && !update.security =>
+            {
+                UpdatePolicy::DownloadOnly
+            }
+            policy => policy,
+        }
+    }
+
+    /// Drop updates for held packages
+    pub fn retain_unheld(&self, updates: &mut Vec<UpdateInfo>) {
+        updates.retain(|update| !self.is_held(&update.package_id));
+    }
+}
+
+/// What the daemon does with the updates it finds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdatePolicy {
+    /// Neither check nor notify
+    Off,
+    /// Notify, leaving the updates to the user
+    #[default]
+    Notify,
+    /// Download the updates when the system is idle, then notify
+    DownloadOnly,
+    /// Install the updates without asking
+    Install,
+}
+
+/// Update policy of each source the daemon checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatePolicies {
+    pub flatpak: UpdatePolicy,
+    pub apt: UpdatePolicy,
+    pub pacman: UpdatePolicy,
+    pub snap: UpdatePolicy,
+}
+
+impl UpdatePolicies {
+    /// Policy of `source`; AUR packages are built by hand, so only notified
+    pub fn get(&self, source: PackageSource) -> UpdatePolicy {
+        match source {
+            PackageSource::Flatpak => self.flatpak,
+            PackageSource::Apt => self.apt,
+            PackageSource::Pacman => self.pacman,
+            PackageSource::Snap => self.snap,
+            PackageSource::Aur => UpdatePolicy::Notify,
+        }
+    }
+
+    /// Whether any source is downloaded or installed without asking
+    pub fn is_unattended(&self) -> bool {
+        PackageSource::ALL.iter().any(|source| {
+            matches!(
+                self.get(*source),
+                UpdatePolicy::DownloadOnly | UpdatePolicy::Install
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexConfig {
+    /// Answer searches from the local package index
+    pub enabled: bool,
+    /// Refresh the index when it is older than this
+    pub max_age_hours: u64,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OdrsConfig {
+    /// Base URL of the Open Desktop Ratings Service API
+    pub api_url: String,
+    /// Serve ratings and reviews from the disk cache while younger than this
+    pub cache_ttl_hours: u64,
+}
+
+impl Default for OdrsConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "https://odrs.gnome.org/1.0/reviews/api".to_string(),
+            cache_ttl_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaConfig {
+    /// Size the icon and screenshot cache may grow to before the least
+    /// recently used downloads are evicted
+    pub max_cache_mb: u64,
+    /// Icon size in pixels used for package icons
+    pub icon_size: u32,
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        Self {
+            max_cache_mb: 256,
+            icon_size: 64,
+        }
+    }
+}
+
+/// When the background daemon checks for updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Time between update checks
+    pub check_interval_hours: u64,
+    /// Largest random delay added to each check, so that machines started
+    /// together do not all hit the mirrors at once
+    pub jitter_minutes: u64,
+    /// Local times during which no check starts, such as `22:00-07:00`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    /// Check while the network connection is metered
+    pub check_on_metered: bool,
+    /// Check while running on battery
+    pub check_on_battery: bool,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_hours: 6,
+            jitter_minutes: 30,
+            quiet_hours: None,
+            check_on_metered: false,
+            check_on_battery: true,
+        }
+    }
+}
+
+/// A daily span of local time, which may wrap past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for QuietHours {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| anyhow::anyhow!("Invalid time {:?}, expected HH:MM", time.trim()))
+        };
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("Invalid quiet hours {:?}, expected HH:MM-HH:MM", s))?;
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+impl TryFrom<String> for QuietHours {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<QuietHours> for String {
+    fn from(hours: QuietHours) -> Self {
+        hours.to_string()
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Where the parental controls policy is read from; only root may change it
+pub const PARENTAL_POLICY_PATH: &str = "/etc/fluxara/parental.toml";
+
+/// Parental controls based on the OARS content ratings of apps
+///
+/// The policy lives in [`PARENTAL_POLICY_PATH`] rather than in the user's own
+/// configuration, which the restricted user could edit. The privileged
+/// helper enforces it on every install it is asked for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentalConfig {
+    pub enabled: bool,
+    /// Users the policy applies to; empty applies it to every user
+    #[serde(default)]
+    pub restricted_users: Vec<String>,
+    /// Highest age an app's content may be rated for
+    #[serde(default)]
+    pub max_age: Option<u32>,
+    /// Highest intensity allowed per OARS attribute, e.g. `violence-realistic = "mild"`
+    #[serde(default)]
+    pub limits: BTreeMap<String, ContentLevel>,
+    /// Allow packages without a content rating, such as most libraries and tools
+    #[serde(default = "allow_unrated_default")]
+    pub allow_unrated: bool,
+}
+
+fn allow_unrated_default() -> bool {
+    true
+}
+
+impl Default for ParentalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            restricted_users: Vec::new(),
+            max_age: None,
+            limits: BTreeMap::new(),
+            allow_unrated: allow_unrated_default(),
+        }
+    }
+}
+
+impl ParentalConfig {
+    /// The system-wide policy; disabled when the file does not exist
+    pub fn system() -> anyhow::Result<Self> {
+        Self::load_from(Path::new(PARENTAL_POLICY_PATH))
+    }
+
+    /// Read a policy, refusing files anyone but root could have written
+    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            anyhow::bail!(
+                "{} must be owned by root and writable by no one else",
+                path.display()
+            );
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid policy {}", path.display()))
+    }
+
+    /// Whether the policy restricts the user running Fluxara
+    pub fn applies(&self) -> bool {
+        // SAFETY: getuid cannot fail and has no preconditions
+        self.applies_to(unsafe { libc::getuid() })
+    }
+
+    /// Whether the policy restricts the user with `uid`
+    pub fn applies_to(&self, uid: u32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.restricted_users.is_empty() {
+            return true;
+        }
+        user_name(uid).is_some_and(|user| self.restricted_users.contains(&user))
+    }
+
+    /// Reasons content with `rating` breaks the policy; empty when it is allowed
+    pub fn violations(&self, rating: Option<&ContentRating>) -> Vec<String> {
+        let Some(rating) = rating else {
+            if self.allow_unrated {
+                return vec![];
+            }
+            return vec!["no content rating".to_string()];
+        };
+
+        let mut violations = Vec::new();
+        if let Some(max_age) = self.max_age {
+            let age = rating.minimum_age();
+            if age > max_age {
+                violations.push(format!("rated for ages {}+, limit is {}", age, max_age));
+            }
+        }
+        for (attribute, limit) in &self.limits {
+            let level = rating.level(attribute);
+            if level > *limit {
+                violations.push(format!("{} is {}, limit is {}", attribute, level, limit));
+            }
+        }
+        violations
+    }
+}
+
+/// Login name of `uid` from the user database
+fn user_name(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    // SAFETY: passwd is plain data, filled in by getpwuid_r
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut found = std::ptr::null_mut();
+    // SAFETY: every pointer is valid for the duration of the call and the
+    // buffer length is passed along
+    let status = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut entry,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut found,
+        )
+    };
+    if status != 0 || found.is_null() {
+        return None;
+    }
+    // SAFETY: on success pw_name points to a NUL-terminated string in buffer
+    let name = unsafe { std::ffi::CStr::from_ptr(entry.pw_name) };
+    name.to_str().ok().map(str::to_string)
+}
+
+/// Entry of the install preference order
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourcePreference {
+    Flatpak,
+    Snap,
+    Aur,
+    Apt,
+    Pacman,
+    /// The distribution's own package manager, whichever it is
+    Native,
+}
+
+impl SourcePreference {
+    pub fn matches(&self, source: PackageSource) -> bool {
+        match self {
+            SourcePreference::Flatpak => source == PackageSource::Flatpak,
+            SourcePreference::Snap => source == PackageSource::Snap,
+            SourcePreference::Aur => source == PackageSource::Aur,
+            SourcePreference::Apt => source == PackageSource::Apt,
+            SourcePreference::Pacman => source == PackageSource::Pacman,
+            SourcePreference::Native => {
+                matches!(source, PackageSource::Apt | PackageSource::Pacman)
+            }
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -61,6 +524,12 @@ impl Default for Config {
                 conversion_policy: ConversionPolicy::Safe,
             },
             telemetry: TelemetryConfig { enabled: false },
+            install: InstallConfig::default(),
+            updates: UpdatesConfig::default(),
+            index: IndexConfig::default(),
+            odrs: OdrsConfig::default(),
+            media: MediaConfig::default(),
+            daemon: DaemonConfig::default(),
         }
     }
 }
@@ -92,7 +561,7 @@ impl Config {
         Ok(())
     }
 
-    fn config_path() -> anyhow::Result<std::path::PathBuf> {
+    pub fn config_path() -> anyhow::Result<std::path::PathBuf> {
         let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
         Ok(std::path::PathBuf::from(home)
             .join(".config")
@@ -100,6 +569,29 @@ impl Config {
             .join("config.toml"))
     }
 
+    /// Directory for Fluxara's persistent state, following the XDG base directory spec
+    pub fn data_dir() -> anyhow::Result<std::path::PathBuf> {
+        if let Some(data_home) = std::env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+            return Ok(std::path::PathBuf::from(data_home).join("fluxara"));
+        }
+        let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
+        Ok(std::path::PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("fluxara"))
+    }
+
+    /// Directory for data that can be regenerated, following the XDG base directory spec
+    pub fn cache_dir() -> anyhow::Result<std::path::PathBuf> {
+        if let Some(cache_home) = std::env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+            return Ok(std::path::PathBuf::from(cache_home).join("fluxara"));
+        }
+        let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
+        Ok(std::path::PathBuf::from(home)
+            .join(".cache")
+            .join("fluxara"))
+    }
+
     fn is_arch_based() -> bool {
         std::fs::read_to_string("/etc/os-release")
             .map(|content| {
@@ -111,3 +603,139 @@ impl Config {
             .unwrap_or(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn update(package_id: &str, source: PackageSource) -> UpdateInfo {
+        UpdateInfo {
+            package_id: package_id.to_string(),
+            current_version: "1.0".to_string(),
+            new_version: "1.1".to_string(),
+            source,
+            size_bytes: None,
+            security: false,
+        }
+    }
+
+    fn holding(ids: &[&str]) -> UpdatesConfig {
+        UpdatesConfig {
+            holds: ids.iter().map(|id| id.to_string()).collect(),
+            ..UpdatesConfig::default()
+        }
+    }
+
+    #[test]
+    fn sources_rank_in_the_configured_order() {
+        let install = InstallConfig::default();
+        assert_eq!(install.rank("gimp", PackageSource::Flatpak), 1);
+        assert_eq!(install.rank("gimp", PackageSource::Apt), 2);
+        assert_eq!(install.rank("gimp", PackageSource::Pacman), 2);
+        assert_eq!(install.rank("gimp", PackageSource::Snap), 3);
+        // Sources missing from the order come after every listed one
+        assert_eq!(install.rank("gimp", PackageSource::Aur), usize::MAX);
+    }
+
+    #[test]
+    fn overrides_rank_ahead_of_the_order() {
+        let install = InstallConfig {
+            overrides: BTreeMap::from([("firefox".to_string(), SourcePreference::Snap)]),
+            ..InstallConfig::default()
+        };
+        assert_eq!(install.rank("firefox", PackageSource::Snap), 0);
+        assert_eq!(install.rank("firefox", PackageSource::Flatpak), 1);
+        // Other packages keep the global order
+        assert_eq!(install.rank("gimp", PackageSource::Snap), 3);
+    }
+
+    #[test]
+    fn holds_match_whole_package_ids() {
+        let updates = holding(&["firefox", "org.gimp.GIMP"]);
+        assert!(updates.is_held("firefox"));
+        assert!(updates.is_held("org.gimp.GIMP"));
+        assert!(!updates.is_held("firefox-esr"));
+        assert!(!updates.is_held("org.gimp"));
+        assert!(!holding(&[]).is_held("firefox"));
+    }
+
+    #[test]
+    fn held_updates_are_dropped_whatever_their_source() {
+        let mut found = vec![
+            update("firefox", PackageSource::Apt),
+            update("firefox", PackageSource::Snap),
+            update("curl", PackageSource::Apt),
+            update("org.gimp.GIMP", PackageSource::Flatpak),
+        ];
+
+        holding(&["firefox", "org.gimp.GIMP"]).retain_unheld(&mut found);
+
+        let left: Vec<_> = found
+            .iter()
+            .map(|u| (u.package_id.as_str(), u.source))
+            .collect();
+        assert_eq!(left, [("curl", PackageSource::Apt)]);
+    }
+
+    fn write_policy(dir: &Path, mode: u32) -> std::path::PathBuf {
+        let path = dir.join("parental.toml");
+        std::fs::write(&path, "enabled = true\nmax_age = 12\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn missing_policy_is_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = ParentalConfig::load_from(&dir.path().join("parental.toml")).unwrap();
+        assert!(!policy.enabled);
+        assert!(!policy.applies_to(0));
+    }
+
+    #[test]
+    fn policy_others_could_write_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_policy(dir.path(), 0o664);
+        assert!(ParentalConfig::load_from(&path).is_err());
+
+        // Only a policy root owns is trusted
+        let path = write_policy(dir.path(), 0o644);
+        let owned_by_root = std::fs::metadata(&path).unwrap().uid() == 0;
+        match ParentalConfig::load_from(&path) {
+            Ok(policy) => {
+                assert!(owned_by_root);
+                assert!(policy.enabled);
+                assert_eq!(policy.max_age, Some(12));
+            }
+            Err(_) => assert!(!owned_by_root),
+        }
+    }
+
+    #[test]
+    fn policy_applies_to_restricted_users_by_uid() {
+        let everyone = ParentalConfig {
+            enabled: true,
+            ..ParentalConfig::default()
+        };
+        assert!(everyone.applies_to(0));
+
+        let restricted = ParentalConfig {
+            restricted_users: vec!["root".to_string()],
+            ..everyone.clone()
+        };
+        assert!(restricted.applies_to(0));
+
+        let others = ParentalConfig {
+            restricted_users: vec!["no-such-user-for-fluxara-tests".to_string()],
+            ..everyone.clone()
+        };
+        assert!(!others.applies_to(0));
+
+        let disabled = ParentalConfig {
+            enabled: false,
+            ..everyone
+        };
+        assert!(!disabled.applies_to(0));
+    }
+}
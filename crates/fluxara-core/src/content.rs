@@ -0,0 +1,121 @@
+//! Open Age Ratings Service (OARS) content ratings
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Intensity of one kind of content, from least to most severe
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentLevel {
+    #[default]
+    None,
+    Mild,
+    Moderate,
+    Intense,
+}
+
+impl ContentLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentLevel::None => "none",
+            ContentLevel::Mild => "mild",
+            ContentLevel::Moderate => "moderate",
+            ContentLevel::Intense => "intense",
+        }
+    }
+}
+
+impl fmt::Display for ContentLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(ContentLevel::None),
+            "mild" => Ok(ContentLevel::Mild),
+            "moderate" => Ok(ContentLevel::Moderate),
+            "intense" => Ok(ContentLevel::Intense),
+            other => anyhow::bail!("Unknown content level: {}", other),
+        }
+    }
+}
+
+/// Minimum age for each OARS attribute at mild, moderate and intense levels,
+/// following the Common Sense Media mapping used by libappstream
+const CSM_AGES: &[(&str, [u32; 3])] = &[
+    ("violence-cartoon", [3, 4, 6]),
+    ("violence-fantasy", [3, 7, 8]),
+    ("violence-realistic", [4, 9, 14]),
+    ("violence-bloodshed", [9, 11, 18]),
+    ("violence-sexual", [18, 18, 18]),
+    ("violence-desecration", [3, 7, 13]),
+    ("violence-slavery", [3, 13, 15]),
+    ("violence-worship", [3, 13, 15]),
+    ("drugs-alcohol", [11, 13, 16]),
+    ("drugs-narcotics", [12, 14, 17]),
+    ("drugs-tobacco", [10, 13, 13]),
+    ("sex-nudity", [12, 14, 14]),
+    ("sex-themes", [13, 14, 15]),
+    ("sex-homosexuality", [13, 14, 15]),
+    ("sex-prostitution", [12, 14, 18]),
+    ("sex-adultery", [8, 10, 18]),
+    ("sex-appearance", [10, 10, 15]),
+    ("language-profanity", [8, 11, 14]),
+    ("language-humor", [3, 8, 14]),
+    ("language-discrimination", [9, 10, 11]),
+    ("social-chat", [4, 10, 13]),
+    ("social-info", [0, 13, 13]),
+    ("social-audio", [15, 15, 15]),
+    ("social-location", [13, 13, 13]),
+    ("social-contacts", [12, 12, 12]),
+    ("money-purchasing", [12, 14, 18]),
+    ("money-gambling", [16, 16, 16]),
+];
+
+/// Minimum age for content of `attribute` at `level`; unknown attributes
+/// do not raise the age
+pub fn minimum_age(attribute: &str, level: ContentLevel) -> u32 {
+    let Some((_, ages)) = CSM_AGES.iter().find(|(id, _)| *id == attribute) else {
+        return 0;
+    };
+    match level {
+        ContentLevel::None => 0,
+        ContentLevel::Mild => ages[0],
+        ContentLevel::Moderate => ages[1],
+        ContentLevel::Intense => ages[2],
+    }
+}
+
+/// Open Age Ratings Service content rating
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentRating {
+    /// Rating scheme, e.g. `oars-1.1`
+    pub kind: String,
+    /// Attribute id (`violence-cartoon`, ...) to intensity
+    pub attributes: BTreeMap<String, ContentLevel>,
+}
+
+impl ContentRating {
+    /// Intensity of `attribute`; attributes not listed have none
+    pub fn level(&self, attribute: &str) -> ContentLevel {
+        self.attributes.get(attribute).copied().unwrap_or_default()
+    }
+
+    /// Youngest age the content is suitable for
+    pub fn minimum_age(&self) -> u32 {
+        self.attributes
+            .iter()
+            .map(|(attribute, level)| minimum_age(attribute, *level))
+            .max()
+            .unwrap_or(0)
+    }
+}
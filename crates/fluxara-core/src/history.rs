@@ -0,0 +1,309 @@
+use crate::config::Config;
+use crate::transaction::{OperationKind, OperationOutcome, Transaction};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// One recorded transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    /// Entry this transaction reverted, if it was an undo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_of: Option<u64>,
+    /// Run by the daemon on its own, following the update policy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unattended: bool,
+    pub operations: Vec<OperationOutcome>,
+}
+
+impl HistoryEntry {
+    pub fn failed(&self) -> usize {
+        self.operations.iter().filter(|o| !o.succeeded()).count()
+    }
+
+    /// The transaction that reverts the successful operations of this entry
+    ///
+    /// Installs are undone by removing, removals by reinstalling, and updates
+    /// by downgrading to the version installed before. Operations that cannot
+    /// be reverted (an update whose previous version is unknown) are returned
+    /// separately.
+    pub fn inverse(&self) -> (Transaction, Vec<&OperationOutcome>) {
+        let mut transaction = Transaction::new();
+        let mut irreversible = Vec::new();
+
+        for outcome in self.operations.iter().rev().filter(|o| o.succeeded()) {
+            let operation = &outcome.operation;
+            match (operation.kind, &outcome.old_version) {
+                (OperationKind::Install, _) => transaction.add(
+                    OperationKind::Remove,
+                    &operation.package_id,
+                    operation.source,
+                ),
+                (OperationKind::Remove, _) => transaction.add(
+                    OperationKind::Install,
+                    &operation.package_id,
+                    operation.source,
+                ),
+                (OperationKind::Update | OperationKind::Downgrade, Some(old))
+                    if outcome.new_version.as_ref() != Some(old) =>
+                {
+                    transaction.add_downgrade(&operation.package_id, operation.source, old)
+                }
+                _ => irreversible.push(outcome),
+            }
+        }
+
+        (transaction, irreversible)
+    }
+}
+
+/// Why a transaction ran, as recorded with it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Origin {
+    /// Asked for by the user
+    #[default]
+    User,
+    /// Reverting the entry with this id
+    Undo(u64),
+    /// Run by the daemon on its own, following the update policy
+    Unattended,
+}
+
+/// Append-only journal of every transaction, stored as JSON lines
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// The journal in the user's data directory
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(Config::data_dir()?.join("history.jsonl")))
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All entries, oldest first; unreadable lines are skipped
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open {}", self.path.display()))
+            }
+        };
+
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect())
+    }
+
+    pub fn get(&self, id: u64) -> Result<Option<HistoryEntry>> {
+        Ok(self.entries()?.into_iter().find(|entry| entry.id == id))
+    }
+
+    /// Append the outcome of a transaction and return the new entry
+    pub fn record(
+        &self,
+        operations: Vec<OperationOutcome>,
+        undo_of: Option<u64>,
+    ) -> Result<HistoryEntry> {
+        self.record_as(operations, undo_of.map_or(Origin::User, Origin::Undo))
+    }
+
+    /// Append the outcome of updates the daemon applied on its own
+    pub fn record_unattended(&self, operations: Vec<OperationOutcome>) -> Result<HistoryEntry> {
+        self.record_as(operations, Origin::Unattended)
+    }
+
+    /// Append the outcome of a transaction that ran for `origin`
+    ///
+    /// The journal is locked while the next id is read and the entry written,
+    /// so the CLI, the store and the daemon recording at once never share an id.
+    pub fn record_as(
+        &self,
+        operations: Vec<OperationOutcome>,
+        origin: Origin,
+    ) -> Result<HistoryEntry> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.lock()
+            .with_context(|| format!("Failed to lock {}", self.path.display()))?;
+
+        let entry = HistoryEntry {
+            id: self.entries()?.last().map_or(1, |last| last.id + 1),
+            timestamp: Utc::now(),
+            user: current_user(),
+            undo_of: match origin {
+                Origin::Undo(id) => Some(id),
+                _ => None,
+            },
+            unattended: origin == Origin::Unattended,
+            operations,
+        };
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+
+        Ok(entry)
+    }
+}
+
+/// The invoking user, looking through sudo
+fn current_user() -> String {
+    ["SUDO_USER", "USER", "LOGNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PackageSource;
+    use crate::transaction::Operation;
+
+    fn outcome(
+        kind: OperationKind,
+        package_id: &str,
+        old_version: Option<&str>,
+        new_version: Option<&str>,
+    ) -> OperationOutcome {
+        OperationOutcome {
+            operation: Operation {
+                kind,
+                package_id: package_id.to_string(),
+                source: PackageSource::Apt,
+                version: None,
+            },
+            old_version: old_version.map(str::to_string),
+            new_version: new_version.map(str::to_string),
+            error: None,
+        }
+    }
+
+    fn entry(operations: Vec<OperationOutcome>) -> HistoryEntry {
+        HistoryEntry {
+            id: 1,
+            timestamp: Utc::now(),
+            user: "alice".to_string(),
+            undo_of: None,
+            unattended: false,
+            operations,
+        }
+    }
+
+    #[test]
+    fn updates_and_downgrades_are_reverted_to_the_previous_version() {
+        let entry = entry(vec![
+            outcome(OperationKind::Update, "vim", Some("9.0"), Some("9.1")),
+            outcome(OperationKind::Downgrade, "curl", Some("8.2"), Some("8.1")),
+        ]);
+
+        let (transaction, irreversible) = entry.inverse();
+
+        assert!(irreversible.is_empty());
+        let operations: Vec<_> = transaction
+            .operations()
+            .iter()
+            .map(|op| (op.kind, op.package_id.as_str(), op.version.as_deref()))
+            .collect();
+        assert_eq!(
+            operations,
+            [
+                (OperationKind::Downgrade, "curl", Some("8.2")),
+                (OperationKind::Downgrade, "vim", Some("9.0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn installs_removals_and_unknown_versions() {
+        let mut failed = outcome(OperationKind::Install, "broken", None, None);
+        failed.error = Some("not found".to_string());
+        let entry = entry(vec![
+            outcome(OperationKind::Install, "htop", None, Some("3.3")),
+            outcome(OperationKind::Remove, "nano", Some("7.2"), None),
+            outcome(OperationKind::Update, "git", None, Some("2.44")),
+            outcome(OperationKind::Update, "bash", Some("5.2"), Some("5.2")),
+            failed,
+        ]);
+
+        let (transaction, irreversible) = entry.inverse();
+
+        let operations: Vec<_> = transaction
+            .operations()
+            .iter()
+            .map(|op| (op.kind, op.package_id.as_str()))
+            .collect();
+        assert_eq!(
+            operations,
+            [
+                (OperationKind::Install, "nano"),
+                (OperationKind::Remove, "htop"),
+            ]
+        );
+        let irreversible: Vec<_> = irreversible
+            .iter()
+            .map(|o| o.operation.package_id.as_str())
+            .collect();
+        assert_eq!(irreversible, ["bash", "git"]);
+    }
+
+    #[test]
+    fn concurrent_records_get_distinct_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let history = History::at(path);
+                    (0..10)
+                        .map(|_| history.record(vec![], None).unwrap().id)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut ids: Vec<u64> = writers
+            .into_iter()
+            .flat_map(|writer| writer.join().unwrap())
+            .collect();
+        ids.sort();
+
+        assert_eq!(ids, (1..=80).collect::<Vec<_>>());
+        assert_eq!(History::at(path).entries().unwrap().len(), 80);
+    }
+
+    #[test]
+    fn origin_is_recorded_with_the_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::at(dir.path().join("history.jsonl"));
+
+        let first = history.record_as(vec![], Origin::User).unwrap();
+        let undo = history.record_as(vec![], Origin::Undo(first.id)).unwrap();
+        let unattended = history.record_as(vec![], Origin::Unattended).unwrap();
+
+        assert_eq!((first.undo_of, first.unattended), (None, false));
+        assert_eq!((undo.undo_of, undo.unattended), (Some(first.id), false));
+        assert_eq!((unattended.undo_of, unattended.unattended), (None, true));
+        assert_eq!(history.get(undo.id).unwrap().unwrap().undo_of, Some(1));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::models::{Package, PackageSource};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One application, possibly installable from several sources
+#[derive(Debug, Clone, Serialize)]
+pub struct App {
+    /// AppStream component id when known, otherwise the package name
+    pub id: String,
+    pub name: String,
+    pub summary: Option<String>,
+    pub icon_url: Option<String>,
+    /// Installable packages, one per source at most, in the order they were found
+    pub packages: Vec<Package>,
+}
+
+impl App {
+    pub fn sources(&self) -> impl Iterator<Item = PackageSource> + '_ {
+        self.packages.iter().map(|p| p.source)
+    }
+
+    pub fn installed(&self) -> bool {
+        self.packages.iter().any(|p| p.installed)
+    }
+
+    fn from_package(id: String, package: Package) -> Self {
+        Self {
+            id,
+            name: package.name.clone(),
+            summary: package.description.clone(),
+            icon_url: package.icon_url.clone(),
+            packages: vec![package],
+        }
+    }
+
+    fn add(&mut self, package: Package) {
+        // Flatpak names and summaries come from AppStream and read best
+        if package.source == PackageSource::Flatpak {
+            self.name = package.name.clone();
+            self.summary = package.description.clone().or(self.summary.take());
+        }
+        if self.icon_url.is_none() {
+            self.icon_url = package.icon_url.clone();
+        }
+        self.packages.push(package);
+    }
+}
+
+/// Maps packages of different sources to the application they ship
+///
+/// Knowledge comes from AppStream: a component id names the app, its
+/// `pkgname` tells which distribution package ships it, its launchable
+/// `.desktop` file and its `provides` ids are alternative names for it.
+/// Packages nothing is known about fall back to a name heuristic, where a
+/// reverse-DNS id such as `org.mozilla.firefox` matches the package `firefox`.
+#[derive(Debug, Clone, Default)]
+pub struct IdentityResolver {
+    /// Package id (optionally per source) to component id
+    packages: HashMap<(Option<PackageSource>, String), String>,
+    /// Alternative names (desktop ids, provided ids) to component id
+    aliases: HashMap<String, String>,
+}
+
+impl IdentityResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `package_id` ships component `app_id`, in one source or in all of them
+    pub fn add_package(&mut self, app_id: &str, source: Option<PackageSource>, package_id: &str) {
+        self.packages
+            .insert((source, package_id.to_string()), app_id.to_string());
+    }
+
+    /// Record a launchable desktop id, e.g. `firefox.desktop`
+    pub fn add_launchable(&mut self, app_id: &str, desktop_id: &str) {
+        let name = desktop_id.strip_suffix(".desktop").unwrap_or(desktop_id);
+        self.aliases.insert(name.to_lowercase(), app_id.to_string());
+    }
+
+    /// Record an id listed under the component's `provides`
+    pub fn add_provides(&mut self, app_id: &str, provided_id: &str) {
+        let id = provided_id.strip_suffix(".desktop").unwrap_or(provided_id);
+        self.aliases.insert(id.to_lowercase(), app_id.to_string());
+    }
+
+    /// The component id a package is known to ship, if any
+    pub fn known_app_id(&self, package: &Package) -> Option<&str> {
+        self.packages
+            .get(&(Some(package.source), package.id.clone()))
+            .or_else(|| self.packages.get(&(None, package.id.clone())))
+            .or_else(|| self.aliases.get(&package.id.to_lowercase()))
+            .map(String::as_str)
+    }
+
+    /// Group packages into applications, keeping the order of first appearance
+    pub fn group(&self, packages: Vec<Package>) -> Vec<App> {
+        // Reverse-DNS ids seen in this batch, by their last segment, for the name heuristic
+        let mut short_names: HashMap<String, String> = HashMap::new();
+        for package in &packages {
+            let app_id = self
+                .known_app_id(package)
+                .map_or_else(|| package.id.clone(), str::to_string);
+            if let Some(short) = short_name(&app_id) {
+                short_names.entry(short).or_insert(app_id);
+            }
+        }
+
+        let mut apps: Vec<App> = Vec::new();
+        for package in packages {
+            let app_id = match self.known_app_id(&package) {
+                Some(app_id) => app_id.to_string(),
+                None if is_reverse_dns(&package.id) => package.id.clone(),
+                None => short_names
+                    .get(&package.id.to_lowercase())
+                    .cloned()
+                    .unwrap_or_else(|| package.id.clone()),
+            };
+
+            match apps.iter_mut().find(|app| app.id == app_id) {
+                // A second package from the same source is a different app after all
+                Some(app) if app.sources().any(|s| s == package.source) => {
+                    apps.push(App::from_package(package.id.clone(), package));
+                }
+                Some(app) => app.add(package),
+                None => apps.push(App::from_package(app_id, package)),
+            }
+        }
+
+        apps
+    }
+}
+
+/// Whether an id looks like an AppStream component id, e.g. `org.gnome.Maps`
+fn is_reverse_dns(id: &str) -> bool {
+    id.split('.').filter(|segment| !segment.is_empty()).count() >= 3
+}
+
+/// Last segment of a reverse-DNS id, lowercased: `org.mozilla.firefox` gives `firefox`
+fn short_name(app_id: &str) -> Option<String> {
+    is_reverse_dns(app_id)
+        .then(|| app_id.rsplit('.').next())
+        .flatten()
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(id: &str, name: &str, source: PackageSource) -> Package {
+        Package {
+            id: id.to_string(),
+            name: name.to_string(),
+            version: None,
+            description: Some(format!("{} from {}", name, source)),
+            icon_url: None,
+            source,
+            installed: false,
+        }
+    }
+
+    fn grouped(apps: &[App]) -> Vec<(&str, Vec<PackageSource>)> {
+        apps.iter()
+            .map(|app| (app.id.as_str(), app.sources().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn packages_are_grouped_by_component_id() {
+        let mut resolver = IdentityResolver::new();
+        resolver.add_package("org.gnome.Maps", Some(PackageSource::Apt), "gnome-maps");
+        resolver.add_package("org.gnome.Maps", None, "gnome-maps");
+
+        let apps = resolver.group(vec![
+            package("gnome-maps", "gnome-maps", PackageSource::Apt),
+            package("org.gnome.Maps", "Maps", PackageSource::Flatpak),
+            package("gnome-maps", "gnome-maps", PackageSource::Pacman),
+        ]);
+
+        assert_eq!(
+            grouped(&apps),
+            [(
+                "org.gnome.Maps",
+                vec![
+                    PackageSource::Apt,
+                    PackageSource::Flatpak,
+                    PackageSource::Pacman
+                ]
+            )]
+        );
+        // The Flatpak's AppStream name wins over the distribution package's
+        assert_eq!(apps[0].name, "Maps");
+        assert_eq!(apps[0].summary.as_deref(), Some("Maps from flatpak"));
+    }
+
+    #[test]
+    fn packages_are_grouped_by_launchable() {
+        let mut resolver = IdentityResolver::new();
+        resolver.add_launchable("org.videolan.VLC", "vlc.desktop");
+
+        let apps = resolver.group(vec![
+            package("org.videolan.VLC", "VLC", PackageSource::Flatpak),
+            package("vlc", "vlc", PackageSource::Snap),
+        ]);
+
+        assert_eq!(
+            grouped(&apps),
+            [(
+                "org.videolan.VLC",
+                vec![PackageSource::Flatpak, PackageSource::Snap]
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_packages_are_grouped_by_name() {
+        let resolver = IdentityResolver::new();
+
+        let apps = resolver.group(vec![
+            package("firefox", "firefox", PackageSource::Apt),
+            package("org.mozilla.firefox", "Firefox", PackageSource::Flatpak),
+            package("firefox", "firefox", PackageSource::Snap),
+            package("curl", "curl", PackageSource::Apt),
+        ]);
+
+        assert_eq!(
+            grouped(&apps),
+            [
+                (
+                    "org.mozilla.firefox",
+                    vec![
+                        PackageSource::Apt,
+                        PackageSource::Flatpak,
+                        PackageSource::Snap
+                    ]
+                ),
+                ("curl", vec![PackageSource::Apt]),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_packages_of_one_source_stay_apart() {
+        let mut resolver = IdentityResolver::new();
+        resolver.add_package("org.mozilla.firefox", None, "firefox");
+        resolver.add_package("org.mozilla.firefox", None, "firefox-esr");
+
+        let apps = resolver.group(vec![
+            package("firefox", "firefox", PackageSource::Apt),
+            package("firefox-esr", "firefox-esr", PackageSource::Apt),
+        ]);
+
+        assert_eq!(
+            grouped(&apps),
+            [
+                ("org.mozilla.firefox", vec![PackageSource::Apt]),
+                ("firefox-esr", vec![PackageSource::Apt]),
+            ]
+        );
+    }
+}
@@ -1,7 +1,20 @@
 pub mod config;
+pub mod content;
+pub mod history;
+pub mod identity;
+pub mod manifest;
 pub mod models;
+pub mod registry;
 pub mod traits;
+pub mod transaction;
+pub mod units;
 
 pub use config::Config;
+pub use content::{ContentLevel, ContentRating};
+pub use history::{History, HistoryEntry, Origin};
+pub use identity::{App, IdentityResolver};
+pub use manifest::Manifest;
 pub use models::*;
+pub use registry::ProviderRegistry;
 pub use traits::*;
+pub use transaction::{Operation, OperationKind, OperationOutcome, Transaction};
@@ -0,0 +1,224 @@
+use crate::models::{Package, PackageSource};
+use crate::transaction::{OperationKind, Transaction};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Declarative description of the applications a machine should have
+///
+/// ```toml
+/// [flatpak]
+/// remotes = [{ name = "flathub", url = "https://dl.flathub.org/repo/" }]
+/// packages = ["org.mozilla.firefox", { id = "org.gimp.GIMP", version = "2.10.38" }]
+///
+/// [apt]
+/// repositories = ["deb http://deb.debian.org/debian bookworm main"]
+/// packages = ["git", "curl"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flatpak: Option<SourceSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apt: Option<SourceSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pacman: Option<SourceSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aur: Option<SourceSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snap: Option<SourceSection>,
+}
+
+/// Repositories and packages of one source
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceSection {
+    /// Flatpak remotes to add before installing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remotes: Vec<ManifestRemote>,
+    /// APT one-line source entries, e.g. `deb http://... bookworm main`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repositories: Vec<String>,
+    #[serde(default)]
+    pub packages: Vec<ManifestPackage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestRemote {
+    pub name: String,
+    pub url: String,
+}
+
+/// A package id, optionally pinned to a version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestPackage {
+    Id(String),
+    Pinned { id: String, version: String },
+}
+
+impl ManifestPackage {
+    pub fn id(&self) -> &str {
+        match self {
+            ManifestPackage::Id(id) | ManifestPackage::Pinned { id, .. } => id,
+        }
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            ManifestPackage::Id(_) => None,
+            ManifestPackage::Pinned { version, .. } => Some(version),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn section(&self, source: PackageSource) -> Option<&SourceSection> {
+        match source {
+            PackageSource::Flatpak => self.flatpak.as_ref(),
+            PackageSource::Apt => self.apt.as_ref(),
+            PackageSource::Pacman => self.pacman.as_ref(),
+            PackageSource::Aur => self.aur.as_ref(),
+            PackageSource::Snap => self.snap.as_ref(),
+        }
+    }
+
+    /// The section of a source, created empty if missing
+    pub fn section_mut(&mut self, source: PackageSource) -> &mut SourceSection {
+        match source {
+            PackageSource::Flatpak => self.flatpak.get_or_insert_with(Default::default),
+            PackageSource::Apt => self.apt.get_or_insert_with(Default::default),
+            PackageSource::Pacman => self.pacman.get_or_insert_with(Default::default),
+            PackageSource::Aur => self.aur.get_or_insert_with(Default::default),
+            PackageSource::Snap => self.snap.get_or_insert_with(Default::default),
+        }
+    }
+
+    /// Add the operations that bring one source in line with this manifest
+    ///
+    /// Missing packages are installed and pinned packages at another version
+    /// are switched to the pinned one. With `remove_extras`, packages in
+    /// `explicit` (installed on purpose, so not dependencies) that the manifest
+    /// does not list are removed.
+    pub fn plan(
+        &self,
+        source: PackageSource,
+        installed: &[Package],
+        explicit: Option<&[Package]>,
+        transaction: &mut Transaction,
+    ) {
+        let wanted = self.section(source).map_or(&[][..], |s| &s.packages[..]);
+
+        for package in wanted {
+            let current = installed.iter().find(|p| p.id == package.id());
+            match (current, package.version()) {
+                (None, None) => transaction.add(OperationKind::Install, package.id(), source),
+                (None, Some(version)) => transaction.add_downgrade(package.id(), source, version),
+                (Some(current), Some(version)) if current.version.as_deref() != Some(version) => {
+                    transaction.add_downgrade(package.id(), source, version)
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(explicit) = explicit {
+            for package in explicit {
+                if !wanted.iter().any(|p| p.id() == package.id) {
+                    transaction.add(OperationKind::Remove, &package.id, source);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(id: &str, version: &str) -> Package {
+        Package {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: Some(version.to_string()),
+            description: None,
+            icon_url: None,
+            source: PackageSource::Apt,
+            installed: true,
+        }
+    }
+
+    fn manifest() -> Manifest {
+        toml::from_str(
+            r#"
+            [apt]
+            packages = ["git", "curl", { id = "vim", version = "9.0" }, { id = "htop", version = "3.3" }]
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn planned(transaction: &Transaction) -> Vec<(OperationKind, &str, Option<&str>)> {
+        transaction
+            .operations()
+            .iter()
+            .map(|op| (op.kind, op.package_id.as_str(), op.version.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn plan_installs_missing_packages_and_switches_pinned_versions() {
+        let installed = [
+            package("git", "2.44"),
+            package("vim", "9.1"),
+            package("htop", "3.3"),
+            package("nano", "7.2"),
+        ];
+        let mut transaction = Transaction::new();
+
+        manifest().plan(PackageSource::Apt, &installed, None, &mut transaction);
+
+        assert_eq!(
+            planned(&transaction),
+            [
+                (OperationKind::Install, "curl", None),
+                (OperationKind::Downgrade, "vim", Some("9.0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_removes_explicit_extras_only_when_asked() {
+        let installed = [
+            package("git", "2.44"),
+            package("curl", "8.5"),
+            package("vim", "9.0"),
+            package("htop", "3.3"),
+            package("nano", "7.2"),
+            package("libc6", "2.36"),
+        ];
+        let explicit = [package("git", "2.44"), package("nano", "7.2")];
+        let mut transaction = Transaction::new();
+
+        manifest().plan(
+            PackageSource::Apt,
+            &installed,
+            Some(&explicit),
+            &mut transaction,
+        );
+        manifest().plan(PackageSource::Flatpak, &[], None, &mut transaction);
+
+        assert_eq!(
+            planned(&transaction),
+            [(OperationKind::Remove, "nano", None)]
+        );
+    }
+}
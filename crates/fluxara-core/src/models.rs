@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Package {
@@ -11,7 +13,7 @@ pub struct Package {
     pub installed: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum PackageSource {
     Flatpak,
     Apt,
@@ -20,6 +22,44 @@ pub enum PackageSource {
     Snap,
 }
 
+impl PackageSource {
+    pub const ALL: &'static [PackageSource] = &[
+        PackageSource::Flatpak,
+        PackageSource::Apt,
+        PackageSource::Pacman,
+        PackageSource::Aur,
+        PackageSource::Snap,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageSource::Flatpak => "flatpak",
+            PackageSource::Apt => "apt",
+            PackageSource::Pacman => "pacman",
+            PackageSource::Aur => "aur",
+            PackageSource::Snap => "snap",
+        }
+    }
+}
+
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PackageSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PackageSource::ALL
+            .iter()
+            .find(|source| source.as_str().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Unknown package source: {}", s))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallPlan {
     pub package_id: String,
@@ -44,6 +84,9 @@ pub struct UpdateInfo {
     pub new_version: String,
     pub source: PackageSource,
     pub size_bytes: Option<u64>,
+    /// Whether the source marks the update as fixing security issues
+    #[serde(default)]
+    pub security: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +96,9 @@ pub struct DriverInfo {
     pub device_ids: Vec<String>,
     pub driver_type: DriverType,
     pub recommended: bool,
+    /// Packages installing the driver with the distribution's package manager
+    #[serde(default)]
+    pub packages: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,10 +116,90 @@ pub struct AppRating {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppReview {
+    /// Identifier assigned by the ratings service, unset for new reviews
+    #[serde(default)]
+    pub review_id: Option<u64>,
     pub app_id: String,
+    /// Stars, from 1 to 5
     pub rating: u32,
     pub summary: String,
     pub description: String,
     pub reviewer: String,
     pub date: String,
+    /// Application version the review was written against
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Written by the current user, who may remove it
+    #[serde(default)]
+    pub own: bool,
+}
+
+/// Installation a package goes into, for sources that have more than one
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallScope {
+    /// The current user's installation, writable without elevation
+    User,
+    /// The system-wide installation, shared by every user
+    #[default]
+    System,
+}
+
+impl InstallScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstallScope::User => "user",
+            InstallScope::System => "system",
+        }
+    }
+}
+
+impl fmt::Display for InstallScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How strongly an installed application is isolated from the host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Confinement {
+    /// Flatpak sandbox; see `PackageDetails::permissions` for holes
+    Sandboxed,
+    /// Snap strict confinement
+    Strict,
+    /// Snap classic confinement (full host access)
+    Classic,
+    /// Snap developer mode (confinement violations only logged)
+    Devmode,
+    /// Native distribution package
+    Unconfined,
+}
+
+impl Confinement {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Confinement::Sandboxed => "sandboxed",
+            Confinement::Strict => "strict",
+            Confinement::Classic => "classic",
+            Confinement::Devmode => "devmode",
+            Confinement::Unconfined => "unconfined",
+        }
+    }
+}
+
+/// Detailed description of a package as offered by one source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageDetails {
+    pub id: String,
+    pub name: String,
+    pub source: PackageSource,
+    pub version: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub license: Option<String>,
+    pub confinement: Confinement,
+    pub permissions: Vec<String>,
+    /// Update channel: Flatpak remote/branch, APT suite, pacman repo or Snap channel
+    pub channel: Option<String>,
+    pub installed: bool,
 }
@@ -0,0 +1,42 @@
+use crate::models::PackageSource;
+use crate::traits::PackageManager;
+
+/// Set of package providers known to a frontend
+///
+/// Frontends register the concrete providers they link against; the registry
+/// only hands out the ones whose backend is actually installed.
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: Vec<Box<dyn PackageManager>>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, provider: Box<dyn PackageManager>) {
+        self.providers.push(provider);
+    }
+
+    /// Providers whose backend is present on this system
+    pub fn available(&self) -> impl Iterator<Item = &dyn PackageManager> {
+        self.providers
+            .iter()
+            .map(|provider| provider.as_ref())
+            .filter(|provider| provider.is_available())
+    }
+
+    /// Available providers, optionally restricted to a single source
+    pub fn select(&self, source: Option<PackageSource>) -> Vec<&dyn PackageManager> {
+        self.available()
+            .filter(|provider| source.is_none_or(|s| provider.source() == s))
+            .collect()
+    }
+
+    /// Provider for a given source, if registered and available
+    pub fn get(&self, source: PackageSource) -> Option<&dyn PackageManager> {
+        self.available()
+            .find(|provider| provider.source() == source)
+    }
+}
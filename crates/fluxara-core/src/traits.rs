@@ -1,14 +1,93 @@
-use crate::models::{InstallPlan, Package, UpdateInfo};
+use crate::models::{InstallPlan, Package, PackageDetails, PackageSource, UpdateInfo};
 use anyhow::Result;
 use async_trait::async_trait;
 
 #[async_trait]
 pub trait PackageManager: Send + Sync {
+    /// The package source this provider manages
+    fn source(&self) -> PackageSource;
+    /// Whether the backing package manager is present on this system
+    fn is_available(&self) -> bool;
+
     async fn search(&self, query: &str) -> Result<Vec<Package>>;
     async fn install(&self, package_id: &str) -> Result<()>;
     async fn remove(&self, package_id: &str) -> Result<()>;
     async fn update(&self, package_id: &str) -> Result<()>;
     async fn list_installed(&self) -> Result<Vec<Package>>;
+
+    /// Every package this source offers, for building a local search index
+    ///
+    /// Sources that cannot enumerate their catalog return an empty list and are
+    /// searched live instead.
+    async fn list_available(&self) -> Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    /// Packages installed on purpose, leaving out automatically installed dependencies
+    async fn list_explicit(&self) -> Result<Vec<Package>> {
+        self.list_installed().await
+    }
     async fn list_updates(&self) -> Result<Vec<UpdateInfo>>;
     async fn get_install_plan(&self, package_id: &str) -> Result<InstallPlan>;
+    /// Detailed information about a package, or `None` if this source does not offer it
+    async fn details(&self, package_id: &str) -> Result<Option<PackageDetails>>;
+
+    /// Install several packages; backends override this to use a single invocation
+    async fn install_packages(&self, package_ids: &[String]) -> Result<()> {
+        for package_id in package_ids {
+            self.install(package_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove several packages; backends override this to use a single invocation
+    async fn remove_packages(&self, package_ids: &[String]) -> Result<()> {
+        for package_id in package_ids {
+            self.remove(package_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Install a specific version of a package, replacing the installed one
+    async fn install_version(&self, package_id: &str, version: &str) -> Result<()> {
+        anyhow::bail!(
+            "{} cannot install version {} of {}",
+            self.source(),
+            version,
+            package_id
+        )
+    }
+
+    /// Update several packages; backends override this to use a single invocation
+    async fn update_packages(&self, package_ids: &[String]) -> Result<()> {
+        for package_id in package_ids {
+            self.update(package_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Fetch the updates of several packages into the backend's cache without
+    /// installing them, so that installing them later is quick
+    async fn download_updates(&self, package_ids: &[String]) -> Result<()> {
+        anyhow::bail!(
+            "{} cannot download updates of {} ahead of installing them",
+            self.source(),
+            package_ids.join(", ")
+        )
+    }
+
+    /// Keep a package at its installed version using the backend's own mechanism
+    async fn hold(&self, package_id: &str) -> Result<()> {
+        anyhow::bail!("{} does not support holding {}", self.source(), package_id)
+    }
+
+    /// Release a hold placed with [`PackageManager::hold`]
+    async fn unhold(&self, package_id: &str) -> Result<()> {
+        anyhow::bail!("{} does not support holding {}", self.source(), package_id)
+    }
+
+    /// Packages the backend currently holds back
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
 }
@@ -0,0 +1,439 @@
+use crate::history::{History, HistoryEntry, Origin};
+use crate::models::PackageSource;
+use crate::registry::ProviderRegistry;
+use crate::traits::PackageManager;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Install,
+    Remove,
+    Update,
+    /// Install a specific version, usually an earlier one
+    Downgrade,
+}
+
+impl OperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationKind::Install => "install",
+            OperationKind::Remove => "remove",
+            OperationKind::Update => "update",
+            OperationKind::Downgrade => "downgrade",
+        }
+    }
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single package change within a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub package_id: String,
+    pub source: PackageSource,
+    /// Target version, only set for downgrades
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Result of one operation after the transaction ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationOutcome {
+    pub operation: Operation,
+    /// Installed version before the operation
+    #[serde(default)]
+    pub old_version: Option<String>,
+    /// Installed version after the operation
+    #[serde(default)]
+    pub new_version: Option<String>,
+    pub error: Option<String>,
+}
+
+impl OperationOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Operations that run in one backend invocation
+#[derive(Debug, Clone)]
+pub struct Batch {
+    pub source: PackageSource,
+    pub kind: OperationKind,
+    pub package_ids: Vec<String>,
+    /// Target version of a downgrade; downgrades always run one package per batch
+    pub version: Option<String>,
+}
+
+/// A set of install/remove/update operations across package sources
+///
+/// Operations are grouped so that each backend is invoked once per kind of
+/// operation, which also means a single authorization prompt per backend
+/// instead of one per package.
+#[derive(Debug, Clone, Default)]
+pub struct Transaction {
+    operations: Vec<Operation>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an operation; duplicates are ignored
+    pub fn add(&mut self, kind: OperationKind, package_id: &str, source: PackageSource) {
+        self.push(Operation {
+            kind,
+            package_id: package_id.to_string(),
+            source,
+            version: None,
+        });
+    }
+
+    /// Add an install of exactly `version`, replacing any installed version
+    pub fn add_downgrade(&mut self, package_id: &str, source: PackageSource, version: &str) {
+        self.push(Operation {
+            kind: OperationKind::Downgrade,
+            package_id: package_id.to_string(),
+            source,
+            version: Some(version.to_string()),
+        });
+    }
+
+    fn push(&mut self, operation: Operation) {
+        if !self.operations.contains(&operation) {
+            self.operations.push(operation);
+        }
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Group operations by backend and kind
+    ///
+    /// Removals run first, then installs, updates and finally downgrades.
+    pub fn batches(&self) -> Vec<Batch> {
+        let mut batches: Vec<Batch> = Vec::new();
+
+        for kind in [
+            OperationKind::Remove,
+            OperationKind::Install,
+            OperationKind::Update,
+            OperationKind::Downgrade,
+        ] {
+            for operation in self.operations.iter().filter(|op| op.kind == kind) {
+                let existing = batches.iter_mut().find(|b| {
+                    b.kind == kind
+                        && b.source == operation.source
+                        && kind != OperationKind::Downgrade
+                });
+                match existing {
+                    Some(batch) => batch.package_ids.push(operation.package_id.clone()),
+                    None => batches.push(Batch {
+                        source: operation.source,
+                        kind,
+                        package_ids: vec![operation.package_id.clone()],
+                        version: operation.version.clone(),
+                    }),
+                }
+            }
+        }
+
+        batches
+    }
+
+    /// Run every batch and report the outcome of each operation
+    pub async fn execute(&self, registry: &ProviderRegistry) -> Vec<OperationOutcome> {
+        let mut outcomes = Vec::new();
+
+        for batch in self.batches() {
+            let Some(provider) = registry.get(batch.source) else {
+                let error = format!("Package source {} is not available", batch.source);
+                outcomes.extend(batch_outcomes(
+                    &batch,
+                    &HashMap::new(),
+                    &HashMap::new(),
+                    |_| Some(error.clone()),
+                ));
+                continue;
+            };
+
+            let before = installed_versions(provider).await;
+            let result = match batch.kind {
+                OperationKind::Install => provider.install_packages(&batch.package_ids).await,
+                OperationKind::Remove => provider.remove_packages(&batch.package_ids).await,
+                OperationKind::Update => provider.update_packages(&batch.package_ids).await,
+                OperationKind::Downgrade => {
+                    let version = batch.version.as_deref().unwrap_or_default();
+                    provider
+                        .install_version(&batch.package_ids[0], version)
+                        .await
+                }
+            };
+            let after = installed_versions(provider).await;
+
+            // Without both listings nothing is known to have taken effect
+            let applied = match (&result, &before, &after) {
+                (Err(_), Some(before), Some(after)) => {
+                    applied_despite_failure(&batch, before, after)
+                }
+                _ => HashSet::new(),
+            };
+            let before = before.unwrap_or_default();
+            let after = after.unwrap_or_default();
+            match result {
+                Ok(()) => outcomes.extend(batch_outcomes(&batch, &before, &after, |_| None)),
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    outcomes.extend(batch_outcomes(&batch, &before, &after, |id| {
+                        (!applied.contains(id)).then(|| error.clone())
+                    }));
+                }
+            }
+        }
+
+        outcomes
+    }
+
+    /// Run every batch and record the outcome in `history`
+    ///
+    /// The outcomes are returned even when recording fails, since the packages
+    /// changed regardless.
+    pub async fn execute_recorded(
+        &self,
+        registry: &ProviderRegistry,
+        history: &History,
+        origin: Origin,
+    ) -> (Vec<OperationOutcome>, Result<HistoryEntry>) {
+        let outcomes = self.execute(registry).await;
+        let entry = history.record_as(outcomes.clone(), origin);
+        (outcomes, entry)
+    }
+}
+
+fn batch_outcomes(
+    batch: &Batch,
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+    error_for: impl Fn(&str) -> Option<String>,
+) -> Vec<OperationOutcome> {
+    batch
+        .package_ids
+        .iter()
+        .map(|id| OperationOutcome {
+            operation: Operation {
+                kind: batch.kind,
+                package_id: id.clone(),
+                source: batch.source,
+                version: batch.version.clone(),
+            },
+            old_version: before.get(id).cloned(),
+            new_version: after.get(id).cloned(),
+            error: error_for(id),
+        })
+        .collect()
+}
+
+/// Installed packages of one source by id, with their versions, or `None`
+/// when they could not be listed
+async fn installed_versions(provider: &dyn PackageManager) -> Option<HashMap<String, String>> {
+    let installed = provider.list_installed().await.ok()?;
+    Some(
+        installed
+            .into_iter()
+            .map(|p| (p.id, p.version.unwrap_or_default()))
+            .collect(),
+    )
+}
+
+/// After a failed batch, find the packages whose change took effect anyway
+///
+/// Backends abort a batch on the first bad package but may already have
+/// handled others; comparing the installed packages before and after tells
+/// which ones. Updates cannot be told apart this way and are all reported
+/// as failed.
+fn applied_despite_failure(
+    batch: &Batch,
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+) -> HashSet<String> {
+    if !matches!(batch.kind, OperationKind::Install | OperationKind::Remove)
+        || batch.package_ids.len() < 2
+    {
+        return HashSet::new();
+    }
+
+    batch
+        .package_ids
+        .iter()
+        .filter(|id| {
+            let (was, is) = (
+                before.contains_key(id.as_str()),
+                after.contains_key(id.as_str()),
+            );
+            match batch.kind {
+                OperationKind::Install => !was && is,
+                _ => was && !is,
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(batches: &[Batch]) -> Vec<(PackageSource, OperationKind, Vec<&str>)> {
+        batches
+            .iter()
+            .map(|b| {
+                (
+                    b.source,
+                    b.kind,
+                    b.package_ids.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn batches_group_by_backend_and_kind() {
+        let mut transaction = Transaction::new();
+        transaction.add(OperationKind::Install, "vim", PackageSource::Apt);
+        transaction.add(
+            OperationKind::Install,
+            "org.gnome.Maps",
+            PackageSource::Flatpak,
+        );
+        transaction.add(OperationKind::Remove, "nano", PackageSource::Apt);
+        transaction.add(OperationKind::Install, "curl", PackageSource::Apt);
+        transaction.add(OperationKind::Update, "git", PackageSource::Apt);
+        transaction.add(OperationKind::Install, "vim", PackageSource::Apt);
+
+        assert_eq!(transaction.len(), 5);
+        assert_eq!(
+            summary(&transaction.batches()),
+            [
+                (PackageSource::Apt, OperationKind::Remove, vec!["nano"]),
+                (
+                    PackageSource::Apt,
+                    OperationKind::Install,
+                    vec!["vim", "curl"]
+                ),
+                (
+                    PackageSource::Flatpak,
+                    OperationKind::Install,
+                    vec!["org.gnome.Maps"]
+                ),
+                (PackageSource::Apt, OperationKind::Update, vec!["git"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn downgrades_run_one_package_per_batch() {
+        let mut transaction = Transaction::new();
+        transaction.add_downgrade("vim", PackageSource::Apt, "9.0");
+        transaction.add_downgrade("curl", PackageSource::Apt, "8.1");
+        transaction.add(OperationKind::Update, "git", PackageSource::Apt);
+
+        let batches = transaction.batches();
+        assert_eq!(
+            summary(&batches),
+            [
+                (PackageSource::Apt, OperationKind::Update, vec!["git"]),
+                (PackageSource::Apt, OperationKind::Downgrade, vec!["vim"]),
+                (PackageSource::Apt, OperationKind::Downgrade, vec!["curl"]),
+            ]
+        );
+        assert_eq!(batches[1].version.as_deref(), Some("9.0"));
+        assert_eq!(batches[2].version.as_deref(), Some("8.1"));
+    }
+
+    fn batch(kind: OperationKind, ids: &[&str]) -> Batch {
+        Batch {
+            source: PackageSource::Apt,
+            kind,
+            package_ids: ids.iter().map(|id| id.to_string()).collect(),
+            version: None,
+        }
+    }
+
+    fn installed(ids: &[&str]) -> HashMap<String, String> {
+        ids.iter()
+            .map(|id| (id.to_string(), "1.0".to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn failed_batches_keep_the_changes_that_took_effect() {
+        let applied = applied_despite_failure(
+            &batch(OperationKind::Install, &["vim", "broken"]),
+            &installed(&["bash"]),
+            &installed(&["vim", "bash"]),
+        );
+        assert_eq!(applied, HashSet::from(["vim".to_string()]));
+
+        let applied = applied_despite_failure(
+            &batch(OperationKind::Remove, &["nano", "bash"]),
+            &installed(&["nano", "bash"]),
+            &installed(&["bash"]),
+        );
+        assert_eq!(applied, HashSet::from(["nano".to_string()]));
+    }
+
+    #[test]
+    fn packages_unchanged_by_a_failed_batch_are_not_applied() {
+        // vim was installed already, the install did nothing for it
+        let applied = applied_despite_failure(
+            &batch(OperationKind::Install, &["vim", "broken"]),
+            &installed(&["vim"]),
+            &installed(&["vim"]),
+        );
+        assert!(applied.is_empty());
+
+        // ghost was never installed, so its absence is no removal
+        let applied = applied_despite_failure(
+            &batch(OperationKind::Remove, &["ghost", "bash"]),
+            &installed(&["bash"]),
+            &installed(&["bash"]),
+        );
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn failed_updates_and_single_packages_all_fail() {
+        let everything = installed(&["vim", "curl"]);
+        assert!(applied_despite_failure(
+            &batch(OperationKind::Update, &["vim", "curl"]),
+            &installed(&[]),
+            &everything
+        )
+        .is_empty());
+        assert!(applied_despite_failure(
+            &batch(OperationKind::Install, &["vim"]),
+            &installed(&[]),
+            &everything
+        )
+        .is_empty());
+    }
+}
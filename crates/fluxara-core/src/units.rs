@@ -0,0 +1,37 @@
+/// Parse a human readable size such as `12.3 MB`, `4,5 MiB` or `300kB` into bytes
+///
+/// Decimal (kB, MB, GB) and binary (KiB, MiB, GiB) suffixes are both accepted;
+/// a bare number is taken as bytes.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim().replace(',', ".").replace('\u{a0}', " ");
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" | "bytes" => 1.0,
+        "k" | "kb" => 1e3,
+        "m" | "mb" => 1e6,
+        "g" | "gb" => 1e9,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+
+    Some((number * multiplier) as u64)
+}
+
+/// Format a byte count with decimal units, e.g. `12.3 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "kB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
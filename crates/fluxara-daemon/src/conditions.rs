@@ -0,0 +1,130 @@
+//! Network, power and idle conditions, read from NetworkManager, UPower and
+//! logind
+//!
+//! When a service is missing its condition is taken as not holding anything
+//! back: a desktop without UPower is on mains power, a system without
+//! NetworkManager is assumed to have an unmetered connection, and one
+//! without logind is taken as idle.
+
+use tokio::sync::OnceCell;
+
+/// `NMMetered` values meaning the connection is metered
+const NM_METERED_YES: u32 = 1;
+const NM_METERED_GUESS_YES: u32 = 3;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager",
+    gen_blocking = false
+)]
+trait NetworkManager {
+    #[zbus(property)]
+    fn metered(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower",
+    gen_blocking = false
+)]
+trait UPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1",
+    gen_blocking = false
+)]
+trait Login {
+    #[zbus(property)]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+}
+
+/// Reads conditions over the system bus
+pub struct SystemConditions {
+    connection: OnceCell<Option<zbus::Connection>>,
+}
+
+impl Default for SystemConditions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemConditions {
+    /// Conditions from the system bus, connected on first use
+    pub fn new() -> Self {
+        Self {
+            connection: OnceCell::new(),
+        }
+    }
+
+    /// Conditions from the services reachable over `connection`
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        Self {
+            connection: OnceCell::new_with(Some(Some(connection))),
+        }
+    }
+
+    async fn connection(&self) -> Option<&zbus::Connection> {
+        self.connection
+            .get_or_init(|| async {
+                zbus::Connection::system()
+                    .await
+                    .inspect_err(|e| tracing::debug!("No system bus: {}", e))
+                    .ok()
+            })
+            .await
+            .as_ref()
+    }
+
+    /// Whether NetworkManager reports the primary connection as metered
+    pub async fn is_metered(&self) -> bool {
+        let Some(connection) = self.connection().await else {
+            return false;
+        };
+        let metered = match NetworkManagerProxy::new(connection).await {
+            Ok(proxy) => proxy.metered().await,
+            Err(e) => Err(e),
+        };
+        match metered {
+            Ok(metered) => matches!(metered, NM_METERED_YES | NM_METERED_GUESS_YES),
+            Err(e) => {
+                tracing::debug!("Metered state unknown: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Whether UPower reports the machine as running on battery
+    pub async fn on_battery(&self) -> bool {
+        let Some(connection) = self.connection().await else {
+            return false;
+        };
+        let on_battery = match UPowerProxy::new(connection).await {
+            Ok(proxy) => proxy.on_battery().await,
+            Err(e) => Err(e),
+        };
+        on_battery
+            .inspect_err(|e| tracing::debug!("Power state unknown: {}", e))
+            .unwrap_or(false)
+    }
+
+    /// Whether logind reports every session as idle
+    pub async fn is_idle(&self) -> bool {
+        let Some(connection) = self.connection().await else {
+            return true;
+        };
+        let idle = match LoginProxy::new(connection).await {
+            Ok(proxy) => proxy.idle_hint().await,
+            Err(e) => Err(e),
+        };
+        idle.inspect_err(|e| tracing::debug!("Idle state unknown: {}", e))
+            .unwrap_or(true)
+    }
+}
@@ -1,94 +1,696 @@
+//! Background service checking every package source for updates on a
+//! configurable schedule, and applying them as each source's update policy
+//! says
+
+mod conditions;
+mod notify;
+mod schedule;
+mod state;
+mod tray;
+mod unattended;
+
+pub use conditions::SystemConditions;
+pub use notify::{NotificationAction, Notifier, Urgency};
+pub use schedule::Schedule;
+pub use state::DaemonState;
+pub use tray::{TrayAction, TrayIcon};
+pub use unattended::{UnattendedPlan, UnattendedReport};
+
 use anyhow::Result;
-use fluxara_core::{Config, PackageManager};
+use chrono::{DateTime, Local, Utc};
+use fluxara_core::config::UpdatePolicy;
+use fluxara_core::{
+    Config, History, InstallScope, OperationKind, Origin, PackageManager, PackageSource,
+    ProviderRegistry, Transaction, UpdateInfo,
+};
+use fluxara_index::PackageIndex;
+use fluxara_provider_apt::AptProvider;
 use fluxara_provider_flatpak::FlatpakProvider;
-use std::time::Duration;
-use tokio::time;
-use tracing::{error, info};
+use fluxara_provider_pacman::PacmanProvider;
+use fluxara_provider_snap::SnapProvider;
+use futures_util::stream::{BoxStream, StreamExt};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::{self, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+/// Wait before looking again at a check put off for the network or battery
+const RETRY_DELAY: chrono::Duration = chrono::Duration::minutes(15);
+
+/// Longest single sleep; the clock is read again after each one, so that
+/// time spent suspended counts towards the next check
+const MAX_SLEEP: Duration = Duration::from_secs(5 * 60);
+
+/// How long "Snooze" keeps update notifications away
+const SNOOZE: chrono::Duration = chrono::Duration::hours(24);
+
+/// How often the configuration file is looked at for changes, such as the
+/// tray icon being switched off in the store's settings
+const CONFIG_POLL: Duration = Duration::from_secs(5);
+
+/// How often queued downloads look for the system to be idle
+const IDLE_POLL: Duration = Duration::from_secs(60);
+
+/// Providers of every supported source, unavailable ones skipped by the
+/// registry; changes needing root go through the privileged helper
+pub fn provider_registry(config: &Config) -> ProviderRegistry {
+    registry(config.install.flatpak_scope)
+}
+
+/// Providers for unattended updates: Flatpak apps are updated in the user's
+/// installation, which needs no authorization; the other sources ask the
+/// helper under `com.fluxara.Store.unattended`, which never prompts
+pub fn unattended_registry() -> ProviderRegistry {
+    registry_with(InstallScope::User, fluxara_polkit_agent::route_unattended)
+}
+
+fn registry(scope: InstallScope) -> ProviderRegistry {
+    registry_with(scope, fluxara_polkit_agent::route)
+}
+
+fn registry_with(
+    scope: InstallScope,
+    route: fn(Box<dyn PackageManager>, InstallScope) -> Box<dyn PackageManager>,
+) -> ProviderRegistry {
+    let providers: [Box<dyn PackageManager>; 4] = [
+        Box::new(FlatpakProvider::with_scope(scope)),
+        Box::new(AptProvider::new()),
+        Box::new(PacmanProvider::new()),
+        Box::new(SnapProvider::new()),
+    ];
+
+    let mut registry = ProviderRegistry::new();
+    for provider in providers {
+        registry.register(route(provider, scope));
+    }
+    registry
+}
 
 pub struct UpdateDaemon {
     config: Config,
-    flatpak_provider: FlatpakProvider,
+    registry: ProviderRegistry,
+    /// Providers unattended updates are applied with
+    unattended: ProviderRegistry,
+    history: History,
+    conditions: SystemConditions,
+    notifier: Option<Notifier>,
+    tray: Option<TrayIcon>,
+    tray_actions: Option<mpsc::UnboundedReceiver<TrayAction>>,
+    /// Bus for the tray icon instead of a new session bus connection
+    tray_connection: Option<zbus::Connection>,
+    /// Modification time of the configuration file when last loaded
+    config_modified: Option<SystemTime>,
+    state: DaemonState,
+    state_path: PathBuf,
+    /// Updates found by the last check, installed by "Update now"
+    pending: Vec<UpdateInfo>,
+    /// Updates to fetch once the system is idle
+    downloads: Vec<UpdateInfo>,
 }
 
 impl UpdateDaemon {
     pub fn new() -> Result<Self> {
         let config = Config::load()?;
-        let flatpak_provider = FlatpakProvider::new();
+        Ok(Self::with_parts(
+            config.clone(),
+            provider_registry(&config),
+            SystemConditions::new(),
+            DaemonState::path()?,
+        ))
+    }
 
-        Ok(Self {
+    /// Daemon using the given providers and conditions, keeping its state at
+    /// `state_path` and recording the updates it installs in the history next
+    /// to it, `history.jsonl`
+    pub fn with_parts(
+        config: Config,
+        registry: ProviderRegistry,
+        conditions: SystemConditions,
+        state_path: PathBuf,
+    ) -> Self {
+        Self {
             config,
-            flatpak_provider,
-        })
+            registry,
+            unattended: unattended_registry(),
+            history: History::at(state_path.with_file_name("history.jsonl")),
+            conditions,
+            notifier: None,
+            tray: None,
+            tray_actions: None,
+            tray_connection: None,
+            config_modified: config_modified(),
+            state: DaemonState::load(&state_path),
+            state_path,
+            pending: Vec::new(),
+            downloads: Vec::new(),
+        }
+    }
+
+    /// Apply unattended updates with the providers of `registry`
+    pub fn with_unattended_registry(mut self, registry: ProviderRegistry) -> Self {
+        self.unattended = registry;
+        self
+    }
+
+    /// Show notifications through `notifier`
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Show the tray icon on the bus of `connection`
+    pub fn with_tray_connection(mut self, connection: zbus::Connection) -> Self {
+        self.tray_connection = Some(connection);
+        self
+    }
+
+    /// Bus name of the tray icon, while it is shown
+    pub fn tray_name(&self) -> Option<&str> {
+        self.tray.as_ref().map(TrayIcon::name)
+    }
+
+    pub fn state(&self) -> &DaemonState {
+        &self.state
+    }
+
+    /// Updates found by the last check
+    pub fn pending(&self) -> &[UpdateInfo] {
+        &self.pending
     }
 
-    pub async fn run(&self) -> Result<()> {
+    /// Updates waiting for the system to be idle to be downloaded
+    pub fn downloads(&self) -> &[UpdateInfo] {
+        &self.downloads
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
         info!("Starting Fluxara Update Daemon");
 
-        if self.config.ui.tray_enabled {
-            info!("Tray icon enabled");
-            // TODO: Initialize tray icon
+        self.apply_tray_setting().await;
+        if self.notifier.is_none() {
+            match Notifier::session().await {
+                Ok(notifier) => self.notifier = Some(notifier),
+                Err(e) => warn!("Updates will not be notified: {:#}", e),
+            }
+        }
+        let mut actions = match &self.notifier {
+            Some(notifier) => notifier.actions().await.ok(),
+            None => None,
+        };
+
+        loop {
+            let schedule = Schedule::from_config(&self.config.daemon);
+            let next = schedule.next_check(self.state.last_check, Local::now(), schedule.jitter());
+            info!("Next update check at {}", next.format("%Y-%m-%d %H:%M"));
+            match self.wait(next, &mut actions).await {
+                Wake::Quit => return Ok(()),
+                // Asked for from the tray: no reason to wait applies
+                Wake::CheckNow => {}
+                Wake::Due => {
+                    self.reload_config().await;
+                    // Quiet hours may have begun during a suspend, or just been configured
+                    if self
+                        .config
+                        .daemon
+                        .quiet_hours
+                        .is_some_and(|quiet| quiet.contains(Local::now().time()))
+                    {
+                        continue;
+                    }
+                    if let Some(reason) = self.deferral_reason().await {
+                        info!("Putting off the update check: {}", reason);
+                        // Keep answering the tray and notifications meanwhile
+                        match self.wait(Local::now() + RETRY_DELAY, &mut actions).await {
+                            Wake::Quit => return Ok(()),
+                            Wake::Due => continue,
+                            Wake::CheckNow => {}
+                        }
+                    }
+                }
+            }
+
+            match self.check_updates().await {
+                Ok(updates) => {
+                    let (updates, report) = self.apply_policy(updates).await;
+                    self.report(&report).await;
+                    self.announce(updates).await;
+                    self.download_when_idle().await;
+                }
+                Err(e) => error!("Failed to check updates: {}", e),
+            }
+            self.state.last_check = Some(Utc::now());
+            self.save_state();
+
+            if let Err(e) = self.refresh_index().await {
+                error!("Failed to refresh package index: {}", e);
+            }
         }
+    }
 
-        let mut interval = time::interval(Duration::from_secs(3600)); // Check every hour
+    /// Handle notification buttons, tray menu entries and configuration
+    /// changes until `next`, or until the tray asks for a check or to quit;
+    /// while updates are paused only the tray ends the wait
+    async fn wait(
+        &mut self,
+        next: DateTime<Local>,
+        actions: &mut Option<BoxStream<'static, NotificationAction>>,
+    ) -> Wake {
+        // Made once, so that other wake-ups do not push the polls back
+        let start = time::Instant::now();
+        let mut idle_poll = time::interval_at(start + IDLE_POLL, IDLE_POLL);
+        let mut config_poll = time::interval_at(start + CONFIG_POLL, CONFIG_POLL);
+        idle_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        config_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = sleep_until(next), if !self.state.paused => return Wake::Due,
+                Some(action) = next_action(actions) => self.handle_action(action).await,
+                Some(action) = next_tray_action(&mut self.tray_actions) => {
+                    if let Some(wake) = self.handle_tray_action(action).await {
+                        return wake;
+                    }
+                }
+                _ = idle_poll.tick(), if !self.downloads.is_empty() => {
+                    self.download_when_idle().await;
+                }
+                _ = config_poll.tick() => {
+                    if config_modified() != self.config_modified {
+                        self.reload_config().await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pick up configuration changes
+    async fn reload_config(&mut self) {
+        self.config_modified = config_modified();
+        match Config::load() {
+            Ok(config) => self.apply_config(config).await,
+            Err(e) => warn!("Keeping the previous configuration: {:#}", e),
+        }
+    }
+
+    /// Switch to `config`, showing or hiding the tray icon as it says
+    pub async fn apply_config(&mut self, config: Config) {
+        if config.install.flatpak_scope != self.config.install.flatpak_scope {
+            self.registry = provider_registry(&config);
+        }
+        self.config = config;
+        self.apply_tray_setting().await;
+    }
+
+    async fn apply_tray_setting(&mut self) {
+        if !self.config.ui.tray_enabled {
+            if let Some(tray) = self.tray.take() {
+                info!("Hiding tray icon");
+                tray.hide().await;
+            }
+            self.tray_actions = None;
+            return;
+        }
+        if self.tray.is_some() {
+            return;
+        }
 
-            if let Err(e) = self.check_updates().await {
-                error!("Failed to check updates: {}", e);
+        let (pending, paused) = (self.pending.len(), self.state.paused);
+        let shown = match &self.tray_connection {
+            Some(connection) => {
+                TrayIcon::with_connection(connection.clone(), pending, paused).await
             }
+            None => TrayIcon::session(pending, paused).await,
+        };
+        match shown {
+            Ok((tray, actions)) => {
+                info!("Showing tray icon");
+                self.tray = Some(tray);
+                self.tray_actions = Some(actions);
+            }
+            Err(e) => warn!("No tray icon: {:#}", e),
+        }
+    }
+
+    /// Act on a tray menu entry; the wake-up it calls for, if any
+    pub async fn handle_tray_action(&mut self, action: TrayAction) -> Option<Wake> {
+        info!("Tray action: {}", action.label());
+        match action {
+            TrayAction::OpenStore => open_store(),
+            TrayAction::UpdateAll => self.install_pending().await,
+            TrayAction::CheckNow => return Some(Wake::CheckNow),
+            TrayAction::PauseUpdates => {
+                self.state.paused = !self.state.paused;
+                self.save_state();
+                if let Some(tray) = &self.tray {
+                    if let Err(e) = tray.set_paused(self.state.paused).await {
+                        warn!("Failed to update tray icon: {:#}", e);
+                    }
+                }
+            }
+            TrayAction::Quit => return Some(Wake::Quit),
+        }
+        None
+    }
+
+    /// Why a due check should wait, if it should
+    pub async fn deferral_reason(&self) -> Option<&'static str> {
+        let daemon = &self.config.daemon;
+        if !daemon.check_on_metered && self.conditions.is_metered().await {
+            return Some("the network connection is metered");
+        }
+        if !daemon.check_on_battery && self.conditions.on_battery().await {
+            return Some("running on battery");
         }
+        None
     }
 
-    async fn check_updates(&self) -> Result<()> {
+    /// Re-ingest package metadata once the local search index is older than configured
+    async fn refresh_index(&self) -> Result<()> {
+        if !self.config.index.enabled {
+            return Ok(());
+        }
+
+        let mut index = PackageIndex::open()?;
+        let max_age = chrono::Duration::hours(self.config.index.max_age_hours as i64);
+        if !index.is_stale(max_age)? {
+            return Ok(());
+        }
+
+        info!("Refreshing package index...");
+        index.refresh(&self.registry, None).await?;
+        Ok(())
+    }
+
+    /// Updates from every available source, leaving out held packages
+    ///
+    /// A source that fails is logged and skipped; only when every source
+    /// fails is the check an error.
+    pub async fn check_updates(&self) -> Result<Vec<UpdateInfo>> {
         info!("Checking for updates...");
 
-        // Check Flatpak updates
-        let updates = self.flatpak_provider.list_updates().await?;
+        let mut updates = Vec::new();
+        let mut providers = 0;
+        let mut failures = 0;
+        for provider in self.registry.available() {
+            if self.config.updates.policy.get(provider.source()) == UpdatePolicy::Off {
+                continue;
+            }
+            providers += 1;
+            match provider.list_updates().await {
+                Ok(found) => updates.extend(found),
+                Err(e) => {
+                    warn!("Failed to check {} for updates: {:#}", provider.source(), e);
+                    failures += 1;
+                }
+            }
+        }
+        if providers > 0 && failures == providers {
+            anyhow::bail!("No package source could be checked");
+        }
+        self.config.updates.retain_unheld(&mut updates);
 
         if !updates.is_empty() {
             info!("Found {} updates available", updates.len());
-            // TODO: Show notification
-            self.show_notification(&format!("{} updates available", updates.len()))?;
         }
+        Ok(updates)
+    }
 
-        Ok(())
+    /// Install and queue for download what the update policy says, holds
+    /// left out; returns the updates still to notify, with the report of
+    /// what was installed
+    ///
+    /// System-wide Flatpak apps would need authorization, so with
+    /// `flatpak_scope = "system"` they are only notified, while the user's
+    /// own installation is checked and updated on its own.
+    pub async fn apply_policy(
+        &mut self,
+        mut updates: Vec<UpdateInfo>,
+    ) -> (Vec<UpdateInfo>, UnattendedReport) {
+        let updates_config = &self.config.updates;
+        if !updates_config.policy.is_unattended() {
+            return (updates, UnattendedReport::default());
+        }
+
+        let mut notify = Vec::new();
+        if self.config.install.flatpak_scope == InstallScope::System
+            && matches!(
+                updates_config.policy.get(PackageSource::Flatpak),
+                UpdatePolicy::DownloadOnly | UpdatePolicy::Install
+            )
+        {
+            let (system, others) = updates
+                .into_iter()
+                .partition(|update| update.source == PackageSource::Flatpak);
+            updates = others;
+            notify = system;
+            if let Some(flatpak) = self.unattended.get(PackageSource::Flatpak) {
+                match flatpak.list_updates().await {
+                    Ok(mut user) => {
+                        updates_config.retain_unheld(&mut user);
+                        updates.extend(user);
+                    }
+                    Err(e) => warn!("Failed to check user Flatpak apps for updates: {:#}", e),
+                }
+            }
+        }
+
+        let plan = UnattendedPlan::new(updates_config, updates);
+        notify.extend(plan.notify);
+        notify.extend(plan.download.iter().cloned());
+        self.downloads = plan.download;
+
+        let report = self.install_unattended(&plan.install).await;
+        // What failed to install is still pending
+        notify.extend(plan.install.into_iter().filter(|update| {
+            report
+                .failed()
+                .any(|o| o.operation.package_id == update.package_id)
+        }));
+        (notify, report)
     }
 
-    fn show_notification(&self, message: &str) -> Result<()> {
-        // Stub implementation - would use libnotify or similar
-        println!("Notification: {}", message);
-        Ok(())
+    async fn install_unattended(&self, updates: &[UpdateInfo]) -> UnattendedReport {
+        if updates.is_empty() {
+            return UnattendedReport::default();
+        }
+
+        let mut transaction = Transaction::new();
+        for update in updates {
+            transaction.add(OperationKind::Update, &update.package_id, update.source);
+        }
+        info!("Installing {} updates unattended", transaction.len());
+        let (outcomes, entry) = transaction
+            .execute_recorded(&self.unattended, &self.history, Origin::Unattended)
+            .await;
+        let history_id = match entry {
+            Ok(entry) => Some(entry.id),
+            Err(e) => {
+                warn!(
+                    "Failed to record unattended updates in the history: {:#}",
+                    e
+                );
+                None
+            }
+        };
+        UnattendedReport {
+            outcomes,
+            history_id,
+        }
+    }
+
+    /// Tell what an unattended run installed, in the log and a notification
+    async fn report(&self, report: &UnattendedReport) {
+        if report.is_empty() {
+            return;
+        }
+        let (summary, body, urgency) = report.summary();
+        info!("{}: {}", summary, body.replace('\n', "; "));
+        if let Some(notifier) = &self.notifier {
+            if let Err(e) = notifier.report(&summary, &body, urgency).await {
+                warn!("Failed to show notification: {:#}", e);
+            }
+        }
+    }
+
+    /// Download the queued updates if the system is idle, or leave them
+    /// queued; returns the updates downloaded
+    pub async fn download_when_idle(&mut self) -> Vec<UpdateInfo> {
+        if self.downloads.is_empty() || !self.conditions.is_idle().await {
+            return vec![];
+        }
+        if !self.config.daemon.check_on_metered && self.conditions.is_metered().await {
+            return vec![];
+        }
+
+        let downloads = std::mem::take(&mut self.downloads);
+        let mut downloaded = Vec::new();
+        for source in PackageSource::ALL {
+            let batch: Vec<&UpdateInfo> =
+                downloads.iter().filter(|u| u.source == *source).collect();
+            if batch.is_empty() {
+                continue;
+            }
+            let Some(provider) = self.unattended.get(*source) else {
+                continue;
+            };
+            let ids: Vec<String> = batch.iter().map(|u| u.package_id.clone()).collect();
+            match provider.download_updates(&ids).await {
+                Ok(()) => {
+                    info!("Downloaded {} updates: {}", source, ids.join(", "));
+                    downloaded.extend(batch.into_iter().cloned());
+                }
+                Err(e) => warn!("Failed to download {} updates: {:#}", source, e),
+            }
+        }
+        downloaded
+    }
+
+    /// Keep `updates` for "Update now" and notify them, unless snoozed;
+    /// a notification about updates that are gone is withdrawn
+    pub async fn announce(&mut self, updates: Vec<UpdateInfo>) {
+        self.pending = updates;
+        self.show_pending_in_tray().await;
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+
+        let result = if self.pending.is_empty() {
+            notifier.close().await
+        } else if let Some(until) = self.state.snoozed_until.filter(|until| *until > Utc::now()) {
+            info!("Update notifications snoozed until {}", until);
+            Ok(())
+        } else {
+            notifier.notify_updates(&self.pending).await.map(|_| ())
+        };
+        if let Err(e) = result {
+            warn!("Failed to notify updates: {:#}", e);
+        }
+    }
+
+    /// Act on a button pressed on the updates notification
+    pub async fn handle_action(&mut self, action: NotificationAction) {
+        info!("Notification action: {}", action.label());
+        match action {
+            NotificationAction::UpdateNow => self.install_pending().await,
+            NotificationAction::View => open_store(),
+            NotificationAction::Snooze => {
+                self.state.snoozed_until = Some(Utc::now() + SNOOZE);
+                self.save_state();
+                if let Some(notifier) = &self.notifier {
+                    if let Err(e) = notifier.close().await {
+                        warn!("Failed to close notification: {:#}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Install the updates of the last check and record them in the history
+    async fn install_pending(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let mut transaction = Transaction::new();
+        for update in &self.pending {
+            transaction.add(OperationKind::Update, &update.package_id, update.source);
+        }
+        self.notify("Installing updates", "", Urgency::Low).await;
+        let (outcomes, entry) = transaction
+            .execute_recorded(&self.registry, &self.history, Origin::User)
+            .await;
+        let failed = outcomes.iter().filter(|o| !o.succeeded()).count();
+        let total = outcomes.len();
+        if let Err(e) = entry {
+            warn!("Failed to record the updates in the history: {:#}", e);
+        }
+        self.pending.clear();
+        self.show_pending_in_tray().await;
+
+        if failed == 0 {
+            let body = format!("{} packages updated", total);
+            self.notify("Updates installed", &body, Urgency::Normal)
+                .await;
+        } else {
+            let body = format!("{} of {} updates failed", failed, total);
+            self.notify("Some updates failed", &body, Urgency::Critical)
+                .await;
+        }
+    }
+
+    async fn notify(&self, summary: &str, body: &str, urgency: Urgency) {
+        if let Some(notifier) = &self.notifier {
+            if let Err(e) = notifier.notify(summary, body, urgency).await {
+                warn!("Failed to show notification: {:#}", e);
+            }
+        }
+    }
+
+    async fn show_pending_in_tray(&self) {
+        if let Some(tray) = &self.tray {
+            if let Err(e) = tray.set_pending(self.pending.len()).await {
+                warn!("Failed to update tray icon: {:#}", e);
+            }
+        }
+    }
+
+    fn save_state(&self) {
+        if let Err(e) = self.state.save(&self.state_path) {
+            error!("Failed to save daemon state: {:#}", e);
+        }
     }
 }
 
-pub struct TrayIcon {
-    enabled: bool,
+/// Why the daemon stopped waiting for the next scheduled check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wake {
+    /// The check is due
+    Due,
+    /// "Check Now" was chosen from the tray
+    CheckNow,
+    /// "Quit" was chosen from the tray
+    Quit,
 }
 
-impl TrayIcon {
-    pub fn new(enabled: bool) -> Self {
-        Self { enabled }
+fn open_store() {
+    if let Err(e) = tokio::process::Command::new("fluxara-store").spawn() {
+        error!("Failed to open Fluxara Store: {}", e);
     }
+}
 
-    pub fn show(&self) -> Result<()> {
-        if !self.enabled {
-            return Ok(());
-        }
+/// Modification time of the configuration file, `None` while there is none
+fn config_modified() -> Option<SystemTime> {
+    let path = Config::config_path().ok()?;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
 
-        // Stub implementation - would use system tray library
-        info!("Showing tray icon");
-        Ok(())
+/// Next entry chosen from the tray menu; never resolves without a tray
+async fn next_tray_action(
+    actions: &mut Option<mpsc::UnboundedReceiver<TrayAction>>,
+) -> Option<TrayAction> {
+    match actions {
+        Some(actions) => actions.recv().await,
+        None => std::future::pending().await,
     }
+}
 
-    pub fn hide(&self) -> Result<()> {
-        info!("Hiding tray icon");
-        Ok(())
+/// Next button pressed on the notification; never resolves without notifications
+async fn next_action(
+    actions: &mut Option<BoxStream<'static, NotificationAction>>,
+) -> Option<NotificationAction> {
+    match actions {
+        Some(actions) => actions.next().await,
+        None => std::future::pending().await,
     }
+}
 
-    pub fn show_menu(&self) -> Result<()> {
-        // Menu items: Open Store, Update All, Exit
-        info!("Showing tray menu");
-        Ok(())
+/// Sleep until the wall clock reaches `time`
+async fn sleep_until(time: DateTime<Local>) {
+    while let Ok(remaining) = (time - Local::now()).to_std() {
+        if remaining.is_zero() {
+            break;
+        }
+        time::sleep(remaining.min(MAX_SLEEP)).await;
     }
 }
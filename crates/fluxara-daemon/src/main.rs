@@ -4,7 +4,7 @@ use fluxara_daemon::UpdateDaemon;
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
-    let daemon = UpdateDaemon::new()?;
+    let mut daemon = UpdateDaemon::new()?;
     daemon.run().await?;
 
     Ok(())
@@ -0,0 +1,256 @@
+//! Desktop notifications over `org.freedesktop.Notifications`
+//!
+//! The daemon keeps a single notification about pending updates and replaces
+//! it on every check instead of stacking new ones. Its buttons come back as
+//! [`NotificationAction`]s from [`Notifier::actions`]. Reports of unattended
+//! updates are a second notification, so that they do not hide the first.
+
+use anyhow::{Context, Result};
+use fluxara_core::UpdateInfo;
+use futures_util::stream::{BoxStream, StreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use zbus::zvariant::Value;
+
+/// Desktop file of the store, for servers grouping notifications by app
+const DESKTOP_ENTRY: &str = "com.fluxara.Store";
+const APP_NAME: &str = "Fluxara Store";
+const ICON: &str = "system-software-update";
+
+/// Packages named in the body before the rest are counted
+const LISTED_PACKAGES: usize = 5;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications",
+    gen_blocking = false
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+    fn get_capabilities(&self) -> zbus::Result<Vec<String>>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+}
+
+/// A button of the updates notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationAction {
+    /// Install the pending updates
+    UpdateNow,
+    /// Open the store
+    View,
+    /// Stop notifying for a while
+    Snooze,
+}
+
+impl NotificationAction {
+    pub const ALL: &'static [NotificationAction] = &[
+        NotificationAction::UpdateNow,
+        NotificationAction::View,
+        NotificationAction::Snooze,
+    ];
+
+    /// Key sent to the notification server and returned when invoked
+    pub fn key(&self) -> &'static str {
+        match self {
+            NotificationAction::UpdateNow => "update",
+            NotificationAction::View => "view",
+            NotificationAction::Snooze => "snooze",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::UpdateNow => "Update now",
+            NotificationAction::View => "View",
+            NotificationAction::Snooze => "Snooze",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|action| action.key() == key)
+    }
+}
+
+/// Urgency levels of the notification specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Urgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+/// Shows the daemon's notification on the session's notification server
+pub struct Notifier {
+    proxy: NotificationsProxy<'static>,
+    supports_actions: bool,
+    /// Id of the notification currently shown, 0 for none
+    current: Arc<AtomicU32>,
+    /// Id of the last report shown, 0 for none
+    report: AtomicU32,
+}
+
+impl Notifier {
+    /// Notifier for the notification server of the user's session
+    pub async fn session() -> Result<Self> {
+        let connection = zbus::Connection::session()
+            .await
+            .context("Failed to connect to the session bus")?;
+        Self::with_connection(connection).await
+    }
+
+    /// Notifier for the notification server reachable over `connection`
+    pub async fn with_connection(connection: zbus::Connection) -> Result<Self> {
+        let proxy = NotificationsProxy::new(&connection).await?;
+        let supports_actions = proxy
+            .get_capabilities()
+            .await
+            .context("No notification server is running")?
+            .iter()
+            .any(|capability| capability == "actions");
+
+        Ok(Self {
+            proxy,
+            supports_actions,
+            current: Arc::new(AtomicU32::new(0)),
+            report: AtomicU32::new(0),
+        })
+    }
+
+    /// Show how many updates are pending, replacing the previous notification;
+    /// critical when any of them fixes security issues
+    pub async fn notify_updates(&self, updates: &[UpdateInfo]) -> Result<u32> {
+        let security = updates.iter().filter(|update| update.security).count();
+        let summary = match updates.len() {
+            1 => "1 update available".to_string(),
+            n => format!("{} updates available", n),
+        };
+
+        let ids: Vec<&str> = updates.iter().map(|u| u.package_id.as_str()).collect();
+        let mut body = package_list(&ids);
+        if security > 0 {
+            body = format!("{} fixing security issues: {}", security, body);
+        }
+
+        let urgency = if security > 0 {
+            Urgency::Critical
+        } else {
+            Urgency::Normal
+        };
+        self.show(
+            &self.current,
+            &summary,
+            &body,
+            urgency,
+            NotificationAction::ALL,
+        )
+        .await
+    }
+
+    /// Show a notification without buttons, replacing the previous one
+    pub async fn notify(&self, summary: &str, body: &str, urgency: Urgency) -> Result<u32> {
+        self.show(&self.current, summary, body, urgency, &[]).await
+    }
+
+    /// Show a report beside the updates notification, replacing the previous report
+    pub async fn report(&self, summary: &str, body: &str, urgency: Urgency) -> Result<u32> {
+        self.show(&self.report, summary, body, urgency, &[]).await
+    }
+
+    /// Withdraw the notification currently shown
+    pub async fn close(&self) -> Result<()> {
+        let id = self.current.swap(0, Ordering::SeqCst);
+        if id != 0 {
+            self.proxy.close_notification(id).await?;
+        }
+        Ok(())
+    }
+
+    /// Buttons the user presses on the notifications shown by this notifier
+    pub async fn actions(&self) -> Result<BoxStream<'static, NotificationAction>> {
+        let current = self.current.clone();
+        let signals = self.proxy.receive_action_invoked().await?;
+        Ok(signals
+            .filter_map(move |signal| {
+                let action = signal.args().ok().and_then(|args| {
+                    (args.id != 0 && args.id == current.load(Ordering::SeqCst))
+                        .then(|| NotificationAction::from_key(args.action_key))
+                        .flatten()
+                });
+                async move { action }
+            })
+            .boxed())
+    }
+
+    /// Show a notification in place of the one whose id `slot` holds
+    async fn show(
+        &self,
+        slot: &AtomicU32,
+        summary: &str,
+        body: &str,
+        urgency: Urgency,
+        actions: &[NotificationAction],
+    ) -> Result<u32> {
+        let actions: Vec<&str> = if self.supports_actions {
+            actions
+                .iter()
+                .flat_map(|action| [action.key(), action.label()])
+                .collect()
+        } else {
+            vec![]
+        };
+        let hints = HashMap::from([
+            ("urgency", Value::U8(urgency as u8)),
+            ("desktop-entry", Value::from(DESKTOP_ENTRY)),
+        ]);
+
+        let id = self
+            .proxy
+            .notify(
+                APP_NAME,
+                slot.load(Ordering::SeqCst),
+                ICON,
+                summary,
+                body,
+                &actions,
+                hints,
+                -1,
+            )
+            .await
+            .context("Failed to show notification")?;
+        slot.store(id, Ordering::SeqCst);
+        Ok(id)
+    }
+}
+
+/// The first few of `ids`, and how many more there are
+pub(crate) fn package_list(ids: &[&str]) -> String {
+    let mut list = ids
+        .iter()
+        .take(LISTED_PACKAGES)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if ids.len() > LISTED_PACKAGES {
+        list.push_str(&format!(", {} more", ids.len() - LISTED_PACKAGES));
+    }
+    list
+}
@@ -0,0 +1,67 @@
+//! When the next update check is due
+
+use chrono::{DateTime, Days, Duration, TimeZone, Utc};
+use fluxara_core::config::{DaemonConfig, QuietHours};
+
+/// Check interval, jitter and quiet hours from the configuration
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub interval: Duration,
+    pub max_jitter: Duration,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Schedule {
+    pub fn from_config(config: &DaemonConfig) -> Self {
+        Self {
+            // A zero interval would check in a tight loop
+            interval: Duration::hours(config.check_interval_hours.max(1) as i64),
+            max_jitter: Duration::minutes(config.jitter_minutes as i64),
+            quiet_hours: config.quiet_hours,
+        }
+    }
+
+    /// A random delay between zero and the configured jitter
+    pub fn jitter(&self) -> Duration {
+        let max = self.max_jitter.num_seconds().max(0) as u64;
+        Duration::seconds(fastrand::u64(0..=max) as i64)
+    }
+
+    /// When to check after one at `last`: an interval later plus `jitter`,
+    /// not before `now`, and moved to the end of quiet hours if it falls
+    /// inside them; quiet hours are read in the time zone of `now`
+    pub fn next_check<Tz: TimeZone>(
+        &self,
+        last: Option<DateTime<Utc>>,
+        now: DateTime<Tz>,
+        jitter: Duration,
+    ) -> DateTime<Tz> {
+        let due = match last {
+            Some(last) => last.with_timezone(&now.timezone()) + self.interval,
+            None => now.clone(),
+        };
+        self.after_quiet_hours(due.max(now) + jitter)
+    }
+
+    /// `time`, or the end of the quiet hours it falls in
+    pub fn after_quiet_hours<Tz: TimeZone>(&self, time: DateTime<Tz>) -> DateTime<Tz> {
+        let Some(quiet) = self.quiet_hours else {
+            return time;
+        };
+        let local = time.naive_local();
+        if !quiet.contains(local.time()) {
+            return time;
+        }
+
+        // Quiet hours wrapping past midnight end on the next day
+        let mut end = local.date().and_time(quiet.end);
+        if end <= local {
+            end = end + Days::new(1);
+        }
+        time.timezone()
+            .from_local_datetime(&end)
+            .earliest()
+            // The end fell into a daylight saving gap; an hour on is past it
+            .unwrap_or_else(|| time + Duration::hours(1))
+    }
+}
@@ -0,0 +1,48 @@
+//! What the daemon remembers across restarts
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use fluxara_core::Config;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Persisted daemon state, in `~/.local/share/fluxara/daemon.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonState {
+    /// When updates were last checked
+    pub last_check: Option<DateTime<Utc>>,
+    /// Update notifications are held back until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Scheduled checks are paused from the tray
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl DaemonState {
+    pub fn path() -> Result<PathBuf> {
+        Ok(Config::data_dir()?.join("daemon.json"))
+    }
+
+    /// State saved at `path`; a missing or unreadable file is a fresh start
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written aside first so a crash never leaves half a file
+        let partial = path.with_extension("json.part");
+        fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&partial, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
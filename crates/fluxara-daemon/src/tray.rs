@@ -0,0 +1,488 @@
+//! Tray icon as a StatusNotifierItem with a `com.canonical.dbusmenu` menu
+//!
+//! The item is registered with the session's `org.kde.StatusNotifierWatcher`,
+//! which the panel (KDE Plasma, most others, GNOME with the AppIndicator
+//! extension) watches. Menu entries come back as [`TrayAction`]s.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Str, Type, Value};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const ICON: &str = "system-software-update";
+const TITLE: &str = "Fluxara Store";
+
+/// Id of the separator before "Quit"
+const SEPARATOR: i32 = 5;
+
+#[zbus::proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher",
+    gen_blocking = false
+)]
+trait StatusNotifierWatcher {
+    fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+}
+
+/// An entry of the tray menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    OpenStore,
+    UpdateAll,
+    CheckNow,
+    /// Toggle scheduled checks
+    PauseUpdates,
+    Quit,
+}
+
+impl TrayAction {
+    pub const ALL: &'static [TrayAction] = &[
+        TrayAction::OpenStore,
+        TrayAction::UpdateAll,
+        TrayAction::CheckNow,
+        TrayAction::PauseUpdates,
+        TrayAction::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrayAction::OpenStore => "Open Store",
+            TrayAction::UpdateAll => "Update All",
+            TrayAction::CheckNow => "Check Now",
+            TrayAction::PauseUpdates => "Pause Updates",
+            TrayAction::Quit => "Quit",
+        }
+    }
+
+    /// Id of the entry in the menu layout
+    pub fn menu_id(&self) -> i32 {
+        match self {
+            TrayAction::OpenStore => 1,
+            TrayAction::UpdateAll => 2,
+            TrayAction::CheckNow => 3,
+            TrayAction::PauseUpdates => 4,
+            TrayAction::Quit => 6,
+        }
+    }
+
+    pub fn from_menu_id(id: i32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.menu_id() == id)
+    }
+}
+
+/// What the icon and menu show
+#[derive(Debug, Default)]
+struct TrayState {
+    pending: usize,
+    paused: bool,
+    /// Bumped on every menu change, as dbusmenu clients cache layouts by it
+    revision: u32,
+}
+
+impl TrayState {
+    fn status(&self) -> &'static str {
+        if self.pending > 0 {
+            "Active"
+        } else {
+            "Passive"
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        let updates = match self.pending {
+            0 => "Up to date".to_string(),
+            1 => "1 update available".to_string(),
+            n => format!("{} updates available", n),
+        };
+        if self.paused {
+            format!("{}, checks paused", updates)
+        } else {
+            updates
+        }
+    }
+
+    /// Properties of menu entry `id`, `None` for an unknown id
+    fn properties(&self, id: i32) -> Option<HashMap<String, OwnedValue>> {
+        let mut properties = HashMap::new();
+        if id == 0 {
+            properties.insert("children-display".into(), text("submenu"));
+            return Some(properties);
+        }
+        if id == SEPARATOR {
+            properties.insert("type".into(), text("separator"));
+            return Some(properties);
+        }
+
+        let action = TrayAction::from_menu_id(id)?;
+        let label = match action {
+            TrayAction::UpdateAll if self.pending > 0 => {
+                format!("{} ({})", action.label(), self.pending)
+            }
+            _ => action.label().to_string(),
+        };
+        properties.insert("label".into(), text(&label));
+        match action {
+            TrayAction::UpdateAll => {
+                properties.insert("enabled".into(), (self.pending > 0).into());
+            }
+            TrayAction::PauseUpdates => {
+                properties.insert("toggle-type".into(), text("checkmark"));
+                properties.insert("toggle-state".into(), i32::from(self.paused).into());
+            }
+            _ => {}
+        }
+        Some(properties)
+    }
+
+    fn layout(&self, id: i32, depth: i32, names: &[String]) -> Option<Layout> {
+        let children = if id == 0 && depth != 0 {
+            (1..=TrayAction::Quit.menu_id())
+                .filter_map(|child| self.layout(child, depth - 1, names))
+                .map(|layout| OwnedValue::try_from(layout).expect("menu layouts hold no fds"))
+                .collect()
+        } else {
+            vec![]
+        };
+        let mut properties = self.properties(id)?;
+        if !names.is_empty() {
+            properties.retain(|name, _| names.contains(name));
+        }
+        Some(Layout {
+            id,
+            properties,
+            children,
+        })
+    }
+}
+
+fn text(value: &str) -> OwnedValue {
+    Str::from(value.to_string()).into()
+}
+
+/// A menu entry and its children, `(ia{sv}av)` in dbusmenu
+#[derive(Debug, Type, Value, zbus::zvariant::OwnedValue, serde::Serialize)]
+struct Layout {
+    id: i32,
+    properties: HashMap<String, OwnedValue>,
+    children: Vec<OwnedValue>,
+}
+
+/// Icon name, icon pixmaps, title and text of a tooltip, `(sa(iiay)ss)`
+type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+struct Item {
+    state: Arc<Mutex<TrayState>>,
+    actions: mpsc::UnboundedSender<TrayAction>,
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierItem")]
+impl Item {
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "SystemServices"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "fluxara-daemon"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        TITLE
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        self.state.lock().unwrap().status()
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        ICON
+    }
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> ToolTip {
+        let tooltip = self.state.lock().unwrap().tooltip();
+        (ICON.to_string(), vec![], TITLE.to_string(), tooltip)
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).expect("valid object path")
+    }
+
+    /// Left click opens the store
+    fn activate(&self, _x: i32, _y: i32) {
+        let _ = self.actions.send(TrayAction::OpenStore);
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {}
+
+    fn context_menu(&self, _x: i32, _y: i32) {}
+
+    fn scroll(&self, _delta: i32, _orientation: &str) {}
+
+    #[zbus(signal)]
+    async fn new_status(emitter: &SignalEmitter<'_>, status: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_tool_tip(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+struct Menu {
+    state: Arc<Mutex<TrayState>>,
+    actions: mpsc::UnboundedSender<TrayAction>,
+}
+
+impl Menu {
+    /// Forward a click on entry `id`; false for an unknown id
+    fn clicked(&self, id: i32, event: &str) -> bool {
+        match TrayAction::from_menu_id(id) {
+            Some(action) => {
+                if event == "clicked" {
+                    let _ = self.actions.send(action);
+                }
+                true
+            }
+            None => id == 0 || id == SEPARATOR,
+        }
+    }
+}
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        property_names: Vec<String>,
+    ) -> zbus::fdo::Result<(u32, Layout)> {
+        let state = self.state.lock().unwrap();
+        let layout = state
+            .layout(parent_id, recursion_depth, &property_names)
+            .ok_or_else(|| unknown(parent_id))?;
+        Ok((state.revision, layout))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        let state = self.state.lock().unwrap();
+        let ids = if ids.is_empty() {
+            (0..=TrayAction::Quit.menu_id()).collect()
+        } else {
+            ids
+        };
+        ids.into_iter()
+            .filter_map(|id| {
+                let mut properties = state.properties(id)?;
+                if !property_names.is_empty() {
+                    properties.retain(|name, _| property_names.contains(name));
+                }
+                Some((id, properties))
+            })
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: &str) -> zbus::fdo::Result<OwnedValue> {
+        self.state
+            .lock()
+            .unwrap()
+            .properties(id)
+            .and_then(|mut properties| properties.remove(name))
+            .ok_or_else(|| unknown(id))
+    }
+
+    fn event(
+        &self,
+        id: i32,
+        event_id: &str,
+        _data: OwnedValue,
+        _timestamp: u32,
+    ) -> zbus::fdo::Result<()> {
+        if self.clicked(id, event_id) {
+            Ok(())
+        } else {
+            Err(unknown(id))
+        }
+    }
+
+    /// Ids of the events' entries that do not exist
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        events
+            .into_iter()
+            .filter(|(id, event_id, _, _)| !self.clicked(*id, event_id))
+            .map(|(id, ..)| id)
+            .collect()
+    }
+
+    /// The menu is built up front, so nothing ever needs updating before showing
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        (vec![], vec![])
+    }
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        vec![]
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+}
+
+fn unknown(id: i32) -> zbus::fdo::Error {
+    zbus::fdo::Error::InvalidArgs(format!("No menu entry {}", id))
+}
+
+/// The daemon's icon in the panel
+pub struct TrayIcon {
+    connection: zbus::Connection,
+    name: String,
+    state: Arc<Mutex<TrayState>>,
+}
+
+impl TrayIcon {
+    /// Show the icon in the user's session; its menu entries arrive on the receiver
+    pub async fn session(
+        pending: usize,
+        paused: bool,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<TrayAction>)> {
+        let connection = zbus::Connection::session()
+            .await
+            .context("Failed to connect to the session bus")?;
+        Self::with_connection(connection, pending, paused).await
+    }
+
+    /// Show the icon through the watcher reachable over `connection`
+    pub async fn with_connection(
+        connection: zbus::Connection,
+        pending: usize,
+        paused: bool,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<TrayAction>)> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let state = Arc::new(Mutex::new(TrayState {
+            pending,
+            paused,
+            revision: 1,
+        }));
+
+        let object_server = connection.object_server();
+        object_server
+            .at(
+                ITEM_PATH,
+                Item {
+                    state: state.clone(),
+                    actions: sender.clone(),
+                },
+            )
+            .await?;
+        object_server
+            .at(
+                MENU_PATH,
+                Menu {
+                    state: state.clone(),
+                    actions: sender,
+                },
+            )
+            .await?;
+
+        let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+        connection.request_name(name.as_str()).await?;
+        let tray = Self {
+            connection,
+            name,
+            state,
+        };
+
+        let registered = async {
+            StatusNotifierWatcherProxy::new(&tray.connection)
+                .await?
+                .register_status_notifier_item(&tray.name)
+                .await
+        };
+        if let Err(e) = registered.await {
+            tray.hide().await;
+            return Err(e).context("No StatusNotifierWatcher is running to show the tray icon");
+        }
+        Ok((tray, receiver))
+    }
+
+    /// Bus name the item is registered under
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Show `pending` updates in the tooltip and the "Update All" entry
+    pub async fn set_pending(&self, pending: usize) -> Result<()> {
+        self.update(|state| state.pending = pending).await
+    }
+
+    /// Tick "Pause Updates"
+    pub async fn set_paused(&self, paused: bool) -> Result<()> {
+        self.update(|state| state.paused = paused).await
+    }
+
+    /// Withdraw the icon; the watcher drops it once the bus name is gone
+    pub async fn hide(self) {
+        let _ = self.connection.release_name(self.name.as_str()).await;
+        let object_server = self.connection.object_server();
+        let _ = object_server.remove::<Item, _>(ITEM_PATH).await;
+        let _ = object_server.remove::<Menu, _>(MENU_PATH).await;
+    }
+
+    async fn update(&self, change: impl FnOnce(&mut TrayState)) -> Result<()> {
+        let (status, revision) = {
+            let mut state = self.state.lock().unwrap();
+            change(&mut state);
+            state.revision += 1;
+            (state.status(), state.revision)
+        };
+
+        let item = SignalEmitter::new(&self.connection, ITEM_PATH)?;
+        Item::new_status(&item, status).await?;
+        Item::new_tool_tip(&item).await?;
+        let menu = SignalEmitter::new(&self.connection, MENU_PATH)?;
+        Menu::layout_updated(&menu, revision, 0).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! Updates the daemon applies on its own, following each source's policy
+
+use crate::notify::{package_list, Urgency};
+use fluxara_core::config::{UpdatePolicy, UpdatesConfig};
+use fluxara_core::{OperationOutcome, UpdateInfo};
+
+/// Updates sorted by what their source's policy says to do with them
+#[derive(Debug, Clone, Default)]
+pub struct UnattendedPlan {
+    pub install: Vec<UpdateInfo>,
+    /// Fetched when the system is idle, then installed by the user
+    pub download: Vec<UpdateInfo>,
+    pub notify: Vec<UpdateInfo>,
+}
+
+impl UnattendedPlan {
+    /// Sort `updates` by the policies in `config`; updates of sources
+    /// switched off are dropped, held packages are expected to be gone already
+    pub fn new(config: &UpdatesConfig, updates: Vec<UpdateInfo>) -> Self {
+        let mut plan = Self::default();
+        for update in updates {
+            match config.policy_for(&update) {
+                UpdatePolicy::Off => {}
+                UpdatePolicy::Notify => plan.notify.push(update),
+                UpdatePolicy::DownloadOnly => plan.download.push(update),
+                UpdatePolicy::Install => plan.install.push(update),
+            }
+        }
+        plan
+    }
+}
+
+/// What an unattended run applied
+#[derive(Debug, Clone, Default)]
+pub struct UnattendedReport {
+    /// Outcome of each update installed, or attempted
+    pub outcomes: Vec<OperationOutcome>,
+    /// Entry of the history the updates were recorded under
+    pub history_id: Option<u64>,
+}
+
+impl UnattendedReport {
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    pub fn installed(&self) -> impl Iterator<Item = &OperationOutcome> {
+        self.outcomes.iter().filter(|o| o.succeeded())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &OperationOutcome> {
+        self.outcomes.iter().filter(|o| !o.succeeded())
+    }
+
+    /// Summary, body and urgency of the notification reporting the run
+    pub fn summary(&self) -> (String, String, Urgency) {
+        let installed: Vec<&str> = self
+            .installed()
+            .map(|o| o.operation.package_id.as_str())
+            .collect();
+        let failed: Vec<&str> = self
+            .failed()
+            .map(|o| o.operation.package_id.as_str())
+            .collect();
+
+        let summary = match installed.len() {
+            1 => "1 update installed automatically".to_string(),
+            n => format!("{} updates installed automatically", n),
+        };
+        let mut body = package_list(&installed);
+        if failed.is_empty() {
+            return (summary, body, Urgency::Low);
+        }
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(&format!("Failed: {}", package_list(&failed)));
+        (summary, body, Urgency::Normal)
+    }
+}
@@ -0,0 +1,222 @@
+#[path = "../../../tests/support/bus.rs"]
+mod common;
+
+use async_trait::async_trait;
+use common::private_bus;
+use fluxara_core::{
+    Config, InstallPlan, Package, PackageDetails, PackageManager, PackageSource, ProviderRegistry,
+    UpdateInfo,
+};
+use fluxara_daemon::{SystemConditions, UpdateDaemon};
+
+struct MockNetworkManager {
+    metered: u32,
+}
+
+#[zbus::interface(name = "org.freedesktop.NetworkManager")]
+impl MockNetworkManager {
+    #[zbus(property)]
+    fn metered(&self) -> u32 {
+        self.metered
+    }
+}
+
+struct MockUPower {
+    on_battery: bool,
+}
+
+#[zbus::interface(name = "org.freedesktop.UPower")]
+impl MockUPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> bool {
+        self.on_battery
+    }
+}
+
+/// Why a daemon configured with `config` puts off a check while the
+/// stand-ins for NetworkManager and UPower report `metered` and
+/// `on_battery`; `None` when `dbus-daemon` is not installed
+async fn deferral(config: Config, metered: u32, on_battery: bool) -> Option<Option<&'static str>> {
+    let Some(bus) = private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return None;
+    };
+    let _services = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.freedesktop.NetworkManager")
+        .unwrap()
+        .name("org.freedesktop.UPower")
+        .unwrap()
+        .serve_at(
+            "/org/freedesktop/NetworkManager",
+            MockNetworkManager { metered },
+        )
+        .unwrap()
+        .serve_at("/org/freedesktop/UPower", MockUPower { on_battery })
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let connection = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    let (_dir, daemon) = daemon(
+        config,
+        vec![],
+        SystemConditions::with_connection(connection),
+    );
+    Some(daemon.deferral_reason().await)
+}
+
+/// Provider reporting fixed updates, or failing
+struct MockProvider {
+    source: PackageSource,
+    updates: Option<Vec<&'static str>>,
+}
+
+#[async_trait]
+impl PackageManager for MockProvider {
+    fn source(&self) -> PackageSource {
+        self.source
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn search(&self, _query: &str) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn install(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn remove(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn update(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_installed(&self) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn list_updates(&self) -> anyhow::Result<Vec<UpdateInfo>> {
+        let Some(updates) = &self.updates else {
+            anyhow::bail!("{} mirror unreachable", self.source);
+        };
+        Ok(updates
+            .iter()
+            .map(|id| UpdateInfo {
+                package_id: id.to_string(),
+                current_version: "1.0".to_string(),
+                new_version: "1.1".to_string(),
+                source: self.source,
+                size_bytes: None,
+                security: false,
+            })
+            .collect())
+    }
+
+    async fn get_install_plan(&self, _package_id: &str) -> anyhow::Result<InstallPlan> {
+        anyhow::bail!("not needed")
+    }
+
+    async fn details(&self, _package_id: &str) -> anyhow::Result<Option<PackageDetails>> {
+        Ok(None)
+    }
+}
+
+fn daemon(
+    config: Config,
+    providers: Vec<MockProvider>,
+    conditions: SystemConditions,
+) -> (tempfile::TempDir, UpdateDaemon) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut registry = ProviderRegistry::new();
+    for provider in providers {
+        registry.register(Box::new(provider));
+    }
+    let daemon =
+        UpdateDaemon::with_parts(config, registry, conditions, dir.path().join("daemon.json"));
+    (dir, daemon)
+}
+
+#[tokio::test]
+async fn checks_every_provider_and_skips_failing_ones() {
+    let mut config = Config::default();
+    config.updates.holds = vec!["vim".to_string()];
+    let (_dir, daemon) = daemon(
+        config,
+        vec![
+            MockProvider {
+                source: PackageSource::Flatpak,
+                updates: Some(vec!["org.gnome.Maps"]),
+            },
+            MockProvider {
+                source: PackageSource::Apt,
+                updates: Some(vec!["curl", "vim"]),
+            },
+            MockProvider {
+                source: PackageSource::Snap,
+                updates: None,
+            },
+        ],
+        SystemConditions::new(),
+    );
+
+    let updates = daemon.check_updates().await.unwrap();
+
+    let ids: Vec<&str> = updates.iter().map(|u| u.package_id.as_str()).collect();
+    assert_eq!(ids, ["org.gnome.Maps", "curl"]);
+}
+
+#[tokio::test]
+async fn check_fails_when_every_provider_fails() {
+    let (_dir, daemon) = daemon(
+        Config::default(),
+        vec![MockProvider {
+            source: PackageSource::Apt,
+            updates: None,
+        }],
+        SystemConditions::new(),
+    );
+
+    assert!(daemon.check_updates().await.is_err());
+}
+
+#[tokio::test]
+async fn metered_connection_defers_checks_unless_allowed() {
+    let Some(reason) = deferral(Config::default(), 1, false).await else {
+        return;
+    };
+    assert_eq!(reason, Some("the network connection is metered"));
+
+    let mut config = Config::default();
+    config.daemon.check_on_metered = true;
+    assert_eq!(deferral(config, 1, false).await, Some(None));
+
+    // NM_METERED_GUESS_NO
+    assert_eq!(deferral(Config::default(), 4, false).await, Some(None));
+}
+
+#[tokio::test]
+async fn battery_defers_checks_only_when_configured() {
+    let Some(reason) = deferral(Config::default(), 0, true).await else {
+        return;
+    };
+    assert_eq!(reason, None);
+
+    let mut config = Config::default();
+    config.daemon.check_on_battery = false;
+    assert_eq!(
+        deferral(config, 0, true).await,
+        Some(Some("running on battery"))
+    );
+}
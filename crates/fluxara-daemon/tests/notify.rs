@@ -0,0 +1,273 @@
+#[path = "../../../tests/support/bus.rs"]
+mod common;
+
+use common::{private_bus, Bus};
+use fluxara_core::{Config, PackageSource, ProviderRegistry, UpdateInfo};
+use fluxara_daemon::{DaemonState, NotificationAction, Notifier, SystemConditions, UpdateDaemon};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// A `Notify` call as the server received it
+#[derive(Debug, Clone)]
+struct Shown {
+    replaces_id: u32,
+    summary: String,
+    body: String,
+    actions: Vec<String>,
+    urgency: Option<u8>,
+    desktop_entry: Option<String>,
+}
+
+#[derive(Default)]
+struct Calls {
+    shown: Vec<Shown>,
+    closed: Vec<u32>,
+}
+
+/// Notification server recording what it is asked to show
+struct StubServer {
+    capabilities: Vec<String>,
+    calls: Arc<Mutex<Calls>>,
+    next_id: u32,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl StubServer {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &mut self,
+        _app_name: String,
+        replaces_id: u32,
+        _app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        _expire_timeout: i32,
+    ) -> u32 {
+        self.calls.lock().unwrap().shown.push(Shown {
+            replaces_id,
+            summary,
+            body,
+            actions,
+            urgency: hints.get("urgency").and_then(|v| u8::try_from(v).ok()),
+            desktop_entry: hints
+                .get("desktop-entry")
+                .and_then(|v| String::try_from(v.clone()).ok()),
+        });
+        if replaces_id != 0 {
+            return replaces_id;
+        }
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn close_notification(&self, id: u32) {
+        self.calls.lock().unwrap().closed.push(id);
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        self.capabilities.clone()
+    }
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+}
+
+struct Server {
+    _bus: Bus,
+    connection: zbus::Connection,
+    calls: Arc<Mutex<Calls>>,
+    notifier: Notifier,
+}
+
+impl Server {
+    fn shown(&self) -> Vec<Shown> {
+        self.calls.lock().unwrap().shown.clone()
+    }
+
+    async fn invoke(&self, id: u32, action_key: &str) {
+        let emitter = SignalEmitter::new(&self.connection, PATH).unwrap();
+        StubServer::action_invoked(&emitter, id, action_key)
+            .await
+            .unwrap();
+    }
+}
+
+/// A stub server with `capabilities` and a notifier talking to it; `None`
+/// when `dbus-daemon` is not installed
+async fn server(capabilities: &[&str]) -> Option<Server> {
+    let Some(bus) = private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return None;
+    };
+    let calls = Arc::new(Mutex::new(Calls::default()));
+    let connection = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.freedesktop.Notifications")
+        .unwrap()
+        .serve_at(
+            PATH,
+            StubServer {
+                capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+                calls: calls.clone(),
+                next_id: 0,
+            },
+        )
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let client = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    Some(Server {
+        _bus: bus,
+        connection,
+        calls,
+        notifier: Notifier::with_connection(client).await.unwrap(),
+    })
+}
+
+fn update(package_id: &str, security: bool) -> UpdateInfo {
+    UpdateInfo {
+        package_id: package_id.to_string(),
+        current_version: "1.0".to_string(),
+        new_version: "1.1".to_string(),
+        source: PackageSource::Apt,
+        size_bytes: None,
+        security,
+    }
+}
+
+#[tokio::test]
+async fn updates_replace_the_previous_notification() {
+    let Some(server) = server(&["actions", "body"]).await else {
+        return;
+    };
+
+    let first = server
+        .notifier
+        .notify_updates(&[update("curl", false), update("vim", false)])
+        .await
+        .unwrap();
+    let second = server
+        .notifier
+        .notify_updates(&[update("curl", false)])
+        .await
+        .unwrap();
+    assert_eq!(first, second);
+
+    let shown = server.shown();
+    assert_eq!(shown.len(), 2);
+    assert_eq!(shown[0].replaces_id, 0);
+    assert_eq!(shown[1].replaces_id, first);
+    assert_eq!(shown[0].summary, "2 updates available");
+    assert_eq!(shown[0].body, "curl, vim");
+    assert_eq!(shown[1].summary, "1 update available");
+    assert_eq!(
+        shown[0].actions,
+        ["update", "Update now", "view", "View", "snooze", "Snooze"]
+    );
+    assert_eq!(shown[0].urgency, Some(1));
+    assert_eq!(shown[0].desktop_entry.as_deref(), Some("com.fluxara.Store"));
+}
+
+#[tokio::test]
+async fn security_updates_are_critical() {
+    let Some(server) = server(&["actions"]).await else {
+        return;
+    };
+
+    let updates: Vec<UpdateInfo> = (0..7)
+        .map(|i| update(&format!("lib{}", i), i == 3))
+        .collect();
+    server.notifier.notify_updates(&updates).await.unwrap();
+
+    let shown = &server.shown()[0];
+    assert_eq!(shown.urgency, Some(2));
+    assert_eq!(
+        shown.body,
+        "1 fixing security issues: lib0, lib1, lib2, lib3, lib4, 2 more"
+    );
+}
+
+#[tokio::test]
+async fn servers_without_actions_get_no_buttons() {
+    let Some(server) = server(&["body"]).await else {
+        return;
+    };
+
+    server
+        .notifier
+        .notify_updates(&[update("curl", false)])
+        .await
+        .unwrap();
+    assert!(server.shown()[0].actions.is_empty());
+}
+
+#[tokio::test]
+async fn actions_on_the_current_notification_are_reported() {
+    let Some(server) = server(&["actions"]).await else {
+        return;
+    };
+
+    let mut actions = server.notifier.actions().await.unwrap();
+    let id = server
+        .notifier
+        .notify_updates(&[update("curl", false)])
+        .await
+        .unwrap();
+
+    // Another application's notification, then an unknown key
+    server.invoke(id + 100, "update").await;
+    server.invoke(id, "default").await;
+    server.invoke(id, "snooze").await;
+
+    let action = tokio::time::timeout(Duration::from_secs(5), actions.next())
+        .await
+        .unwrap();
+    assert_eq!(action, Some(NotificationAction::Snooze));
+}
+
+#[tokio::test]
+async fn snooze_holds_notifications_back_across_restarts() {
+    let Some(server) = server(&["actions"]).await else {
+        return;
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("daemon.json");
+    let mut daemon = UpdateDaemon::with_parts(
+        Config::default(),
+        ProviderRegistry::new(),
+        SystemConditions::new(),
+        state_path.clone(),
+    )
+    .with_notifier(server.notifier);
+
+    daemon.announce(vec![update("curl", false)]).await;
+    assert_eq!(server.calls.lock().unwrap().shown.len(), 1);
+
+    daemon.handle_action(NotificationAction::Snooze).await;
+    assert_eq!(server.calls.lock().unwrap().closed, [1]);
+    assert!(DaemonState::load(&state_path).snoozed_until.is_some());
+
+    daemon
+        .announce(vec![update("curl", false), update("vim", true)])
+        .await;
+    assert_eq!(server.calls.lock().unwrap().shown.len(), 1);
+    assert_eq!(daemon.pending().len(), 2);
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use fluxara_core::config::{DaemonConfig, QuietHours};
+use fluxara_daemon::{DaemonState, Schedule};
+
+fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).unwrap()
+}
+
+fn schedule(quiet_hours: Option<&str>) -> Schedule {
+    Schedule::from_config(&DaemonConfig {
+        check_interval_hours: 6,
+        jitter_minutes: 30,
+        quiet_hours: quiet_hours.map(|hours| hours.parse().unwrap()),
+        check_on_metered: false,
+        check_on_battery: true,
+    })
+}
+
+#[test]
+fn first_check_is_due_at_once() {
+    let now = at(12, 0);
+    assert_eq!(schedule(None).next_check(None, now, Duration::zero()), now);
+}
+
+#[test]
+fn next_check_waits_an_interval_after_the_last() {
+    let last = at(10, 0);
+    assert_eq!(
+        schedule(None).next_check(Some(last), at(12, 0), Duration::zero()),
+        at(16, 0)
+    );
+}
+
+#[test]
+fn overdue_check_runs_now_rather_than_in_the_past() {
+    let last = at(1, 0);
+    assert_eq!(
+        schedule(None).next_check(Some(last), at(12, 0), Duration::minutes(7)),
+        at(12, 7)
+    );
+}
+
+#[test]
+fn jitter_stays_within_the_configured_range() {
+    let schedule = schedule(None);
+    for _ in 0..100 {
+        let jitter = schedule.jitter();
+        assert!(jitter >= Duration::zero() && jitter <= Duration::minutes(30));
+    }
+}
+
+#[test]
+fn zero_interval_is_raised_to_an_hour() {
+    let schedule = Schedule::from_config(&DaemonConfig {
+        check_interval_hours: 0,
+        ..DaemonConfig::default()
+    });
+    assert_eq!(schedule.interval, Duration::hours(1));
+}
+
+#[test]
+fn checks_falling_in_quiet_hours_move_to_their_end() {
+    let schedule = schedule(Some("22:00-07:00"));
+
+    // Late evening: the quiet hours end the next morning
+    let last = at(17, 0);
+    assert_eq!(
+        schedule.next_check(Some(last), at(18, 0), Duration::zero()),
+        at(7, 0) + Duration::days(1)
+    );
+    // Early morning: they end the same day
+    assert_eq!(
+        schedule.next_check(None, at(3, 30), Duration::zero()),
+        at(7, 0)
+    );
+    // Outside quiet hours nothing moves
+    assert_eq!(
+        schedule.next_check(None, at(7, 0), Duration::zero()),
+        at(7, 0)
+    );
+}
+
+#[test]
+fn quiet_hours_within_a_day() {
+    let schedule = schedule(Some("09:00-17:30"));
+    assert_eq!(
+        schedule.next_check(None, at(12, 0), Duration::zero()),
+        at(17, 30)
+    );
+    assert_eq!(
+        schedule.next_check(None, at(8, 59), Duration::zero()),
+        at(8, 59)
+    );
+}
+
+#[test]
+fn quiet_hours_parse_and_print() {
+    let hours: QuietHours = "22:00-7:05".parse().unwrap();
+    assert_eq!(hours.to_string(), "22:00-07:05");
+    assert!("22:00".parse::<QuietHours>().is_err());
+    assert!("25:00-07:00".parse::<QuietHours>().is_err());
+
+    let config: DaemonConfig = toml::from_str("quiet_hours = \"23:30-06:00\"").unwrap();
+    assert_eq!(config.quiet_hours, Some("23:30-06:00".parse().unwrap()));
+    assert_eq!(config.check_interval_hours, 6);
+    assert!(toml::to_string(&config)
+        .unwrap()
+        .contains("quiet_hours = \"23:30-06:00\""));
+}
+
+#[test]
+fn state_survives_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fluxara").join("daemon.json");
+
+    assert_eq!(DaemonState::load(&path), DaemonState::default());
+    let state = DaemonState {
+        last_check: Some(at(9, 15)),
+        snoozed_until: None,
+        paused: false,
+    };
+    state.save(&path).unwrap();
+    assert_eq!(DaemonState::load(&path), state);
+
+    std::fs::write(&path, "{not json").unwrap();
+    assert_eq!(DaemonState::load(&path), DaemonState::default());
+}
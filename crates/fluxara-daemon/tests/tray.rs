@@ -0,0 +1,291 @@
+#[path = "../../../tests/support/bus.rs"]
+mod common;
+
+use common::{private_bus, Bus};
+use fluxara_core::{Config, ProviderRegistry};
+use fluxara_daemon::{DaemonState, SystemConditions, TrayAction, TrayIcon, UpdateDaemon, Wake};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zbus::zvariant::{OwnedValue, Value};
+
+type Layout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+/// Watcher recording the items registered with it
+struct StubWatcher {
+    items: Arc<Mutex<Vec<String>>>,
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierWatcher")]
+impl StubWatcher {
+    fn register_status_notifier_item(&self, service: String) {
+        self.items.lock().unwrap().push(service);
+    }
+}
+
+struct Session {
+    bus: Bus,
+    _watcher: Option<zbus::Connection>,
+    items: Arc<Mutex<Vec<String>>>,
+}
+
+impl Session {
+    async fn connect(&self) -> zbus::Connection {
+        zbus::connection::Builder::address(self.bus.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+    }
+
+    fn items(&self) -> Vec<String> {
+        self.items.lock().unwrap().clone()
+    }
+}
+
+/// A private session bus, with a tray watcher when `watcher` is set; `None`
+/// when `dbus-daemon` is not installed
+async fn session(watcher: bool) -> Option<Session> {
+    let Some(bus) = private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return None;
+    };
+    let items = Arc::new(Mutex::new(Vec::new()));
+    let watcher = if watcher {
+        let connection = zbus::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.kde.StatusNotifierWatcher")
+            .unwrap()
+            .serve_at(
+                "/StatusNotifierWatcher",
+                StubWatcher {
+                    items: items.clone(),
+                },
+            )
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        Some(connection)
+    } else {
+        None
+    };
+    Some(Session {
+        bus,
+        _watcher: watcher,
+        items,
+    })
+}
+
+async fn proxy<'a>(
+    connection: &zbus::Connection,
+    name: &'a str,
+    path: &'a str,
+    interface: &'a str,
+) -> zbus::Proxy<'a> {
+    zbus::proxy::Builder::new(connection)
+        .destination(name)
+        .unwrap()
+        .path(path)
+        .unwrap()
+        .interface(interface)
+        .unwrap()
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await
+        .unwrap()
+}
+
+/// Revision and labels of the top-level menu entries, "" for separators
+async fn menu(connection: &zbus::Connection, name: &str) -> (u32, Vec<String>) {
+    let menu = proxy(connection, name, "/MenuBar", "com.canonical.dbusmenu").await;
+    let (revision, (_, _, children)): (u32, Layout) = menu
+        .call("GetLayout", &(0i32, -1i32, Vec::<String>::new()))
+        .await
+        .unwrap();
+    let labels = children
+        .into_iter()
+        .map(|child| {
+            let (_, properties, _) = Layout::try_from(child).unwrap();
+            properties
+                .get("label")
+                .map(|label| String::try_from(label.try_clone().unwrap()).unwrap())
+                .unwrap_or_default()
+        })
+        .collect();
+    (revision, labels)
+}
+
+#[tokio::test]
+async fn tray_registers_and_shows_pending_updates() {
+    let Some(session) = session(true).await else {
+        return;
+    };
+    let (tray, _actions) = TrayIcon::with_connection(session.connect().await, 0, false)
+        .await
+        .unwrap();
+    assert_eq!(session.items(), [tray.name()]);
+
+    let client = session.connect().await;
+    let item = proxy(
+        &client,
+        tray.name(),
+        "/StatusNotifierItem",
+        "org.kde.StatusNotifierItem",
+    )
+    .await;
+    assert_eq!(
+        item.get_property::<String>("Status").await.unwrap(),
+        "Passive"
+    );
+    assert_eq!(
+        item.get_property::<String>("IconName").await.unwrap(),
+        "system-software-update"
+    );
+    let (first, labels) = menu(&client, tray.name()).await;
+    assert_eq!(
+        labels,
+        [
+            "Open Store",
+            "Update All",
+            "Check Now",
+            "Pause Updates",
+            "",
+            "Quit"
+        ]
+    );
+
+    tray.set_pending(3).await.unwrap();
+    assert_eq!(
+        item.get_property::<String>("Status").await.unwrap(),
+        "Active"
+    );
+    let (_, _, _, text): ToolTip = item.get_property("ToolTip").await.unwrap();
+    assert_eq!(text, "3 updates available");
+    let (revision, labels) = menu(&client, tray.name()).await;
+    assert!(revision > first);
+    assert_eq!(labels[1], "Update All (3)");
+}
+
+#[tokio::test]
+async fn menu_clicks_come_back_as_actions() {
+    let Some(session) = session(true).await else {
+        return;
+    };
+    let (tray, mut actions) = TrayIcon::with_connection(session.connect().await, 0, false)
+        .await
+        .unwrap();
+    let client = session.connect().await;
+    let menu = proxy(&client, tray.name(), "/MenuBar", "com.canonical.dbusmenu").await;
+    let item = proxy(
+        &client,
+        tray.name(),
+        "/StatusNotifierItem",
+        "org.kde.StatusNotifierItem",
+    )
+    .await;
+
+    let clicked = |id: i32| (id, "clicked", Value::from(0i32), 0u32);
+    let _: () = menu.call("Event", &clicked(4)).await.unwrap();
+    let _: () = item.call("Activate", &(0i32, 0i32)).await.unwrap();
+    assert!(menu.call::<_, _, ()>("Event", &clicked(99)).await.is_err());
+    // Hovering is not choosing
+    let _: () = menu
+        .call("Event", &(6i32, "hovered", Value::from(0i32), 0u32))
+        .await
+        .unwrap();
+    let _: () = menu.call("Event", &clicked(6)).await.unwrap();
+
+    let mut received = vec![];
+    for _ in 0..3 {
+        let action = tokio::time::timeout(Duration::from_secs(5), actions.recv()).await;
+        received.push(action.unwrap().unwrap());
+    }
+    assert_eq!(
+        received,
+        [
+            TrayAction::PauseUpdates,
+            TrayAction::OpenStore,
+            TrayAction::Quit
+        ]
+    );
+}
+
+#[tokio::test]
+async fn no_watcher_means_no_tray() {
+    let Some(session) = session(false).await else {
+        return;
+    };
+    assert!(TrayIcon::with_connection(session.connect().await, 0, false)
+        .await
+        .is_err());
+}
+
+fn daemon(connection: zbus::Connection) -> (tempfile::TempDir, UpdateDaemon) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.ui.tray_enabled = false;
+    let daemon = UpdateDaemon::with_parts(
+        config,
+        ProviderRegistry::new(),
+        SystemConditions::new(),
+        dir.path().join("daemon.json"),
+    )
+    .with_tray_connection(connection);
+    (dir, daemon)
+}
+
+#[tokio::test]
+async fn tray_follows_the_configuration_live() {
+    let Some(session) = session(true).await else {
+        return;
+    };
+    let (_dir, mut daemon) = daemon(session.connect().await);
+    assert_eq!(daemon.tray_name(), None);
+
+    let mut config = Config::default();
+    config.ui.tray_enabled = true;
+    daemon.apply_config(config.clone()).await;
+    let name = daemon.tray_name().unwrap().to_string();
+    assert_eq!(session.items(), [name.as_str()]);
+
+    config.ui.tray_enabled = false;
+    daemon.apply_config(config).await;
+    assert_eq!(daemon.tray_name(), None);
+    let dbus = zbus::fdo::DBusProxy::new(&session.connect().await)
+        .await
+        .unwrap();
+    assert!(!dbus
+        .name_has_owner(name.as_str().try_into().unwrap())
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn pausing_from_the_tray_is_persisted() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("daemon.json");
+    let mut daemon = UpdateDaemon::with_parts(
+        Config::default(),
+        ProviderRegistry::new(),
+        SystemConditions::new(),
+        state_path.clone(),
+    );
+
+    assert_eq!(
+        daemon.handle_tray_action(TrayAction::PauseUpdates).await,
+        None
+    );
+    assert!(daemon.state().paused);
+    assert!(DaemonState::load(&state_path).paused);
+
+    assert_eq!(
+        daemon.handle_tray_action(TrayAction::CheckNow).await,
+        Some(Wake::CheckNow)
+    );
+    assert_eq!(
+        daemon.handle_tray_action(TrayAction::Quit).await,
+        Some(Wake::Quit)
+    );
+}
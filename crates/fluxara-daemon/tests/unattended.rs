@@ -0,0 +1,365 @@
+#[path = "../../../tests/support/bus.rs"]
+mod common;
+
+use async_trait::async_trait;
+use common::private_bus;
+use fluxara_core::config::{UpdatePolicy, UpdatesConfig};
+use fluxara_core::{
+    Config, History, InstallPlan, InstallScope, Package, PackageDetails, PackageManager,
+    PackageSource, ProviderRegistry, UpdateInfo,
+};
+use fluxara_daemon::{SystemConditions, UnattendedPlan, UpdateDaemon};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+type Calls = Arc<Mutex<Vec<String>>>;
+
+fn update(package_id: &str, source: PackageSource, security: bool) -> UpdateInfo {
+    UpdateInfo {
+        package_id: package_id.to_string(),
+        current_version: "1.0".to_string(),
+        new_version: "1.1".to_string(),
+        source,
+        size_bytes: None,
+        security,
+    }
+}
+
+/// Provider offering fixed updates and recording what it is asked to do
+struct MockProvider {
+    source: PackageSource,
+    /// Package ids with updates, and whether each fixes security issues
+    updates: Vec<(&'static str, bool)>,
+    /// Updates fail to install
+    broken: bool,
+    calls: Calls,
+}
+
+impl MockProvider {
+    fn new(source: PackageSource, updates: &[(&'static str, bool)], calls: &Calls) -> Self {
+        Self {
+            source,
+            updates: updates.to_vec(),
+            broken: false,
+            calls: calls.clone(),
+        }
+    }
+
+    fn record(&self, what: &str, package_ids: &[String]) {
+        self.calls.lock().unwrap().push(format!(
+            "{} {} {}",
+            self.source,
+            what,
+            package_ids.join(" ")
+        ));
+    }
+}
+
+#[async_trait]
+impl PackageManager for MockProvider {
+    fn source(&self) -> PackageSource {
+        self.source
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn search(&self, _query: &str) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn install(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn remove(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn update(&self, package_id: &str) -> anyhow::Result<()> {
+        self.update_packages(&[package_id.to_string()]).await
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> anyhow::Result<()> {
+        if self.broken {
+            anyhow::bail!("{} database is locked", self.source);
+        }
+        self.record("update", package_ids);
+        Ok(())
+    }
+
+    async fn download_updates(&self, package_ids: &[String]) -> anyhow::Result<()> {
+        self.record("download", package_ids);
+        Ok(())
+    }
+
+    async fn list_installed(&self) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn list_updates(&self) -> anyhow::Result<Vec<UpdateInfo>> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} list", self.source));
+        Ok(self
+            .updates
+            .iter()
+            .map(|(id, security)| update(id, self.source, *security))
+            .collect())
+    }
+
+    async fn get_install_plan(&self, _package_id: &str) -> anyhow::Result<InstallPlan> {
+        anyhow::bail!("not needed")
+    }
+
+    async fn details(&self, _package_id: &str) -> anyhow::Result<Option<PackageDetails>> {
+        Ok(None)
+    }
+}
+
+fn registry(providers: Vec<MockProvider>) -> ProviderRegistry {
+    let mut registry = ProviderRegistry::new();
+    for provider in providers {
+        registry.register(Box::new(provider));
+    }
+    registry
+}
+
+/// Daemon checking with `checked` and applying unattended updates with `applied`
+fn daemon(
+    config: Config,
+    checked: Vec<MockProvider>,
+    applied: Vec<MockProvider>,
+    conditions: SystemConditions,
+) -> (tempfile::TempDir, UpdateDaemon) {
+    let dir = tempfile::tempdir().unwrap();
+    let daemon = UpdateDaemon::with_parts(
+        config,
+        registry(checked),
+        conditions,
+        dir.path().join("daemon.json"),
+    )
+    .with_unattended_registry(registry(applied));
+    (dir, daemon)
+}
+
+fn ids(updates: &[UpdateInfo]) -> Vec<&str> {
+    updates.iter().map(|u| u.package_id.as_str()).collect()
+}
+
+#[test]
+fn plan_follows_each_source_policy() {
+    let mut config = UpdatesConfig::default();
+    config.policy.flatpak = UpdatePolicy::Install;
+    config.policy.apt = UpdatePolicy::Install;
+    config.policy.pacman = UpdatePolicy::DownloadOnly;
+    config.policy.snap = UpdatePolicy::Off;
+    config.apt_security_only = true;
+
+    let plan = UnattendedPlan::new(
+        &config,
+        vec![
+            update("org.gnome.Maps", PackageSource::Flatpak, false),
+            update("openssl", PackageSource::Apt, true),
+            update("vim", PackageSource::Apt, false),
+            update("linux", PackageSource::Pacman, false),
+            update("firefox", PackageSource::Snap, false),
+            update("yay", PackageSource::Aur, false),
+        ],
+    );
+
+    assert_eq!(ids(&plan.install), ["org.gnome.Maps", "openssl"]);
+    assert_eq!(ids(&plan.download), ["vim", "linux"]);
+    assert_eq!(ids(&plan.notify), ["yay"]);
+}
+
+#[test]
+fn policies_parse_from_the_configuration() {
+    let config: UpdatesConfig = toml::from_str(
+        r#"
+        apt_security_only = true
+        [policy]
+        apt = "install"
+        pacman = "download-only"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.policy.apt, UpdatePolicy::Install);
+    assert_eq!(config.policy.pacman, UpdatePolicy::DownloadOnly);
+    assert_eq!(config.policy.flatpak, UpdatePolicy::Notify);
+    assert!(config.apt_security_only);
+    assert!(!Config::default().updates.policy.is_unattended());
+}
+
+#[tokio::test]
+async fn security_updates_are_installed_and_recorded() {
+    let calls = Calls::default();
+    let mut config = Config::default();
+    config.updates.policy.apt = UpdatePolicy::Install;
+    config.updates.apt_security_only = true;
+    config.updates.holds = vec!["sudo".to_string()];
+    let apt = [("openssl", true), ("sudo", true), ("vim", false)];
+    let (dir, mut daemon) = daemon(
+        config,
+        vec![MockProvider::new(PackageSource::Apt, &apt, &calls)],
+        vec![MockProvider::new(PackageSource::Apt, &apt, &calls)],
+        SystemConditions::new(),
+    );
+
+    let updates = daemon.check_updates().await.unwrap();
+    let (notify, report) = daemon.apply_policy(updates).await;
+
+    assert_eq!(*calls.lock().unwrap(), ["apt list", "apt update openssl"]);
+    assert_eq!(ids(&notify), ["vim"]);
+    assert_eq!(ids(daemon.downloads()), ["vim"]);
+    assert_eq!(report.installed().count(), 1);
+    assert_eq!(report.summary().0, "1 update installed automatically");
+
+    let entries = History::at(dir.path().join("history.jsonl"))
+        .entries()
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].unattended);
+    assert_eq!(Some(entries[0].id), report.history_id);
+    assert_eq!(entries[0].operations[0].operation.package_id, "openssl");
+}
+
+#[tokio::test]
+async fn failed_installs_are_reported_and_still_notified() {
+    let calls = Calls::default();
+    let mut config = Config::default();
+    config.updates.policy.pacman = UpdatePolicy::Install;
+    let mut pacman = MockProvider::new(PackageSource::Pacman, &[("linux", false)], &calls);
+    pacman.broken = true;
+    let (_dir, mut daemon) = daemon(config, vec![], vec![pacman], SystemConditions::new());
+
+    let (notify, report) = daemon
+        .apply_policy(vec![update("linux", PackageSource::Pacman, false)])
+        .await;
+
+    assert_eq!(ids(&notify), ["linux"]);
+    assert_eq!(report.failed().count(), 1);
+    let (summary, body, _) = report.summary();
+    assert_eq!(summary, "0 updates installed automatically");
+    assert_eq!(body, "Failed: linux");
+}
+
+#[tokio::test]
+async fn user_flatpak_apps_are_updated_while_system_ones_are_notified() {
+    let calls = Calls::default();
+    let mut config = Config::default();
+    config.install.flatpak_scope = InstallScope::System;
+    config.updates.policy.flatpak = UpdatePolicy::Install;
+    config.updates.holds = vec!["org.held.App".to_string()];
+    let (_dir, mut daemon) = daemon(
+        config,
+        vec![MockProvider::new(
+            PackageSource::Flatpak,
+            &[("org.system.App", false)],
+            &calls,
+        )],
+        vec![MockProvider::new(
+            PackageSource::Flatpak,
+            &[("org.user.App", false), ("org.held.App", false)],
+            &calls,
+        )],
+        SystemConditions::new(),
+    );
+
+    let updates = daemon.check_updates().await.unwrap();
+    let (notify, report) = daemon.apply_policy(updates).await;
+
+    assert_eq!(ids(&notify), ["org.system.App"]);
+    assert_eq!(report.installed().count(), 1);
+    assert_eq!(
+        calls.lock().unwrap().last().unwrap(),
+        "flatpak update org.user.App"
+    );
+}
+
+#[tokio::test]
+async fn sources_switched_off_are_not_checked() {
+    let calls = Calls::default();
+    let mut config = Config::default();
+    config.updates.policy.snap = UpdatePolicy::Off;
+    let (_dir, daemon) = daemon(
+        config,
+        vec![
+            MockProvider::new(PackageSource::Snap, &[("firefox", false)], &calls),
+            MockProvider::new(PackageSource::Apt, &[("curl", false)], &calls),
+        ],
+        vec![],
+        SystemConditions::new(),
+    );
+
+    let updates = daemon.check_updates().await.unwrap();
+
+    assert_eq!(ids(&updates), ["curl"]);
+    assert_eq!(*calls.lock().unwrap(), ["apt list"]);
+}
+
+struct MockLogin {
+    idle: Arc<AtomicBool>,
+}
+
+#[zbus::interface(name = "org.freedesktop.login1.Manager")]
+impl MockLogin {
+    #[zbus(property)]
+    fn idle_hint(&self) -> bool {
+        self.idle.load(Ordering::SeqCst)
+    }
+}
+
+#[tokio::test]
+async fn downloads_wait_for_the_system_to_be_idle() {
+    let Some(bus) = private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return;
+    };
+    let idle = Arc::new(AtomicBool::new(false));
+    let _services = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.freedesktop.login1")
+        .unwrap()
+        .serve_at("/org/freedesktop/login1", MockLogin { idle: idle.clone() })
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let connection = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    let calls = Calls::default();
+    let mut config = Config::default();
+    config.updates.policy.pacman = UpdatePolicy::DownloadOnly;
+    let (_dir, mut daemon) = daemon(
+        config,
+        vec![],
+        vec![MockProvider::new(PackageSource::Pacman, &[], &calls)],
+        SystemConditions::with_connection(connection),
+    );
+
+    let (notify, report) = daemon
+        .apply_policy(vec![
+            update("linux", PackageSource::Pacman, false),
+            update("mesa", PackageSource::Pacman, false),
+        ])
+        .await;
+    assert_eq!(ids(&notify), ["linux", "mesa"]);
+    assert!(report.is_empty());
+
+    assert!(daemon.download_when_idle().await.is_empty());
+    assert_eq!(ids(daemon.downloads()), ["linux", "mesa"]);
+
+    idle.store(true, Ordering::SeqCst);
+    let downloaded = daemon.download_when_idle().await;
+    assert_eq!(ids(&downloaded), ["linux", "mesa"]);
+    assert!(daemon.downloads().is_empty());
+    assert_eq!(*calls.lock().unwrap(), ["pacman download linux mesa"]);
+}
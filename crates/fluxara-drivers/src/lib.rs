@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use fluxara_core::{DriverInfo, DriverType};
+use serde::Serialize;
 use std::process::Command;
 
 pub struct DriverManager;
@@ -87,12 +88,31 @@ impl DriverManager {
                 device_ids: vec![],
                 driver_type: DriverType::Proprietary,
                 recommended: true,
+                packages: self.nvidia_packages(),
             });
         }
 
         Ok(drivers)
     }
 
+    /// Packages of the NVIDIA driver for this distribution's package manager
+    fn nvidia_packages(&self) -> Vec<String> {
+        let os_release = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
+        let ids: Vec<&str> = os_release
+            .lines()
+            .filter_map(|line| line.strip_prefix("ID=").or(line.strip_prefix("ID_LIKE=")))
+            .flat_map(|value| value.trim_matches('"').split_whitespace())
+            .collect();
+
+        if ids.iter().any(|id| matches!(*id, "arch" | "manjaro")) {
+            vec!["nvidia".to_string(), "nvidia-utils".to_string()]
+        } else if ids.iter().any(|id| matches!(*id, "debian" | "ubuntu")) {
+            vec!["nvidia-driver".to_string()]
+        } else {
+            vec![]
+        }
+    }
+
     fn has_nvidia_gpu(&self) -> Result<bool> {
         let output = Command::new("lspci")
             .output()
@@ -103,14 +123,15 @@ impl DriverManager {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HardwareDevice {
     pub id: String,
     pub name: String,
     pub device_type: DeviceType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DeviceType {
     Pci,
     Usb,
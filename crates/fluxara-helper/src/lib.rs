@@ -0,0 +1,732 @@
+//! Privileged helper, run as root on the system bus
+//!
+//! Only typed operations are offered. Each one validates its arguments,
+//! checks the caller against the PolicyKit action of the operation, and
+//! signals progress back to that caller alone; nothing here runs arbitrary
+//! commands. Installs are checked against the parental controls policy for
+//! the calling user. Every request, allowed or not, is written to the audit log.
+//! Clients use [`fluxara_polkit_agent::HelperClient`].
+
+mod validate;
+
+pub use validate::MAX_PACKAGES;
+
+use fluxara_appstream::AppStreamManager;
+use fluxara_core::config::{ParentalConfig, PARENTAL_POLICY_PATH};
+use fluxara_core::{InstallScope, PackageManager, PackageSource, ProviderRegistry};
+use fluxara_drivers::DriverManager;
+use fluxara_maintenance::MaintenanceManager;
+use fluxara_polkit_agent::{Action, AuditLog, AuditRecord, AuditStatus, PolkitAgent, Subject};
+use fluxara_provider_apt::AptProvider;
+use fluxara_provider_flatpak::FlatpakProvider;
+use fluxara_provider_pacman::PacmanProvider;
+use fluxara_provider_snap::SnapProvider;
+use std::path::PathBuf;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::info;
+use zbus::fdo;
+use zbus::message::Header;
+use zbus::names::BusName;
+use zbus::object_server::SignalEmitter;
+
+/// Providers of every source the helper changes packages of; Flatpak works
+/// on the system installation, user installations need no helper
+pub fn system_registry() -> ProviderRegistry {
+    let mut registry = ProviderRegistry::new();
+    registry.register(Box::new(FlatpakProvider::with_scope(InstallScope::System)));
+    registry.register(Box::new(AptProvider::new()));
+    registry.register(Box::new(PacmanProvider::new()));
+    registry.register(Box::new(SnapProvider::new()));
+    registry
+}
+
+/// The `com.fluxara.Store.Helper1` interface
+pub struct HelperService {
+    registry: ProviderRegistry,
+    polkit: PolkitAgent,
+    audit: AuditLog,
+    /// Read on each install, so that changes apply without a restart
+    parental_policy: PathBuf,
+    /// Content ratings the parental controls policy is checked against
+    appstream: AppStreamManager,
+    /// Held while an operation runs, as package managers lock their databases
+    busy: Mutex<()>,
+}
+
+impl HelperService {
+    pub fn new(registry: ProviderRegistry, polkit: PolkitAgent, audit: AuditLog) -> Self {
+        Self {
+            registry,
+            polkit,
+            audit,
+            parental_policy: PathBuf::from(PARENTAL_POLICY_PATH),
+            appstream: AppStreamManager::new(),
+            busy: Mutex::new(()),
+        }
+    }
+
+    /// Read the parental controls policy from `path` instead of the system's
+    pub fn with_parental_policy(mut self, path: PathBuf) -> Self {
+        self.parental_policy = path;
+        self
+    }
+
+    /// Look up content ratings with `appstream` instead of the system catalog
+    pub fn with_appstream(mut self, appstream: AppStreamManager) -> Self {
+        self.appstream = appstream;
+        self
+    }
+
+    fn provider(&self, source: PackageSource) -> fdo::Result<&dyn PackageManager> {
+        self.registry
+            .get(source)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("{} is not available", source)))
+    }
+
+    /// Authorize the caller for `action`, then wait for any running operation
+    async fn begin<'a>(
+        &'a self,
+        call: &Call<'_>,
+        action: Action,
+        operation: &'static str,
+    ) -> fdo::Result<Job<'a>> {
+        let caller = call
+            .header
+            .sender()
+            .ok_or_else(|| fdo::Error::AccessDenied("Caller has no bus name".to_string()))?
+            .to_owned();
+
+        self.polkit
+            .authorize(action, &Subject::bus_name(caller.as_str()))
+            .await
+            .map_err(|e| fdo::Error::AccessDenied(format!("{:#}", e)))?;
+
+        let guard = self.busy.lock().await;
+        info!("{} for {}", operation, caller);
+        Ok(Job {
+            emitter: call.emitter.to_owned().set_destination(caller.into()),
+            operation,
+            _guard: guard,
+        })
+    }
+
+    /// Write the outcome of a request to the audit log
+    async fn audit<T>(
+        &self,
+        call: &Call<'_>,
+        action: Action,
+        operation: &str,
+        arguments: Vec<String>,
+        result: &fdo::Result<T>,
+    ) {
+        let sender = call.header.sender();
+        let uid = call.uid().await;
+        let (status, message) = match result {
+            Ok(_) => (AuditStatus::Success, None),
+            Err(fdo::Error::AccessDenied(message)) => (AuditStatus::Denied, Some(message.clone())),
+            Err(fdo::Error::InvalidArgs(message)) => (AuditStatus::Invalid, Some(message.clone())),
+            Err(fdo::Error::Failed(message)) => (AuditStatus::Failed, Some(message.clone())),
+            Err(e) => (AuditStatus::Failed, Some(e.to_string())),
+        };
+
+        let record = AuditRecord {
+            uid,
+            caller: sender.map(|s| s.to_string()).unwrap_or_default(),
+            action: action.id().to_string(),
+            operation: operation.to_string(),
+            arguments,
+            status,
+            message,
+        };
+        if let Err(e) = self.audit.record(record) {
+            tracing::error!("Failed to write the audit log: {:#}", e);
+        }
+    }
+
+    /// Refuse installs the parental controls policy forbids the caller
+    async fn check_content(
+        &self,
+        call: &Call<'_>,
+        source: PackageSource,
+        packages: &[String],
+    ) -> fdo::Result<()> {
+        let policy = ParentalConfig::load_from(&self.parental_policy).map_err(failed)?;
+        if !policy.enabled {
+            return Ok(());
+        }
+        // A caller whose user cannot be told is restricted like any other
+        if !call.uid().await.is_none_or(|uid| policy.applies_to(uid)) {
+            return Ok(());
+        }
+
+        let blocked: Vec<String> = packages
+            .iter()
+            .filter_map(|id| {
+                let violations = self.appstream.content_violations(&policy, source, id);
+                (!violations.is_empty()).then(|| format!("{} ({})", id, violations.join("; ")))
+            })
+            .collect();
+        if blocked.is_empty() {
+            return Ok(());
+        }
+        Err(fdo::Error::AccessDenied(format!(
+            "Blocked by parental controls: {}",
+            blocked.join(", ")
+        )))
+    }
+
+    async fn install(&self, call: &Call<'_>, source: &str, packages: &[String]) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        validate::package_ids(source, packages, false)?;
+        let provider = self.provider(source)?;
+        self.check_content(call, source, packages).await?;
+
+        let job = self.begin(call, Action::Install, "InstallPackages").await?;
+        let total = packages.len() as u32;
+        job.progress(0, total, &format!("Installing {}", packages.join(", ")))
+            .await;
+        provider.install_packages(packages).await.map_err(failed)?;
+        job.progress(total, total, &format!("Installed {}", packages.join(", ")))
+            .await;
+        Ok(())
+    }
+
+    async fn remove(&self, call: &Call<'_>, source: &str, packages: &[String]) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        validate::package_ids(source, packages, false)?;
+        let provider = self.provider(source)?;
+
+        let job = self.begin(call, Action::Remove, "RemovePackages").await?;
+        let total = packages.len() as u32;
+        job.progress(0, total, &format!("Removing {}", packages.join(", ")))
+            .await;
+        provider.remove_packages(packages).await.map_err(failed)?;
+        job.progress(total, total, &format!("Removed {}", packages.join(", ")))
+            .await;
+        Ok(())
+    }
+
+    /// Update packages under `action`: [`Action::Update`] for a user, or
+    /// [`Action::Unattended`] for the update daemon
+    async fn update(
+        &self,
+        call: &Call<'_>,
+        action: Action,
+        operation: &'static str,
+        source: &str,
+        packages: &[String],
+    ) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        validate::package_ids(source, packages, true)?;
+        let provider = self.provider(source)?;
+
+        let job = self.begin(call, action, operation).await?;
+        let packages = if packages.is_empty() {
+            job.progress(0, 0, "Checking for updates").await;
+            provider
+                .list_updates()
+                .await
+                .map_err(failed)?
+                .into_iter()
+                .map(|update| update.package_id)
+                .collect()
+        } else {
+            packages.to_vec()
+        };
+        if packages.is_empty() {
+            job.progress(0, 0, "Everything is up to date").await;
+            return Ok(());
+        }
+
+        let total = packages.len() as u32;
+        job.progress(0, total, &format!("Updating {}", packages.join(", ")))
+            .await;
+        provider.update_packages(&packages).await.map_err(failed)?;
+        job.progress(total, total, &format!("Updated {}", packages.join(", ")))
+            .await;
+        Ok(())
+    }
+
+    async fn install_exact(
+        &self,
+        call: &Call<'_>,
+        source: &str,
+        package: &str,
+        version: &str,
+    ) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        validate::package_ids(source, &[package.to_string()], false)?;
+        validate::version(version)?;
+        let provider = self.provider(source)?;
+        self.check_content(call, source, &[package.to_string()])
+            .await?;
+
+        let job = self.begin(call, Action::Install, "InstallVersion").await?;
+        job.progress(0, 1, &format!("Installing {} {}", package, version))
+            .await;
+        provider
+            .install_version(package, version)
+            .await
+            .map_err(failed)?;
+        job.progress(1, 1, &format!("Installed {} {}", package, version))
+            .await;
+        Ok(())
+    }
+
+    /// Hold packages back from updates, or release them with `held` unset
+    async fn set_held(
+        &self,
+        call: &Call<'_>,
+        source: &str,
+        packages: &[String],
+        held: bool,
+    ) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        validate::package_ids(source, packages, false)?;
+        let provider = self.provider(source)?;
+
+        let (operation, verb) = if held {
+            ("HoldPackages", "Held")
+        } else {
+            ("UnholdPackages", "Released")
+        };
+        let job = self.begin(call, Action::Update, operation).await?;
+        let total = packages.len() as u32;
+        for (done, package) in packages.iter().enumerate() {
+            if held {
+                provider.hold(package).await
+            } else {
+                provider.unhold(package).await
+            }
+            .map_err(failed)?;
+            job.progress(done as u32 + 1, total, &format!("{} {}", verb, package))
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Fetch updates under `action`, as [`Self::update`]
+    async fn download(
+        &self,
+        call: &Call<'_>,
+        action: Action,
+        operation: &'static str,
+        source: &str,
+        packages: &[String],
+    ) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        validate::package_ids(source, packages, false)?;
+        let provider = self.provider(source)?;
+
+        let job = self.begin(call, action, operation).await?;
+        let total = packages.len() as u32;
+        job.progress(0, total, &format!("Downloading {}", packages.join(", ")))
+            .await;
+        provider.download_updates(packages).await.map_err(failed)?;
+        job.progress(total, total, &format!("Downloaded {}", packages.join(", ")))
+            .await;
+        Ok(())
+    }
+
+    async fn edit(
+        &self,
+        call: &Call<'_>,
+        source: &str,
+        operation: &str,
+        entry: &str,
+        url: &str,
+    ) -> fdo::Result<()> {
+        let source = validate::source(source)?;
+        let adding = match operation {
+            "add" => true,
+            "remove" => false,
+            other => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "Unknown repository operation: {:?}",
+                    other
+                )))
+            }
+        };
+        match source {
+            PackageSource::Apt => {
+                validate::apt_source_entry(entry)?;
+                if !url.is_empty() {
+                    return Err(fdo::Error::InvalidArgs(
+                        "APT sources carry their URL in the entry".to_string(),
+                    ));
+                }
+            }
+            PackageSource::Flatpak => {
+                validate::remote_name(entry)?;
+                if adding {
+                    validate::remote_url(url)?;
+                }
+            }
+            other => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "Repositories of {} cannot be edited",
+                    other
+                )))
+            }
+        }
+        self.provider(source)?;
+
+        let job = self.begin(call, Action::RepoEdit, "EditRepo").await?;
+        let verb = if adding { "Adding" } else { "Removing" };
+        job.progress(0, 1, &format!("{} {}", verb, entry)).await;
+        match (source, adding) {
+            (PackageSource::Apt, true) => AptProvider::new().add_repository(entry),
+            (PackageSource::Apt, false) => AptProvider::new().remove_repository(entry),
+            (_, true) => FlatpakProvider::with_scope(InstallScope::System).add_remote(entry, url),
+            (_, false) => FlatpakProvider::with_scope(InstallScope::System).remove_remote(entry),
+        }
+        .map_err(failed)?;
+        job.progress(1, 1, &format!("{} {} done", verb, entry))
+            .await;
+        Ok(())
+    }
+
+    async fn clean(&self, call: &Call<'_>) -> fdo::Result<u64> {
+        let job = self.begin(call, Action::Maintenance, "CleanCache").await?;
+        job.progress(0, 1, "Cleaning package caches").await;
+        let freed = MaintenanceManager::new().clean_cache().map_err(failed)?;
+        job.progress(1, 1, &format!("Freed {} bytes", freed)).await;
+        Ok(freed)
+    }
+
+    async fn install_driver_packages(&self, call: &Call<'_>, name: &str) -> fdo::Result<()> {
+        validate::driver_name(name)?;
+        // Only drivers detected for this hardware are offered
+        let driver = DriverManager::new()
+            .check_proprietary_drivers()
+            .map_err(failed)?
+            .into_iter()
+            .find(|driver| driver.name == name)
+            .ok_or_else(|| {
+                fdo::Error::InvalidArgs(format!("No driver {} for this system", name))
+            })?;
+        if driver.packages.is_empty() {
+            return Err(fdo::Error::Failed(format!(
+                "No packages of {} are known for this distribution",
+                name
+            )));
+        }
+        let provider = [PackageSource::Apt, PackageSource::Pacman]
+            .into_iter()
+            .find_map(|source| self.registry.get(source))
+            .ok_or_else(|| fdo::Error::Failed("No native package manager available".to_string()))?;
+
+        let job = self
+            .begin(call, Action::DriverInstall, "InstallDriver")
+            .await?;
+        let total = driver.packages.len() as u32;
+        job.progress(0, total, &format!("Installing {}", driver.description))
+            .await;
+        provider
+            .install_packages(&driver.packages)
+            .await
+            .map_err(failed)?;
+        job.progress(total, total, &format!("Installed {}", driver.description))
+            .await;
+        Ok(())
+    }
+}
+
+/// The message being handled and the means to signal its sender
+struct Call<'a> {
+    header: Header<'a>,
+    emitter: SignalEmitter<'a>,
+}
+
+impl Call<'_> {
+    /// User id of the caller, as the bus daemon knows it
+    async fn uid(&self) -> Option<u32> {
+        let sender = self.header.sender()?;
+        let dbus = fdo::DBusProxy::new(self.emitter.connection()).await.ok()?;
+        dbus.get_connection_unix_user(BusName::Unique(sender.to_owned()))
+            .await
+            .ok()
+    }
+}
+
+/// An authorized operation in progress
+struct Job<'a> {
+    /// Emitter of signals addressed to the caller only
+    emitter: SignalEmitter<'static>,
+    operation: &'static str,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl Job<'_> {
+    async fn progress(&self, current: u32, total: u32, message: &str) {
+        if let Err(e) =
+            HelperService::progress(&self.emitter, self.operation, current, total, message).await
+        {
+            tracing::warn!("Failed to send progress: {}", e);
+        }
+    }
+}
+
+fn failed(error: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(format!("{:#}", error))
+}
+
+#[zbus::interface(name = "com.fluxara.Store.Helper1")]
+impl HelperService {
+    async fn install_packages(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.install(&call, &source, &packages).await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(
+            &call,
+            Action::Install,
+            "InstallPackages",
+            arguments,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    async fn remove_packages(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.remove(&call, &source, &packages).await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(&call, Action::Remove, "RemovePackages", arguments, &result)
+            .await;
+        result
+    }
+
+    /// Update the given packages, or every package with an update when empty
+    async fn update_packages(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self
+            .update(&call, Action::Update, "UpdatePackages", &source, &packages)
+            .await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(&call, Action::Update, "UpdatePackages", arguments, &result)
+            .await;
+        result
+    }
+
+    /// Fetch the updates of the given packages without installing them
+    async fn download_packages(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self
+            .download(
+                &call,
+                Action::Update,
+                "DownloadPackages",
+                &source,
+                &packages,
+            )
+            .await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(
+            &call,
+            Action::Update,
+            "DownloadPackages",
+            arguments,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Update packages for the update daemon, authorized without asking
+    /// anyone; refused unless a rule grants `com.fluxara.Store.unattended`
+    async fn unattended_update(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self
+            .update(
+                &call,
+                Action::Unattended,
+                "UnattendedUpdate",
+                &source,
+                &packages,
+            )
+            .await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(
+            &call,
+            Action::Unattended,
+            "UnattendedUpdate",
+            arguments,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Fetch updates for the update daemon, authorized as
+    /// [`Self::unattended_update`]
+    async fn unattended_download(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self
+            .download(
+                &call,
+                Action::Unattended,
+                "UnattendedDownload",
+                &source,
+                &packages,
+            )
+            .await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(
+            &call,
+            Action::Unattended,
+            "UnattendedDownload",
+            arguments,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Install exactly `version` of a package, replacing the installed one
+    async fn install_version(
+        &self,
+        source: String,
+        package: String,
+        version: String,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.install_exact(&call, &source, &package, &version).await;
+        let arguments = vec![source, package, version];
+        self.audit(&call, Action::Install, "InstallVersion", arguments, &result)
+            .await;
+        result
+    }
+
+    /// Keep packages at their installed version, in the package manager itself
+    async fn hold_packages(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.set_held(&call, &source, &packages, true).await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(&call, Action::Update, "HoldPackages", arguments, &result)
+            .await;
+        result
+    }
+
+    /// Let held packages be updated again
+    async fn unhold_packages(
+        &self,
+        source: String,
+        packages: Vec<String>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.set_held(&call, &source, &packages, false).await;
+        let arguments = [vec![source], packages].concat();
+        self.audit(&call, Action::Update, "UnholdPackages", arguments, &result)
+            .await;
+        result
+    }
+
+    /// Add or remove a repository: for `apt`, `entry` is a one-line source
+    /// and `url` is empty; for `flatpak`, `entry` is the remote name and
+    /// `url` its location when adding
+    async fn edit_repo(
+        &self,
+        source: String,
+        operation: String,
+        entry: String,
+        url: String,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.edit(&call, &source, &operation, &entry, &url).await;
+        let arguments = vec![source, operation, entry, url];
+        self.audit(&call, Action::RepoEdit, "EditRepo", arguments, &result)
+            .await;
+        result
+    }
+
+    /// Clean the package caches; returns the number of bytes freed
+    async fn clean_cache(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<u64> {
+        let call = Call { header, emitter };
+        let result = self.clean(&call).await;
+        self.audit(&call, Action::Maintenance, "CleanCache", vec![], &result)
+            .await;
+        result
+    }
+
+    /// Install a driver detected for this machine, by name
+    async fn install_driver(
+        &self,
+        name: String,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let call = Call { header, emitter };
+        let result = self.install_driver_packages(&call, &name).await;
+        self.audit(
+            &call,
+            Action::DriverInstall,
+            "InstallDriver",
+            vec![name],
+            &result,
+        )
+        .await;
+        result
+    }
+
+    #[zbus(signal)]
+    async fn progress(
+        emitter: &SignalEmitter<'_>,
+        operation: &str,
+        current: u32,
+        total: u32,
+        message: &str,
+    ) -> zbus::Result<()>;
+}
@@ -0,0 +1,20 @@
+use fluxara_helper::{system_registry, HelperService};
+use fluxara_polkit_agent::{AuditLog, PolkitAgent, HELPER_BUS_NAME, HELPER_PATH};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let connection = zbus::Connection::system().await?;
+    let service = HelperService::new(
+        system_registry(),
+        PolkitAgent::with_connection(connection.clone()),
+        AuditLog::open(),
+    );
+    connection.object_server().at(HELPER_PATH, service).await?;
+    connection.request_name(HELPER_BUS_NAME).await?;
+    tracing::info!("Fluxara helper listening as {}", HELPER_BUS_NAME);
+
+    std::future::pending::<()>().await;
+    Ok(())
+}
@@ -0,0 +1,200 @@
+//! Checks of caller-supplied arguments, done before anything is authorized
+//! or run
+//!
+//! Everything that reaches a package manager's command line is checked here,
+//! so that no argument can be taken for an option or smuggle in another one.
+
+use fluxara_core::{InstallScope, PackageSource};
+use fluxara_polkit_agent::requires_elevation;
+use zbus::fdo;
+
+/// Most packages accepted in one call
+pub const MAX_PACKAGES: usize = 500;
+
+/// Longest package id, remote name or repository entry accepted
+const MAX_LEN: usize = 255;
+
+fn invalid(message: String) -> fdo::Error {
+    fdo::Error::InvalidArgs(message)
+}
+
+/// A source whose packages are changed as root
+pub fn source(name: &str) -> fdo::Result<PackageSource> {
+    let source: PackageSource = name
+        .parse()
+        .map_err(|e: anyhow::Error| invalid(e.to_string()))?;
+    if !requires_elevation(source, InstallScope::System) {
+        return Err(invalid(format!(
+            "{} packages are not changed as root",
+            source
+        )));
+    }
+    Ok(source)
+}
+
+/// Package ids for `source`; `allow_empty` for operations that then apply to
+/// every package
+pub fn package_ids(source: PackageSource, ids: &[String], allow_empty: bool) -> fdo::Result<()> {
+    if ids.is_empty() && !allow_empty {
+        return Err(invalid("No packages given".to_string()));
+    }
+    if ids.len() > MAX_PACKAGES {
+        return Err(invalid(format!(
+            "Too many packages: {}, at most {} are accepted",
+            ids.len(),
+            MAX_PACKAGES
+        )));
+    }
+    for id in ids {
+        if !is_package_id(source, id) {
+            return Err(invalid(format!(
+                "Invalid {} package name: {:?}",
+                source, id
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn is_package_id(source: PackageSource, id: &str) -> bool {
+    if id.is_empty() || id.len() > MAX_LEN || id.starts_with('-') {
+        return false;
+    }
+    match source {
+        // Debian policy: lowercase alphanumerics and `+-.`, at least two
+        // characters, starting alphanumeric; optionally `:architecture`
+        PackageSource::Apt => {
+            let (name, arch) = match id.split_once(':') {
+                Some((name, arch)) => (name, Some(arch)),
+                None => (id, None),
+            };
+            name.len() >= 2
+                && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c))
+                && arch.is_none_or(|arch| {
+                    !arch.is_empty()
+                        && arch
+                            .chars()
+                            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                })
+        }
+        // Arch package names: lowercase alphanumerics and `@._+-`, not
+        // starting with a hyphen or dot
+        PackageSource::Pacman | PackageSource::Aur => {
+            !id.starts_with('.')
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "@._+-".contains(c))
+        }
+        // Application ids or refs such as `app/org.gnome.Maps/x86_64/stable`
+        PackageSource::Flatpak => {
+            id.split('/')
+                .all(|part| !part.is_empty() && part != "." && part != "..")
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "._-/".contains(c))
+        }
+        // Snap names: lowercase alphanumerics and single inner hyphens
+        PackageSource::Snap => {
+            id.len() <= 40
+                && !id.ends_with('-')
+                && !id.contains("--")
+                && id.chars().any(|c| c.is_ascii_lowercase())
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        }
+    }
+}
+
+/// A package version such as `1:2.3.4-1ubuntu1`, `2.10.38-2` or `120.0`
+pub fn version(version: &str) -> fdo::Result<()> {
+    // Debian, pacman and snap versions: alphanumerics and `.+~:_-`, starting
+    // with an alphanumeric so that they can never be taken for an option
+    let valid = version.len() <= MAX_LEN
+        && version.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".+~:_-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid version: {:?}", version)))
+    }
+}
+
+/// A one-line APT source: `deb [options] <http(s) uri> <suite> [components]`
+pub fn apt_source_entry(entry: &str) -> fdo::Result<()> {
+    let rejected = || invalid(format!("Invalid APT source entry: {:?}", entry));
+    if entry.len() > MAX_LEN || !entry.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        return Err(rejected());
+    }
+
+    let mut words = entry.split(' ').filter(|word| !word.is_empty());
+    if !matches!(words.next(), Some("deb" | "deb-src")) {
+        return Err(rejected());
+    }
+    let mut rest: Vec<&str> = words.collect();
+    if rest.first().is_some_and(|word| word.starts_with('[')) {
+        let end = rest
+            .iter()
+            .position(|word| word.ends_with(']'))
+            .ok_or_else(rejected)?;
+        rest.drain(..=end);
+    }
+    match rest.as_slice() {
+        [uri, _suite, ..] if is_http_url(uri) => Ok(()),
+        _ => Err(rejected()),
+    }
+}
+
+/// Name of a Flatpak remote
+pub fn remote_name(name: &str) -> fdo::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with(['-', '.'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid remote name: {:?}", name)))
+    }
+}
+
+/// URL of a Flatpak remote or `.flatpakrepo` file
+pub fn remote_url(url: &str) -> fdo::Result<()> {
+    if url.len() <= MAX_LEN && is_http_url(url) {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid remote URL: {:?}", url)))
+    }
+}
+
+/// Name of a driver as listed by `fluxara driver list`
+pub fn driver_name(name: &str) -> fdo::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid driver name: {:?}", name)))
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    !rest.is_empty() && rest.chars().all(|c| c.is_ascii_graphic())
+}
@@ -0,0 +1,723 @@
+#[path = "../../../tests/support/bus.rs"]
+mod common;
+
+use async_trait::async_trait;
+use common::{private_bus, Bus};
+use fluxara_appstream::{AppStreamManager, Catalog};
+use fluxara_core::{
+    InstallPlan, Package, PackageDetails, PackageManager, PackageSource, ProviderRegistry,
+    UpdateInfo,
+};
+use fluxara_helper::{HelperService, MAX_PACKAGES};
+use fluxara_polkit_agent::{
+    AuditLog, AuditStatus, ElevatedProvider, HelperClient, HelperError, PolkitAgent, Progress,
+    RepoEdit, HELPER_BUS_NAME, HELPER_PATH,
+};
+use std::collections::HashMap;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::sync::{Arc, Mutex};
+use zbus::zvariant::OwnedValue;
+
+/// Stand-in for polkitd allowing only the given action ids, recording each
+/// check as `(subject kind, action id)`
+struct MockAuthority {
+    allowed: Vec<String>,
+    checks: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.PolicyKit1.Authority")]
+impl MockAuthority {
+    fn check_authorization(
+        &self,
+        subject: (String, HashMap<String, OwnedValue>),
+        action_id: String,
+        _details: HashMap<String, String>,
+        _flags: u32,
+        _cancellation_id: String,
+    ) -> (bool, bool, HashMap<String, String>) {
+        let authorized = self.allowed.contains(&action_id);
+        self.checks.lock().unwrap().push((subject.0, action_id));
+        (authorized, false, HashMap::new())
+    }
+}
+
+/// APT stand-in recording what it was asked to do
+struct MockProvider {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl PackageManager for MockProvider {
+    fn source(&self) -> PackageSource {
+        PackageSource::Apt
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn search(&self, _query: &str) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn install(&self, package_id: &str) -> anyhow::Result<()> {
+        self.install_packages(&[package_id.to_string()]).await
+    }
+
+    async fn remove(&self, package_id: &str) -> anyhow::Result<()> {
+        self.remove_packages(&[package_id.to_string()]).await
+    }
+
+    async fn update(&self, package_id: &str) -> anyhow::Result<()> {
+        self.update_packages(&[package_id.to_string()]).await
+    }
+
+    async fn install_packages(&self, package_ids: &[String]) -> anyhow::Result<()> {
+        if package_ids.iter().any(|id| id == "broken") {
+            anyhow::bail!("E: Unable to locate package broken");
+        }
+        self.record("install", package_ids);
+        Ok(())
+    }
+
+    async fn remove_packages(&self, package_ids: &[String]) -> anyhow::Result<()> {
+        self.record("remove", package_ids);
+        Ok(())
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> anyhow::Result<()> {
+        self.record("update", package_ids);
+        Ok(())
+    }
+
+    async fn download_updates(&self, package_ids: &[String]) -> anyhow::Result<()> {
+        self.record("download", package_ids);
+        Ok(())
+    }
+
+    async fn install_version(&self, package_id: &str, version: &str) -> anyhow::Result<()> {
+        self.record("install-version", &[format!("{}={}", package_id, version)]);
+        Ok(())
+    }
+
+    async fn hold(&self, package_id: &str) -> anyhow::Result<()> {
+        self.record("hold", &[package_id.to_string()]);
+        Ok(())
+    }
+
+    async fn unhold(&self, package_id: &str) -> anyhow::Result<()> {
+        self.record("unhold", &[package_id.to_string()]);
+        Ok(())
+    }
+
+    async fn list_installed(&self) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn list_updates(&self) -> anyhow::Result<Vec<UpdateInfo>> {
+        Ok(["curl", "vim"]
+            .into_iter()
+            .map(|id| UpdateInfo {
+                package_id: id.to_string(),
+                current_version: "1.0".to_string(),
+                new_version: "1.1".to_string(),
+                source: PackageSource::Apt,
+                size_bytes: None,
+                security: false,
+            })
+            .collect())
+    }
+
+    async fn get_install_plan(&self, _package_id: &str) -> anyhow::Result<InstallPlan> {
+        anyhow::bail!("not needed")
+    }
+
+    async fn details(&self, _package_id: &str) -> anyhow::Result<Option<PackageDetails>> {
+        Ok(None)
+    }
+}
+
+impl MockProvider {
+    fn record(&self, operation: &str, package_ids: &[String]) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", operation, package_ids.join(" ")));
+    }
+}
+
+/// Everything a test needs, kept alive together
+struct Fixture {
+    client: HelperClient,
+    checks: Arc<Mutex<Vec<(String, String)>>>,
+    calls: Arc<Mutex<Vec<String>>>,
+    audit: AuditLog,
+    _services: zbus::Connection,
+    _bus: Bus,
+    _dir: tempfile::TempDir,
+}
+
+/// Helper with an APT stand-in, behind an authority allowing `allowed`
+async fn fixture(allowed: &[&str]) -> Option<Fixture> {
+    fixture_with_policy(allowed, None).await
+}
+
+/// [`fixture`] enforcing the parental controls policy `parental`, with no
+/// app having a content rating
+async fn fixture_with_policy(allowed: &[&str], parental: Option<&str>) -> Option<Fixture> {
+    let Some(bus) = private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return None;
+    };
+    let checks = Arc::new(Mutex::new(Vec::new()));
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    let mut registry = ProviderRegistry::new();
+    registry.register(Box::new(MockProvider {
+        calls: calls.clone(),
+    }));
+    let authority = MockAuthority {
+        allowed: allowed.iter().map(|id| id.to_string()).collect(),
+        checks: checks.clone(),
+    };
+
+    // Authority and helper share one connection, as polkitd would be
+    // reached over the helper's own system bus connection
+    let services = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.freedesktop.PolicyKit1")
+        .unwrap()
+        .name(HELPER_BUS_NAME)
+        .unwrap()
+        .serve_at("/org/freedesktop/PolicyKit1/Authority", authority)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let audit_path = dir.path().join("audit.jsonl");
+    let policy_path = dir.path().join("parental.toml");
+    if let Some(parental) = parental {
+        std::fs::write(&policy_path, parental).unwrap();
+        std::fs::set_permissions(&policy_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    }
+    let helper = HelperService::new(
+        registry,
+        PolkitAgent::with_connection(services.clone()),
+        AuditLog::at(audit_path.clone()),
+    )
+    .with_parental_policy(policy_path)
+    .with_appstream(AppStreamManager::with_catalog(Catalog::new()));
+    services
+        .object_server()
+        .at(HELPER_PATH, helper)
+        .await
+        .unwrap();
+
+    let connection = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let client = HelperClient::with_connection(connection).await.unwrap();
+
+    Some(Fixture {
+        client,
+        checks,
+        calls,
+        audit: AuditLog::at(audit_path),
+        _services: services,
+        _bus: bus,
+        _dir: dir,
+    })
+}
+
+fn ids(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+#[tokio::test]
+async fn authorized_install_runs_and_reports_progress() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+    let mut progress = Vec::new();
+
+    fixture
+        .client
+        .install_packages(PackageSource::Apt, &ids(&["curl", "libc6:i386"]), |p| {
+            progress.push(p)
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(*fixture.calls.lock().unwrap(), ["install curl libc6:i386"]);
+    let checks = fixture.checks.lock().unwrap();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].0, "system-bus-name");
+    assert_eq!(checks[0].1, "com.fluxara.Store.install");
+    assert_eq!(
+        progress.last(),
+        Some(&Progress {
+            operation: "InstallPackages".to_string(),
+            current: 2,
+            total: 2,
+            message: "Installed curl, libc6:i386".to_string(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn each_method_checks_its_own_action() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+
+    let error = fixture
+        .client
+        .remove_packages(PackageSource::Apt, &ids(&["curl"]), |_| {})
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, HelperError::Denied(_)), "{:?}", error);
+    assert_eq!(error.to_string(), "Not authorized to remove packages");
+    assert!(fixture.calls.lock().unwrap().is_empty());
+    assert_eq!(
+        fixture.checks.lock().unwrap()[0].1,
+        "com.fluxara.Store.remove"
+    );
+}
+
+#[tokio::test]
+async fn invalid_arguments_are_rejected_before_authorization() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+    let rejected = [
+        ids(&["-oAPT::Get::Allow-Unauthenticated=true"]),
+        ids(&["curl vim"]),
+        ids(&["Curl"]),
+        ids(&["curl;reboot"]),
+        ids(&[]),
+        vec!["pkg".to_string(); MAX_PACKAGES + 1],
+    ];
+
+    for packages in rejected {
+        let error = fixture
+            .client
+            .install_packages(PackageSource::Apt, &packages, |_| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HelperError::Invalid(_)), "{:?}", error);
+    }
+    assert!(fixture.checks.lock().unwrap().is_empty());
+    assert!(fixture.calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn unavailable_source_is_rejected() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+
+    let error = fixture
+        .client
+        .install_packages(PackageSource::Snap, &ids(&["hello"]), |_| {})
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.to_string(), "snap is not available");
+    assert!(fixture.checks.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn provider_failure_is_reported() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+
+    let error = fixture
+        .client
+        .install_packages(PackageSource::Apt, &ids(&["broken"]), |_| {})
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, HelperError::Failed(_)), "{:?}", error);
+    assert!(error
+        .to_string()
+        .contains("Unable to locate package broken"));
+}
+
+#[tokio::test]
+async fn update_without_packages_updates_everything() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.update"]).await else {
+        return;
+    };
+
+    fixture
+        .client
+        .update_packages(PackageSource::Apt, &[], |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(*fixture.calls.lock().unwrap(), ["update curl vim"]);
+}
+
+#[tokio::test]
+async fn downloads_are_authorized_as_updates() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.update"]).await else {
+        return;
+    };
+
+    fixture
+        .client
+        .download_packages(PackageSource::Apt, &ids(&["curl"]), |_| {})
+        .await
+        .unwrap();
+    let error = fixture
+        .client
+        .download_packages(PackageSource::Apt, &[], |_| {})
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, HelperError::Invalid(_)), "{:?}", error);
+    assert_eq!(*fixture.calls.lock().unwrap(), ["download curl"]);
+}
+
+#[tokio::test]
+async fn unattended_updates_have_their_own_action() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.unattended"]).await else {
+        return;
+    };
+    let provider = ElevatedProvider::with_client(
+        Box::new(MockProvider {
+            calls: Arc::new(Mutex::new(Vec::new())),
+        }),
+        fixture.client.clone(),
+    )
+    .unattended();
+
+    provider.download_updates(&ids(&["curl"])).await.unwrap();
+    provider.update_packages(&[]).await.unwrap();
+    // Granting unattended updates grants nothing a user would be asked for
+    let error = fixture
+        .client
+        .update_packages(PackageSource::Apt, &[], |_| {})
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, HelperError::Denied(_)), "{:?}", error);
+    assert_eq!(
+        *fixture.calls.lock().unwrap(),
+        ["download curl", "update curl vim"]
+    );
+    let actions: Vec<String> = fixture
+        .checks
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, action)| action.clone())
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            "com.fluxara.Store.unattended",
+            "com.fluxara.Store.unattended",
+            "com.fluxara.Store.update"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn version_installs_are_validated_and_authorized_as_installs() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+
+    fixture
+        .client
+        .install_version(PackageSource::Apt, "vim", "2:9.0.1378-2", |_| {})
+        .await
+        .unwrap();
+    for version in ["", "--allow-unauthenticated", "9.0 curl", "9.0;reboot"] {
+        let error = fixture
+            .client
+            .install_version(PackageSource::Apt, "vim", version, |_| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HelperError::Invalid(_)), "{:?}", version);
+    }
+
+    assert_eq!(
+        *fixture.calls.lock().unwrap(),
+        ["install-version vim=2:9.0.1378-2"]
+    );
+    assert_eq!(fixture.checks.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn holds_are_authorized_as_updates() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.update"]).await else {
+        return;
+    };
+
+    fixture
+        .client
+        .hold_packages(
+            PackageSource::Apt,
+            &ids(&["linux-image-amd64", "vim"]),
+            |_| {},
+        )
+        .await
+        .unwrap();
+    fixture
+        .client
+        .unhold_packages(PackageSource::Apt, &ids(&["vim"]), |_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *fixture.calls.lock().unwrap(),
+        ["hold linux-image-amd64", "hold vim", "unhold vim"]
+    );
+    let checks = fixture.checks.lock().unwrap();
+    assert!(checks
+        .iter()
+        .all(|(_, action)| action == "com.fluxara.Store.update"));
+    let operations: Vec<String> = fixture
+        .audit
+        .entries()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.operation)
+        .collect();
+    assert_eq!(operations, ["HoldPackages", "UnholdPackages"]);
+}
+
+#[tokio::test]
+async fn malformed_repository_edits_are_rejected() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.repo-edit"]).await else {
+        return;
+    };
+    let rejected = [
+        RepoEdit::AddAptSource("deb file:///tmp/repo stable main".to_string()),
+        RepoEdit::AddAptSource("deb https://example.org/debian".to_string()),
+        RepoEdit::AddAptSource(
+            "deb https://example.org/debian stable main\ndeb http://evil.example stable main"
+                .to_string(),
+        ),
+        RepoEdit::AddFlatpakRemote {
+            name: "--system".to_string(),
+            url: "https://flathub.org/repo/flathub.flatpakrepo".to_string(),
+        },
+        RepoEdit::AddFlatpakRemote {
+            name: "flathub".to_string(),
+            url: "ftp://example.org/repo".to_string(),
+        },
+    ];
+
+    for edit in &rejected {
+        let error = fixture.client.edit_repo(edit, |_| {}).await.unwrap_err();
+        assert!(matches!(error, HelperError::Invalid(_)), "{:?}", edit);
+    }
+    assert!(fixture.checks.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn unknown_driver_is_rejected() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.driver-install"]).await else {
+        return;
+    };
+
+    let error = fixture
+        .client
+        .install_driver("../../bin/sh", |_| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(error, HelperError::Invalid(_)), "{:?}", error);
+    // Without the hardware, or without lspci to detect it, nothing is offered
+    assert!(fixture
+        .client
+        .install_driver("no-such-driver", |_| {})
+        .await
+        .is_err());
+    assert!(fixture.checks.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn clean_cache_needs_maintenance_authorization() {
+    let Some(fixture) = fixture(&[]).await else {
+        return;
+    };
+
+    let error = fixture.client.clean_cache(|_| {}).await.unwrap_err();
+
+    assert!(matches!(error, HelperError::Denied(_)), "{:?}", error);
+    assert_eq!(
+        fixture.checks.lock().unwrap()[0].1,
+        "com.fluxara.Store.maintenance"
+    );
+}
+
+#[tokio::test]
+async fn parental_controls_block_installs_for_restricted_callers() {
+    // Only a policy owned by root is trusted
+    if std::fs::metadata("/proc/self").unwrap().uid() != 0 {
+        eprintln!("not running as root, skipping");
+        return;
+    }
+    let policy = "enabled = true\nallow_unrated = false\n";
+    let Some(fixture) = fixture_with_policy(&["com.fluxara.Store.install"], Some(policy)).await
+    else {
+        return;
+    };
+
+    let error = fixture
+        .client
+        .install_packages(PackageSource::Apt, &ids(&["curl"]), |_| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        &error,
+        HelperError::Denied(message)
+            if message == "Blocked by parental controls: curl (no content rating)"
+    ));
+    let error = fixture
+        .client
+        .install_version(PackageSource::Apt, "curl", "8.5.0", |_| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(error, HelperError::Denied(_)));
+
+    // Refused before authorization, so no password is asked for
+    assert!(fixture.checks.lock().unwrap().is_empty());
+    assert!(fixture.calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn parental_controls_leave_other_users_alone() {
+    if std::fs::metadata("/proc/self").unwrap().uid() != 0 {
+        eprintln!("not running as root, skipping");
+        return;
+    }
+    let policy = "enabled = true\nallow_unrated = false\nrestricted_users = [\"no-such-user-for-fluxara-tests\"]\n";
+    let Some(fixture) = fixture_with_policy(&["com.fluxara.Store.install"], Some(policy)).await
+    else {
+        return;
+    };
+
+    fixture
+        .client
+        .install_packages(PackageSource::Apt, &ids(&["curl"]), |_| {})
+        .await
+        .unwrap();
+    assert_eq!(*fixture.calls.lock().unwrap(), ["install curl"]);
+}
+
+#[tokio::test]
+async fn requests_are_audited() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+    let client = &fixture.client;
+
+    client
+        .install_packages(PackageSource::Apt, &ids(&["curl"]), |_| {})
+        .await
+        .unwrap();
+    let _ = client
+        .remove_packages(PackageSource::Apt, &ids(&["curl"]), |_| {})
+        .await;
+    let _ = client
+        .install_packages(PackageSource::Apt, &ids(&["-y"]), |_| {})
+        .await;
+    let _ = client
+        .install_packages(PackageSource::Apt, &ids(&["broken"]), |_| {})
+        .await;
+
+    let entries = fixture.audit.entries().unwrap();
+    let statuses: Vec<AuditStatus> = entries.iter().map(|entry| entry.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            AuditStatus::Success,
+            AuditStatus::Denied,
+            AuditStatus::Invalid,
+            AuditStatus::Failed
+        ]
+    );
+
+    let installed = &entries[0];
+    let uid = std::fs::metadata(fixture.audit.path()).unwrap().uid();
+    assert_eq!(installed.uid, Some(uid));
+    assert!(installed.caller.starts_with(':'));
+    assert_eq!(installed.action, "com.fluxara.Store.install");
+    assert_eq!(installed.operation, "InstallPackages");
+    assert_eq!(installed.arguments, ["apt", "curl"]);
+    assert_eq!(installed.message, None);
+
+    assert_eq!(entries[1].action, "com.fluxara.Store.remove");
+    assert_eq!(
+        entries[1].message.as_deref(),
+        Some("Not authorized to remove packages")
+    );
+    assert!(fixture.audit.verify().unwrap().is_intact());
+}
+
+#[tokio::test]
+async fn aur_packages_are_not_built_as_root() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install"]).await else {
+        return;
+    };
+
+    let error = fixture
+        .client
+        .install_packages(PackageSource::Aur, &ids(&["yay"]), |_| {})
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, HelperError::Invalid(_)), "{:?}", error);
+    assert!(fixture.checks.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn elevated_provider_changes_packages_through_the_helper() {
+    let Some(fixture) = fixture(&["com.fluxara.Store.install", "com.fluxara.Store.update"]).await
+    else {
+        return;
+    };
+    // The local provider records into a list of its own, which must stay empty
+    let local = Arc::new(Mutex::new(Vec::new()));
+    let provider = ElevatedProvider::with_client(
+        Box::new(MockProvider {
+            calls: local.clone(),
+        }),
+        fixture.client.clone(),
+    );
+
+    provider.install("curl").await.unwrap();
+    provider.update_packages(&[]).await.unwrap();
+    provider.install_version("vim", "9.0").await.unwrap();
+    provider.hold("vim").await.unwrap();
+    provider.unhold("vim").await.unwrap();
+    let error = provider.remove("curl").await.unwrap_err();
+
+    assert_eq!(
+        *fixture.calls.lock().unwrap(),
+        [
+            "install curl",
+            "update curl vim",
+            "install-version vim=9.0",
+            "hold vim",
+            "unhold vim"
+        ]
+    );
+    assert!(local.lock().unwrap().is_empty());
+    assert!(matches!(
+        error.downcast_ref::<HelperError>(),
+        Some(HelperError::Denied(_))
+    ));
+    // Queries are still answered locally
+    assert_eq!(provider.source(), PackageSource::Apt);
+    assert_eq!(provider.list_updates().await.unwrap().len(), 2);
+}
@@ -0,0 +1,445 @@
+//! Local package index for fast, offline search
+//!
+//! Package metadata from every provider that can enumerate its catalog is
+//! stored in an SQLite FTS5 table under the user's cache directory. Searches
+//! rank exact and prefix name matches first, then full-text relevance across
+//! id, name, summary and keywords, and fall back to fuzzy name matching so
+//! that typos still find something; a trigram table of names narrows the
+//! fuzzy candidates. After each refresh, AppStream catalogs attach component
+//! ids, summaries and keywords to the packages shipping them.
+//! [`PackageIndex::record_outcomes`] keeps the installed state current between
+//! refreshes.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use fluxara_appstream::Catalog;
+use fluxara_core::{
+    Config, IdentityResolver, OperationKind, OperationOutcome, Package, PackageSource,
+    ProviderRegistry,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Minimum Jaro-Winkler similarity for a fuzzy name match
+const FUZZY_THRESHOLD: f64 = 0.88;
+
+/// Names sharing the most trigrams with the query that fuzzy matching compares
+const FUZZY_CANDIDATES: i64 = 200;
+
+const SCHEMA: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS packages USING fts5(
+        id, name, summary, keywords,
+        source UNINDEXED, version UNINDEXED, installed UNINDEXED, app_id UNINDEXED,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS names USING fts5(name, tokenize = 'trigram');
+    CREATE TABLE IF NOT EXISTS sources (
+        source TEXT PRIMARY KEY,
+        refreshed_at TEXT NOT NULL,
+        packages INTEGER NOT NULL
+    );
+";
+
+/// When a source was last ingested and how many packages it had
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+    pub source: PackageSource,
+    pub refreshed_at: DateTime<Utc>,
+    pub packages: usize,
+}
+
+pub struct PackageIndex {
+    conn: Connection,
+}
+
+impl PackageIndex {
+    /// The index in the user's cache directory
+    pub fn open() -> Result<Self> {
+        let dir = Config::cache_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        Self::open_at(&dir.join("index.db"))
+    }
+
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open package index {}", path.display()))?;
+        conn.execute_batch(SCHEMA)?;
+        // Indexes built before names were kept apart get them on first open
+        conn.execute(
+            "INSERT INTO names (rowid, name)
+             SELECT rowid, name FROM packages WHERE NOT EXISTS (SELECT 1 FROM names)",
+            [],
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Replace everything known about one source
+    pub fn replace_source(&mut self, source: PackageSource, packages: &[Package]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM names WHERE rowid IN (SELECT rowid FROM packages WHERE source = ?1)",
+            params![source.as_str()],
+        )?;
+        tx.execute(
+            "DELETE FROM packages WHERE source = ?1",
+            params![source.as_str()],
+        )?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO packages (id, name, summary, keywords, source, version, installed, app_id)
+                 VALUES (?1, ?2, ?3, '', ?4, ?5, ?6, NULL)",
+            )?;
+            let mut insert_name = tx.prepare("INSERT INTO names (rowid, name) VALUES (?1, ?2)")?;
+            for package in packages {
+                let rowid = insert.insert(params![
+                    package.id,
+                    package.name,
+                    package.description,
+                    source.as_str(),
+                    package.version,
+                    package.installed,
+                ])?;
+                insert_name.execute(params![rowid, package.name])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO sources (source, refreshed_at, packages) VALUES (?1, ?2, ?3)",
+            params![source.as_str(), Utc::now(), packages.len()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Attach AppStream component ids, summaries and keywords to the packages shipping them
+    ///
+    /// Flatpak components are matched against Flatpak packages, the others
+    /// against the distribution sources. Returns the number of packages updated.
+    pub fn ingest_appstream(&mut self, catalog: &Catalog) -> Result<usize> {
+        let mut rows: HashMap<String, Vec<(i64, bool)>> = HashMap::new();
+        {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT rowid, id, source FROM packages")?;
+            let mut query = stmt.query([])?;
+            while let Some(row) = query.next()? {
+                let source: String = row.get(2)?;
+                rows.entry(row.get(1)?)
+                    .or_default()
+                    .push((row.get(0)?, source == PackageSource::Flatpak.as_str()));
+            }
+        }
+
+        let tx = self.conn.transaction()?;
+        let mut updated = 0;
+        {
+            let mut update = tx.prepare(
+                "UPDATE packages SET app_id = ?1, keywords = ?2, summary = coalesce(?3, summary)
+                 WHERE rowid = ?4",
+            )?;
+            for component in catalog.components() {
+                let Some(pkgname) = &component.pkgname else {
+                    continue;
+                };
+                let flatpak = component.source == Some(PackageSource::Flatpak);
+                for &(rowid, _) in rows
+                    .get(pkgname)
+                    .into_iter()
+                    .flatten()
+                    .filter(|(_, is_flatpak)| *is_flatpak == flatpak)
+                {
+                    update.execute(params![
+                        component.app_id(),
+                        component.keywords.join(" "),
+                        component.summary,
+                        rowid
+                    ])?;
+                    updated += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Mark the packages of successful operations installed or removed, so that
+    /// searches need not wait for the next refresh to show it
+    ///
+    /// Returns the number of indexed packages updated.
+    pub fn record_outcomes(&mut self, outcomes: &[OperationOutcome]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut updated = 0;
+        {
+            let mut update =
+                tx.prepare("UPDATE packages SET installed = ?1 WHERE source = ?2 AND id = ?3")?;
+            for outcome in outcomes.iter().filter(|outcome| outcome.succeeded()) {
+                let operation = &outcome.operation;
+                updated += update.execute(params![
+                    operation.kind != OperationKind::Remove,
+                    operation.source.as_str(),
+                    operation.package_id,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Package-to-app mapping recorded by the last AppStream ingestion
+    pub fn identity_resolver(&self) -> Result<IdentityResolver> {
+        let mut resolver = IdentityResolver::new();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT app_id, source, id FROM packages WHERE app_id IS NOT NULL")?;
+        let mut query = stmt.query([])?;
+        while let Some(row) = query.next()? {
+            let app_id: String = row.get(0)?;
+            let source: String = row.get(1)?;
+            let id: String = row.get(2)?;
+            resolver.add_package(&app_id, source.parse().ok(), &id);
+        }
+        Ok(resolver)
+    }
+
+    /// Sources present in the index
+    pub fn sources(&self) -> Result<Vec<SourceStatus>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT source, refreshed_at, packages FROM sources ORDER BY source")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, DateTime<Utc>>(1)?,
+                row.get::<_, usize>(2)?,
+            ))
+        })?;
+
+        let mut sources = Vec::new();
+        for row in rows {
+            let (source, refreshed_at, packages) = row?;
+            if let Ok(source) = source.parse() {
+                sources.push(SourceStatus {
+                    source,
+                    refreshed_at,
+                    packages,
+                });
+            }
+        }
+        Ok(sources)
+    }
+
+    pub fn status(&self, source: PackageSource) -> Result<Option<SourceStatus>> {
+        Ok(self.sources()?.into_iter().find(|s| s.source == source))
+    }
+
+    /// Whether any indexed source is older than `max_age`, or nothing is indexed yet
+    pub fn is_stale(&self, max_age: Duration) -> Result<bool> {
+        let sources = self.sources()?;
+        Ok(sources.is_empty()
+            || sources
+                .iter()
+                .any(|s| Utc::now() - s.refreshed_at > max_age))
+    }
+
+    /// Re-ingest every available provider that can list its catalog
+    ///
+    /// Returns the sources that were refreshed with their package counts.
+    pub async fn refresh(
+        &mut self,
+        registry: &ProviderRegistry,
+        only: Option<PackageSource>,
+    ) -> Result<Vec<(PackageSource, usize)>> {
+        let mut refreshed = Vec::new();
+
+        for provider in registry.select(only) {
+            let source = provider.source();
+            match provider.list_available().await {
+                Ok(packages) if packages.is_empty() => {
+                    info!("{} cannot be indexed, it will be searched live", source);
+                }
+                Ok(packages) => {
+                    self.replace_source(source, &packages)?;
+                    info!("Indexed {} {} packages", packages.len(), source);
+                    refreshed.push((source, packages.len()));
+                }
+                Err(e) => warn!("Failed to index {}: {:#}", source, e),
+            }
+        }
+
+        if !refreshed.is_empty() {
+            let updated = self.ingest_appstream(&Catalog::load_system())?;
+            info!("Attached AppStream metadata to {} packages", updated);
+        }
+
+        Ok(refreshed)
+    }
+
+    /// Search the index, best matches first
+    pub fn search(
+        &self,
+        query: &str,
+        sources: &[PackageSource],
+        limit: usize,
+    ) -> Result<Vec<Package>> {
+        let needle = query.trim().to_lowercase();
+        let terms = fts_query(&needle);
+        if terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut hits: Vec<(f64, Package)> = Vec::new();
+
+        // bm25 weights follow the column order: id, name, summary, keywords, then unindexed columns
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, name, summary, source, version, installed,
+                    bm25(packages, 4.0, 10.0, 1.0, 3.0, 0.0, 0.0, 0.0, 0.0)
+             FROM packages WHERE packages MATCH ?1 AND instr(?3, ',' || source || ',') > 0
+             ORDER BY bm25(packages, 4.0, 10.0, 1.0, 3.0, 0.0, 0.0, 0.0, 0.0)
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(
+            params![terms, (limit * 4) as i64, source_list(sources)],
+            |row| Ok((read_package(row)?, row.get::<_, f64>(6)?)),
+        )?;
+        for row in rows {
+            let (package, bm25) = row?;
+            if let Some(package) = package {
+                // bm25 is negative, lower is better
+                let score = -bm25 + name_bonus(&needle, &package);
+                hits.push((score, package));
+            }
+        }
+
+        if hits.len() < limit {
+            self.fuzzy_matches(&needle, sources, &mut hits)?;
+        }
+
+        hits.sort_by(|a, b| b.0.total_cmp(&a.0));
+        hits.truncate(limit);
+        Ok(hits.into_iter().map(|(_, package)| package).collect())
+    }
+
+    /// Add packages whose name is close to the query, for typos FTS cannot match
+    fn fuzzy_matches(
+        &self,
+        needle: &str,
+        sources: &[PackageSource],
+        hits: &mut Vec<(f64, Package)>,
+    ) -> Result<()> {
+        let trigrams = trigram_query(needle);
+        if trigrams.is_empty() {
+            return Ok(());
+        }
+
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT p.id, p.name, p.summary, p.source, p.version, p.installed
+             FROM names JOIN packages p ON p.rowid = names.rowid
+             WHERE names MATCH ?1 AND instr(?2, ',' || p.source || ',') > 0
+             ORDER BY bm25(names)
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![trigrams, source_list(sources), FUZZY_CANDIDATES],
+            read_package,
+        )?;
+
+        for package in rows {
+            let Some(package) = package? else {
+                continue;
+            };
+            let similarity = strsim::jaro_winkler(needle, &package.name.to_lowercase());
+            if similarity < FUZZY_THRESHOLD {
+                continue;
+            }
+            if hits
+                .iter()
+                .any(|(_, p)| p.id == package.id && p.source == package.source)
+            {
+                continue;
+            }
+            hits.push((similarity * 5.0, package));
+        }
+
+        Ok(())
+    }
+
+    /// The indexed record of a package, if any
+    pub fn get(&self, source: PackageSource, package_id: &str) -> Result<Option<Package>> {
+        let package = self
+            .conn
+            .query_row(
+                "SELECT id, name, summary, source, version, installed FROM packages
+                 WHERE source = ?1 AND id = ?2",
+                params![source.as_str(), package_id],
+                read_package,
+            )
+            .optional()?;
+        Ok(package.flatten())
+    }
+}
+
+fn read_package(row: &rusqlite::Row) -> rusqlite::Result<Option<Package>> {
+    let source: String = row.get(3)?;
+    let Ok(source) = source.parse() else {
+        return Ok(None);
+    };
+    Ok(Some(Package {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        source,
+        version: row.get(4)?,
+        installed: row.get(5)?,
+        icon_url: None,
+    }))
+}
+
+/// Sources as `,apt,flatpak,` for matching with `instr` in SQL
+fn source_list(sources: &[PackageSource]) -> String {
+    let names: Vec<&str> = sources.iter().map(PackageSource::as_str).collect();
+    format!(",{},", names.join(","))
+}
+
+/// Turn free text into an FTS5 query: every word must match as a prefix
+///
+/// Words are quoted so that FTS5 operators in user input are taken literally.
+fn fts_query(needle: &str) -> String {
+    needle
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Turn a query into an FTS5 query matching names sharing any trigram with it
+///
+/// Empty for queries shorter than three characters, too short for a typo
+/// to be told from another name.
+fn trigram_query(needle: &str) -> String {
+    let chars: Vec<char> = needle.chars().collect();
+    let mut trigrams: Vec<String> = chars
+        .windows(3)
+        .map(|window| {
+            let trigram: String = window.iter().collect();
+            format!("\"{}\"", trigram.replace('"', "\"\""))
+        })
+        .collect();
+    trigrams.dedup();
+    trigrams.join(" OR ")
+}
+
+/// Extra score for names that match the query exactly or start with it
+fn name_bonus(needle: &str, package: &Package) -> f64 {
+    let name = package.name.to_lowercase();
+    let id = package.id.to_lowercase();
+    if name == needle || id == needle || id.rsplit('.').next() == Some(needle) {
+        100.0
+    } else if name.starts_with(needle) || id.starts_with(needle) {
+        20.0
+    } else {
+        0.0
+    }
+}
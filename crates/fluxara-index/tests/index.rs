@@ -0,0 +1,298 @@
+use async_trait::async_trait;
+use fluxara_core::{
+    InstallPlan, Operation, OperationKind, OperationOutcome, Package, PackageDetails,
+    PackageManager, PackageSource, ProviderRegistry, UpdateInfo,
+};
+use fluxara_index::PackageIndex;
+
+/// Provider whose catalog is a fixed list of packages
+struct MockProvider {
+    source: PackageSource,
+    available: Vec<Package>,
+}
+
+#[async_trait]
+impl PackageManager for MockProvider {
+    fn source(&self) -> PackageSource {
+        self.source
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn search(&self, _query: &str) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn install(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn remove(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn update(&self, _package_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_installed(&self) -> anyhow::Result<Vec<Package>> {
+        Ok(vec![])
+    }
+
+    async fn list_available(&self) -> anyhow::Result<Vec<Package>> {
+        Ok(self.available.clone())
+    }
+
+    async fn list_updates(&self) -> anyhow::Result<Vec<UpdateInfo>> {
+        Ok(vec![])
+    }
+
+    async fn get_install_plan(&self, _package_id: &str) -> anyhow::Result<InstallPlan> {
+        anyhow::bail!("not needed")
+    }
+
+    async fn details(&self, _package_id: &str) -> anyhow::Result<Option<PackageDetails>> {
+        Ok(None)
+    }
+}
+
+fn package(id: &str, name: &str, summary: &str, source: PackageSource) -> Package {
+    Package {
+        id: id.to_string(),
+        name: name.to_string(),
+        version: Some("1.0".to_string()),
+        description: Some(summary.to_string()),
+        icon_url: None,
+        source,
+        installed: false,
+    }
+}
+
+fn apt_packages() -> Vec<Package> {
+    vec![
+        package(
+            "firefox-esr",
+            "firefox-esr",
+            "Mozilla Firefox web browser",
+            PackageSource::Apt,
+        ),
+        package(
+            "thunderbird",
+            "thunderbird",
+            "Email, RSS and chat client",
+            PackageSource::Apt,
+        ),
+        package(
+            "gimp",
+            "gimp",
+            "GNU Image Manipulation Program",
+            PackageSource::Apt,
+        ),
+        package(
+            "inkscape",
+            "inkscape",
+            "Vector-based drawing program",
+            PackageSource::Apt,
+        ),
+        package(
+            "vlc",
+            "vlc",
+            "Multimedia player and streamer",
+            PackageSource::Apt,
+        ),
+    ]
+}
+
+fn index(dir: &tempfile::TempDir) -> PackageIndex {
+    let mut index = PackageIndex::open_at(&dir.path().join("index.db")).unwrap();
+    index
+        .replace_source(PackageSource::Apt, &apt_packages())
+        .unwrap();
+    index
+        .replace_source(
+            PackageSource::Flatpak,
+            &[package(
+                "org.mozilla.firefox",
+                "Firefox",
+                "Fast, private and safe web browser",
+                PackageSource::Flatpak,
+            )],
+        )
+        .unwrap();
+    index
+}
+
+fn ids(packages: &[Package]) -> Vec<&str> {
+    packages.iter().map(|p| p.id.as_str()).collect()
+}
+
+const ALL: &[PackageSource] = &[PackageSource::Apt, PackageSource::Flatpak];
+
+#[test]
+fn search_matches_words_as_prefixes_across_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = index(&dir);
+
+    let found = index.search("brows", ALL, 10).unwrap();
+    let mut found = ids(&found);
+    found.sort();
+    assert_eq!(found, ["firefox-esr", "org.mozilla.firefox"]);
+
+    let found = index.search("image manipulation", ALL, 10).unwrap();
+    assert_eq!(ids(&found), ["gimp"]);
+}
+
+#[test]
+fn search_ranks_name_matches_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = index(&dir);
+
+    let found = index.search("firefox", ALL, 10).unwrap();
+    assert_eq!(ids(&found)[0], "org.mozilla.firefox");
+    assert!(ids(&found).contains(&"firefox-esr"));
+}
+
+#[test]
+fn search_is_limited_to_the_given_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = index(&dir);
+
+    let found = index
+        .search("firefox", &[PackageSource::Flatpak], 10)
+        .unwrap();
+    assert_eq!(ids(&found), ["org.mozilla.firefox"]);
+}
+
+#[test]
+fn fts_operators_in_queries_are_taken_literally() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = index(&dir);
+
+    assert!(index.search("vlc OR", ALL, 10).is_ok());
+    assert!(index.search("\"gimp", ALL, 10).is_ok());
+    assert!(index.search("  ", ALL, 10).unwrap().is_empty());
+}
+
+#[test]
+fn typos_are_found_by_fuzzy_matching() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = index(&dir);
+
+    let found = index.search("thunderbrid", ALL, 10).unwrap();
+    assert_eq!(ids(&found), ["thunderbird"]);
+
+    let found = index.search("inksacpe", ALL, 10).unwrap();
+    assert_eq!(ids(&found), ["inkscape"]);
+
+    assert!(index.search("zzzzzz", ALL, 10).unwrap().is_empty());
+}
+
+#[test]
+fn replacing_a_source_drops_its_old_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut index = index(&dir);
+
+    index
+        .replace_source(
+            PackageSource::Apt,
+            &[package(
+                "vlc",
+                "vlc",
+                "Multimedia player",
+                PackageSource::Apt,
+            )],
+        )
+        .unwrap();
+
+    assert!(index.search("gimp", ALL, 10).unwrap().is_empty());
+    // Nor are the dropped names left for fuzzy matching
+    assert!(index.search("thunderbrid", ALL, 10).unwrap().is_empty());
+    assert_eq!(
+        index.status(PackageSource::Apt).unwrap().unwrap().packages,
+        1
+    );
+    assert!(index
+        .get(PackageSource::Flatpak, "org.mozilla.firefox")
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn outcomes_update_the_installed_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut index = index(&dir);
+    let outcome = |kind, package_id: &str, error: Option<&str>| OperationOutcome {
+        operation: Operation {
+            kind,
+            package_id: package_id.to_string(),
+            source: PackageSource::Apt,
+            version: None,
+        },
+        old_version: None,
+        new_version: None,
+        error: error.map(str::to_string),
+    };
+
+    let updated = index
+        .record_outcomes(&[
+            outcome(OperationKind::Install, "gimp", None),
+            outcome(OperationKind::Install, "vlc", Some("E: broken")),
+        ])
+        .unwrap();
+    assert_eq!(updated, 1);
+    let installed = |index: &PackageIndex, id| {
+        index
+            .get(PackageSource::Apt, id)
+            .unwrap()
+            .unwrap()
+            .installed
+    };
+    assert!(installed(&index, "gimp"));
+    assert!(!installed(&index, "vlc"));
+
+    index
+        .record_outcomes(&[outcome(OperationKind::Remove, "gimp", None)])
+        .unwrap();
+    assert!(!installed(&index, "gimp"));
+}
+
+#[tokio::test]
+async fn refresh_indexes_sources_that_list_their_catalog() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut index = PackageIndex::open_at(&dir.path().join("index.db")).unwrap();
+    let mut registry = ProviderRegistry::new();
+    registry.register(Box::new(MockProvider {
+        source: PackageSource::Apt,
+        available: apt_packages(),
+    }));
+    // Sources that cannot enumerate their catalog are searched live instead
+    registry.register(Box::new(MockProvider {
+        source: PackageSource::Snap,
+        available: vec![],
+    }));
+
+    let refreshed = index.refresh(&registry, None).await.unwrap();
+
+    assert_eq!(refreshed, [(PackageSource::Apt, 5)]);
+    assert!(index.status(PackageSource::Snap).unwrap().is_none());
+    assert!(!index.is_stale(chrono::Duration::hours(1)).unwrap());
+    assert_eq!(ids(&index.search("vlc", ALL, 10).unwrap()), ["vlc"]);
+}
+
+#[test]
+fn index_without_names_is_backfilled_on_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.db");
+    drop(index(&dir));
+    {
+        // As left by a version that did not keep names apart
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute("DELETE FROM names", []).unwrap();
+    }
+
+    let index = PackageIndex::open_at(&path).unwrap();
+    let found = index.search("thunderbrid", ALL, 10).unwrap();
+    assert_eq!(ids(&found), ["thunderbird"]);
+}
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use fluxara_core::RepoInfo;
+use serde::Serialize;
 use std::process::Command;
-use std::time::Instant;
 
 pub struct MaintenanceManager;
 
@@ -45,17 +45,12 @@ impl MaintenanceManager {
         // Stub implementation - would download a small test file and measure speed
         println!("Testing mirror speed: {}", mirror_url);
 
-        // Simulate speed test
-        let start = Instant::now();
-
         // In real implementation, would use wget or curl with timing
         let output = Command::new("curl")
             .args(["-I", "-s", "-w", "%{speed_download}", mirror_url])
             .output()
             .context("Failed to test mirror speed")?;
 
-        let elapsed = start.elapsed();
-
         // Parse speed from curl output (bytes per second)
         let speed_bps = String::from_utf8_lossy(&output.stdout)
             .trim()
@@ -132,7 +127,7 @@ impl MaintenanceManager {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PackageConflict {
     pub package1: String,
     pub package2: String,
@@ -0,0 +1,337 @@
+//! Local cache of app icons and screenshots
+//!
+//! Icons are resolved from what AppStream catalogs describe: local icons and
+//! the catalogs' cached icon directories are used in place, remote icons and
+//! screenshots are downloaded into the user's cache directory. Downloads are
+//! kept until the cache outgrows its size cap, then the least recently used
+//! ones are evicted. Theme (stock) icons are left to the toolkit.
+
+use anyhow::{bail, Context, Result};
+use fluxara_appstream::{Catalog, Component, Icon, IconKind, Screenshot};
+use fluxara_core::{Config, Package};
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Largest single file downloaded
+const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
+const USER_AGENT: &str = concat!("fluxara/", env!("CARGO_PKG_VERSION"));
+
+/// Which size of a screenshot to fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotSize {
+    /// The smallest thumbnail, for gallery strips
+    Thumbnail,
+    /// The smallest image at least this many pixels wide
+    Width(u32),
+    /// The full-size image
+    Source,
+}
+
+/// Icon and screenshot cache under `~/.cache/fluxara/media`
+pub struct MediaCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    agent: ureq::Agent,
+}
+
+impl MediaCache {
+    /// Cache in the user's cache directory with the default size cap
+    pub fn new() -> Result<Self> {
+        Self::from_config(&Config::default())
+    }
+
+    /// Cache in the user's cache directory capped by `[media] max_cache_mb`
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::with_dir(
+            Config::cache_dir()?.join("media"),
+            config.media.max_cache_mb * 1024 * 1024,
+        ))
+    }
+
+    /// Cache in `dir`, evicting downloads beyond `max_bytes`
+    pub fn with_dir(dir: PathBuf, max_bytes: u64) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(30)))
+            .user_agent(USER_AGENT)
+            .build()
+            .into();
+
+        Self {
+            dir,
+            max_bytes,
+            agent,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Icon file of `component` closest to `size` pixels, without downloading
+    ///
+    /// Remote icons count only when an earlier download is still cached.
+    pub fn local_icon(&self, component: &Component, size: u32) -> Option<PathBuf> {
+        ranked_icons(component, size)
+            .into_iter()
+            .find_map(|icon| match icon.kind {
+                IconKind::Remote => self.cached(&icon.value),
+                _ => local_icon_file(component, icon),
+            })
+    }
+
+    /// Icon file of `component` closest to `size` pixels, downloading a
+    /// remote icon when no local one fits
+    pub fn icon(&self, component: &Component, size: u32) -> Result<Option<PathBuf>> {
+        for icon in ranked_icons(component, size) {
+            match icon.kind {
+                IconKind::Remote => return self.fetch(&icon.value).map(Some),
+                _ => {
+                    if let Some(path) = local_icon_file(component, icon) {
+                        return Ok(Some(path));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Local copy of a screenshot in the requested size
+    pub fn screenshot(&self, screenshot: &Screenshot, size: ScreenshotSize) -> Result<PathBuf> {
+        let url = match size {
+            ScreenshotSize::Source => screenshot.url.as_str(),
+            ScreenshotSize::Thumbnail => screenshot
+                .thumbnail_url
+                .as_deref()
+                .unwrap_or(&screenshot.url),
+            ScreenshotSize::Width(width) => screenshot
+                .images
+                .iter()
+                .filter(|image| image.width.is_some_and(|w| w >= width))
+                .min_by_key(|image| image.width)
+                .map_or(screenshot.url.as_str(), |image| image.url.as_str()),
+        };
+        self.fetch(url)
+    }
+
+    /// Set `icon_url` of each package to a local icon file from the catalog
+    ///
+    /// Only icons available without downloading are used; packages the
+    /// catalog has no such icon for keep theirs.
+    pub fn fill_icon_urls(&self, catalog: &Catalog, packages: &mut [Package], size: u32) {
+        for package in packages {
+            let component = catalog
+                .find_by_package(package.source, &package.id)
+                .or_else(|| catalog.get(&package.id));
+            if let Some(path) = component.and_then(|c| self.local_icon(c, size)) {
+                package.icon_url = Some(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    /// Local copy of `url`, downloaded on first use
+    pub fn fetch(&self, url: &str) -> Result<PathBuf> {
+        if let Some(path) = self.cached(url) {
+            return Ok(path);
+        }
+
+        let path = self.path_for(url);
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        let mut response = self
+            .agent
+            .get(url)
+            .call()
+            .with_context(|| format!("Failed to download {}", url))?;
+        let data = response
+            .body_mut()
+            .with_config()
+            .limit(MAX_DOWNLOAD_BYTES)
+            .read_to_vec()
+            .with_context(|| format!("Failed to download {}", url))?;
+        if data.is_empty() {
+            bail!("{} is empty", url);
+        }
+
+        // Written aside first so a partial download is never served
+        let partial = path.with_extension("part");
+        fs::write(&partial, &data)?;
+        fs::rename(&partial, &path)?;
+
+        if let Err(e) = self.evict_except(Some(&path)) {
+            tracing::warn!("Failed to evict media cache: {:#}", e);
+        }
+        Ok(path)
+    }
+
+    /// Total size of the cached downloads in bytes
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|entry| entry.size).sum())
+    }
+
+    /// Remove the least recently used downloads until the cache fits its cap;
+    /// returns the number of bytes freed
+    pub fn evict(&self) -> Result<u64> {
+        self.evict_except(None)
+    }
+
+    /// Remove every download; returns the number of bytes freed
+    pub fn clear(&self) -> Result<u64> {
+        let mut freed = 0;
+        for entry in self.entries()? {
+            fs::remove_file(&entry.path)?;
+            freed += entry.size;
+        }
+        Ok(freed)
+    }
+
+    fn evict_except(&self, keep: Option<&Path>) -> Result<u64> {
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        if total <= self.max_bytes {
+            return Ok(0);
+        }
+
+        entries.sort_by_key(|entry| entry.used);
+        let mut freed = 0;
+        for entry in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if keep == Some(entry.path.as_path()) {
+                continue;
+            }
+            fs::remove_file(&entry.path)?;
+            tracing::debug!("Evicted {}", entry.path.display());
+            total -= entry.size;
+            freed += entry.size;
+        }
+        Ok(freed)
+    }
+
+    /// Cached download of `url`, marked as just used
+    fn cached(&self, url: &str) -> Option<PathBuf> {
+        let path = self.path_for(url);
+        if !path.is_file() {
+            return None;
+        }
+        // The modification time records the last use for eviction
+        if let Err(e) = File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            tracing::debug!("Failed to touch {}: {}", path.display(), e);
+        }
+        Some(path)
+    }
+
+    /// Download location of `url`: a hash of it, keeping the file extension
+    fn path_for(&self, url: &str) -> PathBuf {
+        let digest = Sha1::digest(url.as_bytes());
+        let mut name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        let file_name = url
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        if let Some((_, extension)) = file_name.rsplit_once('.') {
+            if !extension.is_empty()
+                && extension.len() <= 5
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                name.push('.');
+                name.push_str(&extension.to_lowercase());
+            }
+        }
+        self.dir.join(name)
+    }
+
+    fn entries(&self) -> Result<Vec<Entry>> {
+        let read_dir = match fs::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).context(format!("Failed to read {}", self.dir.display())),
+        };
+
+        let mut entries = Vec::new();
+        for entry in read_dir {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let path = entry.path();
+            if !metadata.is_file() || path.extension().is_some_and(|ext| ext == "part") {
+                continue;
+            }
+            entries.push(Entry {
+                path,
+                size: metadata.len(),
+                used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// A downloaded file
+struct Entry {
+    path: PathBuf,
+    size: u64,
+    used: SystemTime,
+}
+
+/// Non-theme icons of a component, best fit for `size` first
+///
+/// The smallest icon at least `size` wide fits best, then the largest smaller
+/// one; icons without a size come last. On a tie, icons on disk are
+/// preferred over remote ones.
+fn ranked_icons(component: &Component, size: u32) -> Vec<&Icon> {
+    let mut icons: Vec<&Icon> = component
+        .icons
+        .iter()
+        .filter(|icon| icon.kind != IconKind::Stock)
+        .collect();
+    icons.sort_by_key(|icon| {
+        let fit = match icon.width {
+            Some(width) if width >= size => (0, width),
+            Some(width) => (1, u32::MAX - width),
+            None => (2, 0),
+        };
+        (fit, icon.kind == IconKind::Remote)
+    });
+    icons
+}
+
+/// File of a local or cached icon, if it exists
+fn local_icon_file(component: &Component, icon: &Icon) -> Option<PathBuf> {
+    match icon.kind {
+        IconKind::Local => {
+            let path = PathBuf::from(&icon.value);
+            path.is_file().then_some(path)
+        }
+        IconKind::Cached => {
+            let cache = component.icon_cache.as_ref()?;
+            match (icon.width, icon.height) {
+                (Some(width), Some(height)) => {
+                    let mut dir = format!("{}x{}", width, height);
+                    if let Some(scale) = icon.scale.filter(|&scale| scale > 1) {
+                        dir.push_str(&format!("@{}", scale));
+                    }
+                    let path = cache.join(dir).join(&icon.value);
+                    path.is_file().then_some(path)
+                }
+                // Without a size, any of the size directories may have it
+                _ => fs::read_dir(cache)
+                    .ok()?
+                    .filter_map(|entry| Some(entry.ok()?.path().join(&icon.value)))
+                    .find(|path| path.is_file()),
+            }
+        }
+        _ => None,
+    }
+}
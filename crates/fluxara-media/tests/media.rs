@@ -0,0 +1,288 @@
+use fluxara_appstream::{Catalog, Component, Icon, IconKind, Screenshot, ScreenshotImage};
+use fluxara_core::{Package, PackageSource};
+use fluxara_media::{MediaCache, ScreenshotSize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Size of every file the stand-in server returns
+const FILE_SIZE: usize = 1000;
+
+/// Stand-in media server returning `FILE_SIZE` bytes for any path but
+/// `/missing`; returns its base URL and the paths requested so far
+fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let seen = requests.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            seen.lock().unwrap().push(request.url().to_string());
+            let response = if request.url() == "/missing.png" {
+                tiny_http::Response::from_data(Vec::new()).with_status_code(404)
+            } else {
+                tiny_http::Response::from_data(vec![b'x'; FILE_SIZE])
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    (base, requests)
+}
+
+fn icon(kind: IconKind, value: &str, size: Option<u32>, scale: Option<u32>) -> Icon {
+    Icon {
+        kind,
+        value: value.to_string(),
+        width: size,
+        height: size,
+        scale,
+    }
+}
+
+fn component(icons: Vec<Icon>, icon_cache: Option<&Path>) -> Component {
+    Component {
+        id: "org.example.Notes".to_string(),
+        pkgname: Some("notes".to_string()),
+        name: "Notes".to_string(),
+        icons,
+        icon_cache: icon_cache.map(Path::to_path_buf),
+        ..Component::default()
+    }
+}
+
+/// Icon cache with `notes.png` at 64x64, 64x64@2 and 128x128
+fn icon_cache(root: &Path) {
+    for dir in ["64x64", "64x64@2", "128x128"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(root.join(dir).join("notes.png"), dir).unwrap();
+    }
+}
+
+#[test]
+fn cached_icon_closest_to_size_is_used() {
+    let icons = tempfile::tempdir().unwrap();
+    icon_cache(icons.path());
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+    let component = component(
+        vec![
+            icon(IconKind::Stock, "notes", None, None),
+            icon(IconKind::Cached, "notes.png", Some(64), None),
+            icon(IconKind::Cached, "notes.png", Some(64), Some(2)),
+            icon(IconKind::Cached, "notes.png", Some(128), None),
+        ],
+        Some(icons.path()),
+    );
+
+    assert_eq!(
+        cache.local_icon(&component, 48).unwrap(),
+        icons.path().join("64x64/notes.png")
+    );
+    assert_eq!(
+        cache.local_icon(&component, 96).unwrap(),
+        icons.path().join("128x128/notes.png")
+    );
+    // Nothing is large enough, so the largest is the closest
+    assert_eq!(
+        cache.local_icon(&component, 256).unwrap(),
+        icons.path().join("128x128/notes.png")
+    );
+}
+
+#[test]
+fn scaled_cached_icon_is_found_in_its_directory() {
+    let icons = tempfile::tempdir().unwrap();
+    icon_cache(icons.path());
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+    let component = component(
+        vec![icon(IconKind::Cached, "notes.png", Some(64), Some(2))],
+        Some(icons.path()),
+    );
+
+    assert_eq!(
+        cache.local_icon(&component, 64).unwrap(),
+        icons.path().join("64x64@2/notes.png")
+    );
+}
+
+#[test]
+fn remote_icon_is_downloaded_once() {
+    let (base, requests) = serve();
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+    let component = component(
+        vec![icon(
+            IconKind::Remote,
+            &format!("{}/icons/128x128/notes.png", base),
+            Some(128),
+            None,
+        )],
+        None,
+    );
+
+    assert!(cache.local_icon(&component, 64).is_none());
+
+    let path = cache.icon(&component, 64).unwrap().unwrap();
+    assert!(path.starts_with(media.path()));
+    assert_eq!(path.extension().unwrap(), "png");
+    assert_eq!(fs::metadata(&path).unwrap().len(), FILE_SIZE as u64);
+
+    assert_eq!(cache.icon(&component, 64).unwrap().unwrap(), path);
+    assert_eq!(cache.local_icon(&component, 64).unwrap(), path);
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn better_fitting_remote_icon_is_preferred_for_download() {
+    let (base, requests) = serve();
+    let icons = tempfile::tempdir().unwrap();
+    icon_cache(icons.path());
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+    let component = component(
+        vec![
+            icon(IconKind::Cached, "notes.png", Some(128), None),
+            icon(
+                IconKind::Remote,
+                &format!("{}/icons/256x256/notes.png", base),
+                Some(256),
+                None,
+            ),
+        ],
+        Some(icons.path()),
+    );
+
+    // Without downloading, the cached icon is the best there is
+    assert_eq!(
+        cache.local_icon(&component, 256).unwrap(),
+        icons.path().join("128x128/notes.png")
+    );
+    let path = cache.icon(&component, 256).unwrap().unwrap();
+    assert!(path.starts_with(media.path()));
+    assert_eq!(*requests.lock().unwrap(), ["/icons/256x256/notes.png"]);
+}
+
+#[test]
+fn screenshot_sizes_pick_matching_variant() {
+    let (base, requests) = serve();
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+    let image = |name: &str, width: u32, thumbnail: bool| ScreenshotImage {
+        url: format!("{}/shots/{}", base, name),
+        width: Some(width),
+        height: Some(width * 9 / 16),
+        thumbnail,
+    };
+    let screenshot = Screenshot {
+        default: true,
+        caption: None,
+        url: format!("{}/shots/full.png", base),
+        thumbnail_url: Some(format!("{}/shots/224.png", base)),
+        images: vec![
+            image("full.png", 1920, false),
+            image("752.png", 752, true),
+            image("224.png", 224, true),
+        ],
+    };
+
+    cache
+        .screenshot(&screenshot, ScreenshotSize::Thumbnail)
+        .unwrap();
+    cache
+        .screenshot(&screenshot, ScreenshotSize::Width(600))
+        .unwrap();
+    cache
+        .screenshot(&screenshot, ScreenshotSize::Width(4000))
+        .unwrap();
+    cache
+        .screenshot(&screenshot, ScreenshotSize::Source)
+        .unwrap();
+
+    // The full-size image is fetched once, then served from the cache
+    assert_eq!(
+        *requests.lock().unwrap(),
+        ["/shots/224.png", "/shots/752.png", "/shots/full.png"]
+    );
+}
+
+#[test]
+fn least_recently_used_download_is_evicted() {
+    let (base, _requests) = serve();
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), 2500);
+    let url = |name: &str| format!("{}/{}.png", base, name);
+
+    let first = cache.fetch(&url("first")).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    let second = cache.fetch(&url("second")).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    // Using the first again makes the second the least recently used
+    cache.fetch(&url("first")).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    let third = cache.fetch(&url("third")).unwrap();
+
+    assert!(first.exists());
+    assert!(!second.exists());
+    assert!(third.exists());
+    assert_eq!(cache.size().unwrap(), 2 * FILE_SIZE as u64);
+}
+
+#[test]
+fn clear_removes_every_download() {
+    let (base, _requests) = serve();
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+    cache.fetch(&format!("{}/a.png", base)).unwrap();
+    cache.fetch(&format!("{}/b.jpg", base)).unwrap();
+
+    assert_eq!(cache.clear().unwrap(), 2 * FILE_SIZE as u64);
+    assert_eq!(cache.size().unwrap(), 0);
+}
+
+#[test]
+fn failed_download_leaves_nothing_behind() {
+    let (base, _requests) = serve();
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+
+    assert!(cache.fetch(&format!("{}/missing.png", base)).is_err());
+    assert_eq!(cache.size().unwrap(), 0);
+}
+
+#[test]
+fn package_icons_are_filled_from_catalog() {
+    let icons = tempfile::tempdir().unwrap();
+    icon_cache(icons.path());
+    let media = tempfile::tempdir().unwrap();
+    let cache = MediaCache::with_dir(media.path().to_path_buf(), u64::MAX);
+
+    let mut catalog = Catalog::new();
+    catalog.add(vec![component(
+        vec![icon(IconKind::Cached, "notes.png", Some(64), None)],
+        Some(icons.path()),
+    )]);
+    let package = |id: &str| Package {
+        id: id.to_string(),
+        name: id.to_string(),
+        version: None,
+        description: None,
+        icon_url: None,
+        source: PackageSource::Apt,
+        installed: false,
+    };
+    let mut packages = vec![package("notes"), package("bash")];
+
+    cache.fill_icon_urls(&catalog, &mut packages, 64);
+
+    let expected = icons.path().join("64x64/notes.png");
+    assert_eq!(
+        packages[0].icon_url.as_deref(),
+        Some(expected.to_str().unwrap())
+    );
+    assert_eq!(packages[1].icon_url, None);
+}
@@ -0,0 +1,73 @@
+//! PolicyKit actions for privileged operations, defined in
+//! `data/com.fluxara.Store.policy`
+
+use std::fmt;
+
+/// Operation an authorization is checked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Install packages
+    Install,
+    /// Remove packages
+    Remove,
+    /// Update installed packages
+    Update,
+    /// Add, remove or change package repositories and mirrors
+    RepoEdit,
+    /// Install hardware drivers
+    DriverInstall,
+    /// Clean caches, remove orphans and other system maintenance
+    Maintenance,
+    /// Download and apply updates in the background, without anyone to ask
+    Unattended,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Install,
+        Action::Remove,
+        Action::Update,
+        Action::RepoEdit,
+        Action::DriverInstall,
+        Action::Maintenance,
+        Action::Unattended,
+    ];
+
+    /// PolicyKit action id
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::Install => "com.fluxara.Store.install",
+            Action::Remove => "com.fluxara.Store.remove",
+            Action::Update => "com.fluxara.Store.update",
+            Action::RepoEdit => "com.fluxara.Store.repo-edit",
+            Action::DriverInstall => "com.fluxara.Store.driver-install",
+            Action::Maintenance => "com.fluxara.Store.maintenance",
+            Action::Unattended => "com.fluxara.Store.unattended",
+        }
+    }
+
+    /// What the action allows, for error messages
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Install => "install packages",
+            Action::Remove => "remove packages",
+            Action::Update => "update packages",
+            Action::RepoEdit => "change repositories",
+            Action::DriverInstall => "install drivers",
+            Action::Maintenance => "run system maintenance",
+            Action::Unattended => "update packages unattended",
+        }
+    }
+
+    /// Whether polkit may ask for a password; unattended work runs when
+    /// nobody is there to answer, so it is allowed or refused outright
+    pub fn is_interactive(&self) -> bool {
+        *self != Action::Unattended
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
@@ -0,0 +1,289 @@
+//! Tamper-evident log of the privileged operations requested of the helper
+//!
+//! Entries are JSON lines, each carrying the SHA-256 hash of the previous
+//! one, so editing or deleting an entry breaks the chain from that point on.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Location of the system audit log, written by the helper
+pub const AUDIT_LOG_PATH: &str = "/var/log/fluxara/audit.jsonl";
+
+/// Bytes read at a time when looking for the last entry from the end
+const TAIL_CHUNK: u64 = 4096;
+
+/// `prev_hash` of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// How a requested operation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditStatus {
+    Success,
+    /// PolicyKit refused the caller
+    Denied,
+    /// Rejected for its arguments before authorization
+    Invalid,
+    /// Authorized, attempted and failed
+    Failed,
+}
+
+impl AuditStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditStatus::Success => "success",
+            AuditStatus::Denied => "denied",
+            AuditStatus::Invalid => "invalid",
+            AuditStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for AuditStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A request to be logged
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub uid: Option<u32>,
+    pub caller: String,
+    pub action: String,
+    pub operation: String,
+    pub arguments: Vec<String>,
+    pub status: AuditStatus,
+    pub message: Option<String>,
+}
+
+/// One logged request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Uid of the calling process, when the bus could tell
+    pub uid: Option<u32>,
+    /// Unique bus name of the caller
+    pub caller: String,
+    /// PolicyKit action id the request was checked against
+    pub action: String,
+    /// Helper method called
+    pub operation: String,
+    pub arguments: Vec<String>,
+    pub status: AuditStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// `hash` of the previous entry
+    pub prev_hash: String,
+    /// SHA-256 of this entry serialized with an empty `hash`
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let unhashed = AuditEntry {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let json = serde_json::to_string(&unhashed).unwrap_or_default();
+        Sha256::digest(json.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// Where the chain of a log breaks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditBreak {
+    /// 1-based line number
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Result of checking a log's hash chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditVerification {
+    /// Entries that were checked before the chain broke, or all of them
+    pub entries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken: Option<AuditBreak>,
+}
+
+impl AuditVerification {
+    pub fn is_intact(&self) -> bool {
+        self.broken.is_none()
+    }
+}
+
+/// Append-only audit log of privileged requests, stored as hash-chained JSON lines
+pub struct AuditLog {
+    path: PathBuf,
+    /// Serializes appends of this process; the file lock covers any other
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    /// The system audit log
+    pub fn open() -> Self {
+        Self::at(AUDIT_LOG_PATH)
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All readable entries, oldest first
+    pub fn entries(&self) -> Result<Vec<AuditEntry>> {
+        Ok(self
+            .lines()?
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Append a request to the chain and return its entry
+    ///
+    /// Only the last entry is read to continue the chain, under an exclusive
+    /// lock of the file so that no other writer slips in between.
+    pub fn record(&self, record: AuditRecord) -> Result<AuditEntry> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Readable by everyone for `fluxara audit`, writable by the helper only
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .mode(0o644)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.lock()
+            .with_context(|| format!("Failed to lock {}", self.path.display()))?;
+
+        let last = last_line(&mut file)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?
+            .map(|line| serde_json::from_str::<AuditEntry>(&line))
+            .transpose()
+            .with_context(|| format!("Last entry of {} is unreadable", self.path.display()))?;
+        let mut entry = AuditEntry {
+            seq: last.as_ref().map_or(1, |last| last.seq + 1),
+            timestamp: Utc::now(),
+            uid: record.uid,
+            caller: record.caller,
+            action: record.action,
+            operation: record.operation,
+            arguments: record.arguments,
+            status: record.status,
+            message: record.message,
+            prev_hash: last.map_or_else(|| GENESIS_HASH.to_string(), |last| last.hash),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+
+        Ok(entry)
+    }
+
+    /// Check every entry's hash and its link to the one before
+    ///
+    /// Entries removed from the end of the log cannot be detected.
+    pub fn verify(&self) -> Result<AuditVerification> {
+        let mut prev_hash = GENESIS_HASH.to_string();
+        let mut prev_seq = 0;
+        let broken = |line: usize, reason: String| AuditVerification {
+            entries: line - 1,
+            broken: Some(AuditBreak { line, reason }),
+        };
+
+        let lines = self.lines()?;
+        for (index, line) in lines.iter().enumerate() {
+            let number = index + 1;
+            let entry: AuditEntry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                Err(e) => return Ok(broken(number, format!("not a valid entry: {}", e))),
+            };
+            if entry.seq != prev_seq + 1 {
+                return Ok(broken(
+                    number,
+                    format!("entry {} follows entry {}", entry.seq, prev_seq),
+                ));
+            }
+            if entry.prev_hash != prev_hash {
+                return Ok(broken(
+                    number,
+                    format!("entry {} does not follow the entry before it", entry.seq),
+                ));
+            }
+            if entry.compute_hash() != entry.hash {
+                return Ok(broken(number, format!("entry {} was modified", entry.seq)));
+            }
+            prev_seq = entry.seq;
+            prev_hash = entry.hash;
+        }
+
+        Ok(AuditVerification {
+            entries: lines.len(),
+            broken: None,
+        })
+    }
+
+    fn lines(&self) -> Result<Vec<String>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open {}", self.path.display()))
+            }
+        };
+        BufReader::new(file)
+            .lines()
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+            .collect::<std::io::Result<_>>()
+            .with_context(|| format!("Failed to read {}", self.path.display()))
+    }
+}
+
+/// Last non-blank line of `file`, read backwards from its end
+fn last_line(file: &mut File) -> std::io::Result<Option<String>> {
+    let mut position = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    while position > 0 {
+        let step = position.min(TAIL_CHUNK);
+        position -= step;
+        let mut chunk = vec![0; step as usize];
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+
+        let content = tail.trim_ascii_end();
+        if let Some(newline) = content.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(Some(
+                String::from_utf8_lossy(&content[newline + 1..]).into_owned(),
+            ));
+        }
+    }
+
+    let content = tail.trim_ascii_end();
+    Ok((!content.is_empty()).then(|| String::from_utf8_lossy(content).into_owned()))
+}
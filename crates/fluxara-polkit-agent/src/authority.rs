@@ -0,0 +1,146 @@
+//! Client of the PolicyKit authority, `org.freedesktop.PolicyKit1.Authority`
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use zbus::zvariant::Value;
+
+/// `CheckAuthorization` flag letting polkit ask the user to authenticate
+const ALLOW_USER_INTERACTION: u32 = 1;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority",
+    gen_blocking = false
+)]
+trait Authority {
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+}
+
+/// Whom an authorization is checked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subject {
+    /// A local process, identified by pid and start time so a recycled pid
+    /// is not mistaken for it
+    UnixProcess { pid: u32, start_time: u64, uid: u32 },
+    /// The peer owning a unique name on the system bus, as seen by a service
+    BusName(String),
+}
+
+impl Subject {
+    /// This process
+    pub fn current_process() -> Result<Self> {
+        Self::process(std::process::id())
+    }
+
+    /// A running process, looked up in `/proc`
+    pub fn process(pid: u32) -> Result<Self> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid))
+            .with_context(|| format!("Process {} not found", pid))?;
+        // The command name may contain spaces, the fields after it do not;
+        // the start time is field 22, the 20th after the name
+        let start_time = stat
+            .rsplit_once(')')
+            .and_then(|(_, fields)| fields.split_whitespace().nth(19))
+            .and_then(|field| field.parse().ok())
+            .with_context(|| format!("Unexpected /proc/{}/stat format", pid))?;
+
+        let status = fs::read_to_string(format!("/proc/{}/status", pid))
+            .with_context(|| format!("Process {} not found", pid))?;
+        let uid = status
+            .lines()
+            .find_map(|line| line.strip_prefix("Uid:"))
+            .and_then(|ids| ids.split_whitespace().next())
+            .and_then(|uid| uid.parse().ok())
+            .with_context(|| format!("Unexpected /proc/{}/status format", pid))?;
+
+        Ok(Subject::UnixProcess {
+            pid,
+            start_time,
+            uid,
+        })
+    }
+
+    pub fn bus_name(name: impl Into<String>) -> Self {
+        Subject::BusName(name.into())
+    }
+
+    /// Subject in its D-Bus form, `(sa{sv})`
+    fn to_dbus(&self) -> (&'static str, HashMap<&'static str, Value<'_>>) {
+        match self {
+            Subject::UnixProcess {
+                pid,
+                start_time,
+                uid,
+            } => (
+                "unix-process",
+                HashMap::from([
+                    ("pid", Value::from(*pid)),
+                    ("start-time", Value::from(*start_time)),
+                    ("uid", Value::from(*uid as i32)),
+                ]),
+            ),
+            Subject::BusName(name) => (
+                "system-bus-name",
+                HashMap::from([("name", Value::from(name.as_str()))]),
+            ),
+        }
+    }
+}
+
+/// Outcome of an authorization check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authorization {
+    Authorized,
+    /// Allowed only after authenticating, which was not attempted
+    Challenge,
+    NotAuthorized,
+    /// The user was asked to authenticate and cancelled
+    Dismissed,
+}
+
+impl Authorization {
+    pub fn is_authorized(&self) -> bool {
+        *self == Authorization::Authorized
+    }
+}
+
+/// Ask the authority whether `subject` may perform `action_id`
+pub(crate) async fn check(
+    connection: &zbus::Connection,
+    subject: &Subject,
+    action_id: &str,
+    interactive: bool,
+) -> Result<Authorization> {
+    let authority = AuthorityProxy::new(connection)
+        .await
+        .context("Failed to reach PolicyKit")?;
+    let flags = if interactive {
+        ALLOW_USER_INTERACTION
+    } else {
+        0
+    };
+
+    let (authorized, challenge, details) = authority
+        .check_authorization(&subject.to_dbus(), action_id, HashMap::new(), flags, "")
+        .await
+        .with_context(|| format!("PolicyKit check of {} failed", action_id))?;
+
+    Ok(if authorized {
+        Authorization::Authorized
+    } else if details.get("polkit.dismissed").is_some_and(|v| v == "true") {
+        Authorization::Dismissed
+    } else if challenge {
+        Authorization::Challenge
+    } else {
+        Authorization::NotAuthorized
+    })
+}
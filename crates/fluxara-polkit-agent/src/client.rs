@@ -0,0 +1,333 @@
+//! Client of the privileged helper, `fluxara-helper`, on the system bus
+
+use fluxara_core::PackageSource;
+use futures_util::StreamExt;
+use std::future::Future;
+use thiserror::Error;
+
+/// Well-known name of the helper on the system bus
+pub const HELPER_BUS_NAME: &str = "com.fluxara.Store.Helper";
+/// Object path of the helper
+pub const HELPER_PATH: &str = "/com/fluxara/Store/Helper";
+/// D-Bus interface of the helper
+pub const HELPER_INTERFACE: &str = "com.fluxara.Store.Helper1";
+
+/// Generated proxy, kept apart as its signal type would clash with [`Progress`]
+mod dbus {
+    #[zbus::proxy(
+        interface = "com.fluxara.Store.Helper1",
+        default_service = "com.fluxara.Store.Helper",
+        default_path = "/com/fluxara/Store/Helper",
+        gen_blocking = false
+    )]
+    pub(super) trait Helper {
+        fn install_packages(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn remove_packages(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn update_packages(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn download_packages(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn unattended_update(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn unattended_download(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn install_version(&self, source: &str, package: &str, version: &str) -> zbus::Result<()>;
+        fn hold_packages(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn unhold_packages(&self, source: &str, packages: &[&str]) -> zbus::Result<()>;
+        fn edit_repo(
+            &self,
+            source: &str,
+            operation: &str,
+            entry: &str,
+            url: &str,
+        ) -> zbus::Result<()>;
+        fn clean_cache(&self) -> zbus::Result<u64>;
+        fn install_driver(&self, name: &str) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        fn progress(
+            &self,
+            operation: &str,
+            current: u32,
+            total: u32,
+            message: &str,
+        ) -> zbus::Result<()>;
+    }
+}
+
+/// Progress of a helper operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Method being run, e.g. `InstallPackages`
+    pub operation: String,
+    /// Steps done out of `total`
+    pub current: u32,
+    pub total: u32,
+    pub message: String,
+}
+
+/// Change to a package repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepoEdit {
+    /// Add a one-line APT source such as `deb https://example.org/debian stable main`
+    AddAptSource(String),
+    /// Remove an APT source added with [`RepoEdit::AddAptSource`]
+    RemoveAptSource(String),
+    /// Add a system-wide Flatpak remote
+    AddFlatpakRemote { name: String, url: String },
+    /// Remove a system-wide Flatpak remote
+    RemoveFlatpakRemote(String),
+}
+
+/// Failure reported by the helper
+#[derive(Debug, Error)]
+pub enum HelperError {
+    /// The caller is not authorized, or cancelled authentication
+    #[error("{0}")]
+    Denied(String),
+    /// The request was rejected before anything was done
+    #[error("{0}")]
+    Invalid(String),
+    /// The operation was attempted and failed
+    #[error("{0}")]
+    Failed(String),
+    /// The helper could not be reached
+    #[error("Failed to reach the Fluxara helper: {0}")]
+    Unavailable(String),
+}
+
+impl From<zbus::Error> for HelperError {
+    fn from(error: zbus::Error) -> Self {
+        match error {
+            zbus::Error::MethodError(name, message, _) => {
+                let message = message.unwrap_or_else(|| name.to_string());
+                match name.as_str() {
+                    "org.freedesktop.DBus.Error.AccessDenied" => HelperError::Denied(message),
+                    "org.freedesktop.DBus.Error.InvalidArgs" => HelperError::Invalid(message),
+                    "org.freedesktop.DBus.Error.ServiceUnknown"
+                    | "org.freedesktop.DBus.Error.NameHasNoOwner" => {
+                        HelperError::Unavailable(message)
+                    }
+                    _ => HelperError::Failed(message),
+                }
+            }
+            other => HelperError::Unavailable(other.to_string()),
+        }
+    }
+}
+
+/// Typed access to the privileged helper's operations
+///
+/// Each call waits for the operation to finish, passing the progress the
+/// helper reports on the way to `on_progress`.
+#[derive(Clone)]
+pub struct HelperClient {
+    proxy: dbus::HelperProxy<'static>,
+}
+
+impl HelperClient {
+    /// Client of the helper on the system bus
+    pub async fn system() -> Result<Self, HelperError> {
+        Self::with_connection(zbus::Connection::system().await?).await
+    }
+
+    /// Client of the helper reachable over `connection`
+    pub async fn with_connection(connection: zbus::Connection) -> Result<Self, HelperError> {
+        Ok(Self {
+            proxy: dbus::HelperProxy::new(&connection).await?,
+        })
+    }
+
+    pub async fn install_packages(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.install_packages(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    pub async fn remove_packages(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.remove_packages(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Update the given packages, or every package with an update when empty
+    pub async fn update_packages(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.update_packages(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Fetch the updates of the given packages without installing them
+    pub async fn download_packages(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.download_packages(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Update packages as the update daemon, authorized as
+    /// [`Action::Unattended`](crate::Action::Unattended) without asking anyone
+    pub async fn unattended_update(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.unattended_update(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Fetch updates as the update daemon, authorized as
+    /// [`Self::unattended_update`]
+    pub async fn unattended_download(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.unattended_download(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Install exactly `version` of a package, replacing the installed one
+    pub async fn install_version(
+        &self,
+        source: PackageSource,
+        package_id: &str,
+        version: &str,
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        self.with_progress(
+            self.proxy
+                .install_version(source.as_str(), package_id, version),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Keep the given packages at their installed version
+    pub async fn hold_packages(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(self.proxy.hold_packages(source.as_str(), &ids), on_progress)
+            .await
+    }
+
+    /// Let held packages be updated again
+    pub async fn unhold_packages(
+        &self,
+        source: PackageSource,
+        package_ids: &[String],
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let ids: Vec<&str> = package_ids.iter().map(String::as_str).collect();
+        self.with_progress(
+            self.proxy.unhold_packages(source.as_str(), &ids),
+            on_progress,
+        )
+        .await
+    }
+
+    pub async fn edit_repo(
+        &self,
+        edit: &RepoEdit,
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        let (source, operation, entry, url) = match edit {
+            RepoEdit::AddAptSource(entry) => ("apt", "add", entry.as_str(), ""),
+            RepoEdit::RemoveAptSource(entry) => ("apt", "remove", entry.as_str(), ""),
+            RepoEdit::AddFlatpakRemote { name, url } => {
+                ("flatpak", "add", name.as_str(), url.as_str())
+            }
+            RepoEdit::RemoveFlatpakRemote(name) => ("flatpak", "remove", name.as_str(), ""),
+        };
+        self.with_progress(
+            self.proxy.edit_repo(source, operation, entry, url),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Clean the package caches; returns the number of bytes freed
+    pub async fn clean_cache(&self, on_progress: impl FnMut(Progress)) -> Result<u64, HelperError> {
+        self.with_progress(self.proxy.clean_cache(), on_progress)
+            .await
+    }
+
+    /// Install a driver offered by `fluxara driver list`
+    pub async fn install_driver(
+        &self,
+        name: &str,
+        on_progress: impl FnMut(Progress),
+    ) -> Result<(), HelperError> {
+        self.with_progress(self.proxy.install_driver(name), on_progress)
+            .await
+    }
+
+    /// Await `call`, passing progress signals to `on_progress` meanwhile
+    async fn with_progress<T>(
+        &self,
+        call: impl Future<Output = zbus::Result<T>>,
+        mut on_progress: impl FnMut(Progress),
+    ) -> Result<T, HelperError> {
+        let mut signals = self.proxy.receive_progress().await?;
+        tokio::pin!(call);
+
+        loop {
+            tokio::select! {
+                // Progress already received is passed on before the reply
+                biased;
+                Some(signal) = signals.next() => {
+                    if let Ok(args) = signal.args() {
+                        on_progress(Progress {
+                            operation: args.operation.to_string(),
+                            current: args.current,
+                            total: args.total,
+                            message: args.message.to_string(),
+                        });
+                    }
+                }
+                result = &mut call => return result.map_err(HelperError::from),
+            }
+        }
+    }
+}
@@ -1,7 +1,30 @@
-use anyhow::Result;
+//! PolicyKit authorization, the client of the privileged helper that
+//! performs operations needing root, the routing of package changes to it,
+//! and the audit log the helper keeps
 
-/// PolicyKit helper for privileged operations
-pub struct PolkitAgent;
+mod action;
+mod audit;
+mod authority;
+mod client;
+mod privilege;
+
+pub use action::Action;
+pub use audit::{
+    AuditBreak, AuditEntry, AuditLog, AuditRecord, AuditStatus, AuditVerification, AUDIT_LOG_PATH,
+};
+pub use authority::{Authorization, Subject};
+pub use client::{
+    HelperClient, HelperError, Progress, RepoEdit, HELPER_BUS_NAME, HELPER_INTERFACE, HELPER_PATH,
+};
+pub use privilege::{is_privileged, requires_elevation, route, route_unattended, ElevatedProvider};
+
+use anyhow::{bail, Context, Result};
+use tokio::sync::OnceCell;
+
+/// Checks PolicyKit authorizations for privileged operations
+pub struct PolkitAgent {
+    connection: OnceCell<zbus::Connection>,
+}
 
 impl Default for PolkitAgent {
     fn default() -> Self {
@@ -10,60 +33,58 @@ impl Default for PolkitAgent {
 }
 
 impl PolkitAgent {
+    /// Agent asking the PolicyKit authority on the system bus, connected on first use
     pub fn new() -> Self {
-        Self
+        Self {
+            connection: OnceCell::new(),
+        }
     }
 
-    /// Execute a privileged command via PolicyKit
-    pub fn execute_privileged(&self, command: &str, args: &[&str]) -> Result<()> {
-        // Stub implementation - would use PolicyKit authentication
-        println!("Executing privileged command: {} {:?}", command, args);
-
-        // In real implementation, would:
-        // 1. Check PolicyKit authorization
-        // 2. Execute command with elevated privileges
-        // 3. Return result
-
-        Ok(())
+    /// Agent asking the authority reachable over `connection`
+    pub fn with_connection(connection: zbus::Connection) -> Self {
+        Self {
+            connection: OnceCell::new_with(Some(connection)),
+        }
     }
 
-    /// Check if user has privilege for an action
-    pub fn check_authorization(&self, action: &str) -> Result<bool> {
-        println!("Checking authorization for action: {}", action);
-
-        // Stub - would use PolicyKit to check
-        Ok(true)
+    async fn connection(&self) -> Result<&zbus::Connection> {
+        self.connection
+            .get_or_try_init(|| async {
+                zbus::Connection::system()
+                    .await
+                    .context("Failed to connect to the system bus")
+            })
+            .await
     }
 
-    /// Install package with privilege
-    pub fn install_package(&self, package_manager: &str, package_id: &str) -> Result<()> {
-        match package_manager {
-            "apt" => {
-                self.execute_privileged("apt-get", &["install", "-y", package_id])?;
-            }
-            "pacman" => {
-                self.execute_privileged("pacman", &["-S", "--noconfirm", package_id])?;
-            }
-            _ => {
-                anyhow::bail!("Unsupported package manager: {}", package_manager);
-            }
-        }
-        Ok(())
+    /// Check whether `subject` may perform `action`; with `interactive`,
+    /// polkit may ask the user to authenticate first
+    pub async fn check_authorization(
+        &self,
+        action: Action,
+        subject: &Subject,
+        interactive: bool,
+    ) -> Result<Authorization> {
+        let authorization =
+            authority::check(self.connection().await?, subject, action.id(), interactive).await?;
+        tracing::debug!("{} for {:?}: {:?}", action, subject, authorization);
+        Ok(authorization)
     }
 
-    /// Remove package with privilege
-    pub fn remove_package(&self, package_manager: &str, package_id: &str) -> Result<()> {
-        match package_manager {
-            "apt" => {
-                self.execute_privileged("apt-get", &["remove", "-y", package_id])?;
-            }
-            "pacman" => {
-                self.execute_privileged("pacman", &["-R", "--noconfirm", package_id])?;
+    /// Fail unless `subject` is authorized for `action`, authenticating if
+    /// needed and the action allows it
+    pub async fn authorize(&self, action: Action, subject: &Subject) -> Result<()> {
+        match self
+            .check_authorization(action, subject, action.is_interactive())
+            .await?
+        {
+            Authorization::Authorized => Ok(()),
+            Authorization::Dismissed => {
+                bail!("Authentication to {} was cancelled", action.description())
             }
-            _ => {
-                anyhow::bail!("Unsupported package manager: {}", package_manager);
+            Authorization::Challenge | Authorization::NotAuthorized => {
+                bail!("Not authorized to {}", action.description())
             }
         }
-        Ok(())
     }
 }
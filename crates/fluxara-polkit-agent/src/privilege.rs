@@ -0,0 +1,230 @@
+//! Which operations need root, and routing them through the helper
+//!
+//! Decisions are made per [`PackageSource`] and [`InstallScope`], so a new
+//! backend only has to say whether it writes to the system to have its
+//! installs, removals and updates sent to the helper.
+
+use crate::client::{HelperClient, Progress};
+use anyhow::Result;
+use async_trait::async_trait;
+use fluxara_core::{
+    InstallPlan, InstallScope, Package, PackageDetails, PackageManager, PackageSource, UpdateInfo,
+};
+use std::os::unix::fs::MetadataExt;
+use tokio::sync::OnceCell;
+
+/// Whether changing packages of `source` in `scope` needs administrator rights
+///
+/// Flatpak has a per-user installation that its owner may change freely.
+/// AUR packages are built as the calling user, never as root; the AUR helper
+/// elevates only the final install itself.
+pub fn requires_elevation(source: PackageSource, scope: InstallScope) -> bool {
+    match source {
+        PackageSource::Flatpak => scope == InstallScope::System,
+        PackageSource::Aur => false,
+        PackageSource::Apt | PackageSource::Pacman | PackageSource::Snap => true,
+    }
+}
+
+/// Whether this process runs as root
+pub fn is_privileged() -> bool {
+    std::fs::metadata("/proc/self").is_ok_and(|metadata| metadata.uid() == 0)
+}
+
+/// `provider` as is when it can change packages itself, otherwise wrapped so
+/// that its changes go through the helper
+pub fn route(provider: Box<dyn PackageManager>, scope: InstallScope) -> Box<dyn PackageManager> {
+    if is_privileged() || !requires_elevation(provider.source(), scope) {
+        provider
+    } else {
+        Box::new(ElevatedProvider::new(provider))
+    }
+}
+
+/// As [`route`], for the update daemon: updates and downloads sent to the
+/// helper are authorized as [`Action::Unattended`](crate::Action::Unattended),
+/// which never prompts
+pub fn route_unattended(
+    provider: Box<dyn PackageManager>,
+    scope: InstallScope,
+) -> Box<dyn PackageManager> {
+    if is_privileged() || !requires_elevation(provider.source(), scope) {
+        provider
+    } else {
+        Box::new(ElevatedProvider::new(provider).unattended())
+    }
+}
+
+/// A provider whose installs, removals, updates and holds are performed by
+/// the privileged helper; queries are answered by the wrapped provider
+pub struct ElevatedProvider {
+    inner: Box<dyn PackageManager>,
+    helper: OnceCell<HelperClient>,
+    unattended: bool,
+}
+
+impl ElevatedProvider {
+    /// Send changes to the helper on the system bus, connected on first use
+    pub fn new(inner: Box<dyn PackageManager>) -> Self {
+        Self {
+            inner,
+            helper: OnceCell::new(),
+            unattended: false,
+        }
+    }
+
+    /// Send changes to the helper reachable through `helper`
+    pub fn with_client(inner: Box<dyn PackageManager>, helper: HelperClient) -> Self {
+        Self {
+            inner,
+            helper: OnceCell::new_with(Some(helper)),
+            unattended: false,
+        }
+    }
+
+    /// Ask for updates and downloads as the update daemon, with nobody to
+    /// authenticate
+    pub fn unattended(mut self) -> Self {
+        self.unattended = true;
+        self
+    }
+
+    async fn helper(&self) -> Result<&HelperClient> {
+        Ok(self.helper.get_or_try_init(HelperClient::system).await?)
+    }
+}
+
+fn log_progress(progress: Progress) {
+    tracing::debug!(
+        "{} {}/{}: {}",
+        progress.operation,
+        progress.current,
+        progress.total,
+        progress.message
+    );
+}
+
+#[async_trait]
+impl PackageManager for ElevatedProvider {
+    fn source(&self) -> PackageSource {
+        self.inner.source()
+    }
+
+    fn is_available(&self) -> bool {
+        self.inner.is_available()
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Package>> {
+        self.inner.search(query).await
+    }
+
+    async fn install(&self, package_id: &str) -> Result<()> {
+        self.install_packages(&[package_id.to_string()]).await
+    }
+
+    async fn remove(&self, package_id: &str) -> Result<()> {
+        self.remove_packages(&[package_id.to_string()]).await
+    }
+
+    async fn update(&self, package_id: &str) -> Result<()> {
+        self.update_packages(&[package_id.to_string()]).await
+    }
+
+    async fn list_installed(&self) -> Result<Vec<Package>> {
+        self.inner.list_installed().await
+    }
+
+    async fn list_available(&self) -> Result<Vec<Package>> {
+        self.inner.list_available().await
+    }
+
+    async fn list_explicit(&self) -> Result<Vec<Package>> {
+        self.inner.list_explicit().await
+    }
+
+    async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
+        self.inner.list_updates().await
+    }
+
+    async fn get_install_plan(&self, package_id: &str) -> Result<InstallPlan> {
+        self.inner.get_install_plan(package_id).await
+    }
+
+    async fn details(&self, package_id: &str) -> Result<Option<PackageDetails>> {
+        self.inner.details(package_id).await
+    }
+
+    async fn install_packages(&self, package_ids: &[String]) -> Result<()> {
+        Ok(self
+            .helper()
+            .await?
+            .install_packages(self.source(), package_ids, log_progress)
+            .await?)
+    }
+
+    async fn remove_packages(&self, package_ids: &[String]) -> Result<()> {
+        Ok(self
+            .helper()
+            .await?
+            .remove_packages(self.source(), package_ids, log_progress)
+            .await?)
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> Result<()> {
+        let helper = self.helper().await?;
+        if self.unattended {
+            helper
+                .unattended_update(self.source(), package_ids, log_progress)
+                .await?;
+        } else {
+            helper
+                .update_packages(self.source(), package_ids, log_progress)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn download_updates(&self, package_ids: &[String]) -> Result<()> {
+        let helper = self.helper().await?;
+        if self.unattended {
+            helper
+                .unattended_download(self.source(), package_ids, log_progress)
+                .await?;
+        } else {
+            helper
+                .download_packages(self.source(), package_ids, log_progress)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn install_version(&self, package_id: &str, version: &str) -> Result<()> {
+        Ok(self
+            .helper()
+            .await?
+            .install_version(self.source(), package_id, version, log_progress)
+            .await?)
+    }
+
+    async fn hold(&self, package_id: &str) -> Result<()> {
+        Ok(self
+            .helper()
+            .await?
+            .hold_packages(self.source(), &[package_id.to_string()], log_progress)
+            .await?)
+    }
+
+    async fn unhold(&self, package_id: &str) -> Result<()> {
+        Ok(self
+            .helper()
+            .await?
+            .unhold_packages(self.source(), &[package_id.to_string()], log_progress)
+            .await?)
+    }
+
+    /// Holds are read without root: `apt-mark showhold`, the pacman include
+    /// and `flatpak mask` only need read access
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        self.inner.list_holds().await
+    }
+}
@@ -0,0 +1,155 @@
+use fluxara_polkit_agent::{AuditLog, AuditRecord, AuditStatus};
+use std::fs;
+
+fn record(operation: &str, status: AuditStatus) -> AuditRecord {
+    AuditRecord {
+        uid: Some(1000),
+        caller: ":1.42".to_string(),
+        action: "com.fluxara.Store.install".to_string(),
+        operation: operation.to_string(),
+        arguments: vec!["apt".to_string(), "curl".to_string()],
+        status,
+        message: None,
+    }
+}
+
+/// Log of three entries in a fresh directory
+fn log_of_three() -> (tempfile::TempDir, AuditLog) {
+    let dir = tempfile::tempdir().unwrap();
+    let log = AuditLog::at(dir.path().join("audit.jsonl"));
+    log.record(record("InstallPackages", AuditStatus::Success))
+        .unwrap();
+    log.record(record("RemovePackages", AuditStatus::Denied))
+        .unwrap();
+    log.record(record("UpdatePackages", AuditStatus::Failed))
+        .unwrap();
+    (dir, log)
+}
+
+fn edit_lines(log: &AuditLog, edit: impl FnOnce(&mut Vec<String>)) {
+    let mut lines: Vec<String> = fs::read_to_string(log.path())
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    edit(&mut lines);
+    fs::write(log.path(), lines.join("\n") + "\n").unwrap();
+}
+
+#[test]
+fn records_are_chained() {
+    let (_dir, log) = log_of_three();
+
+    let entries = log.entries().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].seq, 1);
+    assert_eq!(entries[0].prev_hash, "0".repeat(64));
+    assert_eq!(entries[1].prev_hash, entries[0].hash);
+    assert_eq!(entries[2].prev_hash, entries[1].hash);
+    assert_eq!(entries[1].status, AuditStatus::Denied);
+
+    let verification = log.verify().unwrap();
+    assert!(verification.is_intact());
+    assert_eq!(verification.entries, 3);
+}
+
+#[test]
+fn missing_log_is_empty_and_intact() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = AuditLog::at(dir.path().join("audit.jsonl"));
+
+    assert!(log.entries().unwrap().is_empty());
+    let verification = log.verify().unwrap();
+    assert!(verification.is_intact());
+    assert_eq!(verification.entries, 0);
+}
+
+#[test]
+fn modified_entry_is_detected() {
+    let (_dir, log) = log_of_three();
+    edit_lines(&log, |lines| {
+        lines[1] = lines[1].replace("\"denied\"", "\"success\"");
+    });
+
+    let broken = log.verify().unwrap().broken.unwrap();
+    assert_eq!(broken.line, 2);
+    assert_eq!(broken.reason, "entry 2 was modified");
+}
+
+#[test]
+fn deleted_entry_is_detected() {
+    let (_dir, log) = log_of_three();
+    edit_lines(&log, |lines| {
+        lines.remove(1);
+    });
+
+    let verification = log.verify().unwrap();
+    assert_eq!(verification.entries, 1);
+    let broken = verification.broken.unwrap();
+    assert_eq!(broken.line, 2);
+    assert_eq!(broken.reason, "entry 3 follows entry 1");
+}
+
+#[test]
+fn renumbered_entry_is_detected() {
+    let (_dir, log) = log_of_three();
+    // Drop entry 2 and renumber entry 3 so the sequence still looks whole
+    edit_lines(&log, |lines| {
+        lines.remove(1);
+        lines[1] = lines[1].replace("\"seq\":3", "\"seq\":2");
+    });
+
+    let broken = log.verify().unwrap().broken.unwrap();
+    assert_eq!(broken.line, 2);
+    assert_eq!(broken.reason, "entry 2 does not follow the entry before it");
+}
+
+#[test]
+fn garbage_line_is_detected() {
+    let (_dir, log) = log_of_three();
+    edit_lines(&log, |lines| {
+        lines.insert(1, "rm -rf /".to_string());
+    });
+
+    let broken = log.verify().unwrap().broken.unwrap();
+    assert_eq!(broken.line, 2);
+    assert!(broken.reason.starts_with("not a valid entry"));
+    // Readable entries are still listed
+    assert_eq!(log.entries().unwrap().len(), 3);
+}
+
+#[test]
+fn recording_continues_the_chain() {
+    let (_dir, log) = log_of_three();
+    let reopened = AuditLog::at(log.path());
+
+    let entry = reopened
+        .record(record("CleanCache", AuditStatus::Success))
+        .unwrap();
+
+    assert_eq!(entry.seq, 4);
+    assert_eq!(entry.prev_hash, log.entries().unwrap()[2].hash);
+    assert!(reopened.verify().unwrap().is_intact());
+}
+
+#[test]
+fn long_entries_and_trailing_blank_lines_are_followed() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = AuditLog::at(dir.path().join("audit.jsonl"));
+    // Longer than one chunk read from the end of the file
+    let mut long = record("InstallPackages", AuditStatus::Success);
+    long.arguments = (0..2000).map(|i| format!("package-{}", i)).collect();
+    log.record(long.clone()).unwrap();
+    log.record(long).unwrap();
+    let mut content = fs::read_to_string(log.path()).unwrap();
+    content.push_str("\n  \n");
+    fs::write(log.path(), content).unwrap();
+
+    let entry = log
+        .record(record("CleanCache", AuditStatus::Success))
+        .unwrap();
+
+    assert_eq!(entry.seq, 3);
+    assert_eq!(entry.prev_hash, log.entries().unwrap()[1].hash);
+    assert!(log.verify().unwrap().is_intact());
+}
@@ -0,0 +1,274 @@
+#[path = "../../../tests/support/bus.rs"]
+mod common;
+
+use common::{private_bus, Bus};
+use fluxara_polkit_agent::{Action, Authorization, PolkitAgent, Subject};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::zvariant::OwnedValue;
+
+/// `CheckAuthorization` result: authorized, challenge and details
+type CheckResult = (bool, bool, HashMap<String, String>);
+
+/// A `CheckAuthorization` call the mock authority received
+#[derive(Debug, Clone)]
+struct Call {
+    kind: String,
+    subject: HashMap<String, OwnedValue>,
+    action_id: String,
+    flags: u32,
+}
+
+/// Stand-in for polkitd answering each action id with a canned result
+struct MockAuthority {
+    results: HashMap<String, CheckResult>,
+    calls: Arc<Mutex<Vec<Call>>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.PolicyKit1.Authority")]
+impl MockAuthority {
+    fn check_authorization(
+        &self,
+        subject: (String, HashMap<String, OwnedValue>),
+        action_id: String,
+        _details: HashMap<String, String>,
+        flags: u32,
+        _cancellation_id: String,
+    ) -> CheckResult {
+        self.calls.lock().unwrap().push(Call {
+            kind: subject.0,
+            subject: subject.1,
+            action_id: action_id.clone(),
+            flags,
+        });
+        self.results
+            .get(&action_id)
+            .cloned()
+            .unwrap_or((false, false, HashMap::new()))
+    }
+}
+
+/// Everything a test needs, kept alive together
+struct Fixture {
+    agent: PolkitAgent,
+    calls: Arc<Mutex<Vec<Call>>>,
+    _authority: zbus::Connection,
+    _bus: Bus,
+}
+
+/// Canned result of an action: authorized, challenge and details
+type Reply<'a> = (Action, (bool, bool, &'a [(&'a str, &'a str)]));
+
+async fn fixture(results: &[Reply<'_>]) -> Option<Fixture> {
+    let Some(bus) = private_bus() else {
+        eprintln!("dbus-daemon not available, skipping");
+        return None;
+    };
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mock = MockAuthority {
+        results: results
+            .iter()
+            .map(|(action, (authorized, challenge, details))| {
+                let details = details
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect();
+                (action.id().to_string(), (*authorized, *challenge, details))
+            })
+            .collect(),
+        calls: calls.clone(),
+    };
+    let authority = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.freedesktop.PolicyKit1")
+        .unwrap()
+        .serve_at("/org/freedesktop/PolicyKit1/Authority", mock)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    let client = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    Some(Fixture {
+        agent: PolkitAgent::with_connection(client),
+        calls,
+        _authority: authority,
+        _bus: bus,
+    })
+}
+
+#[tokio::test]
+async fn authorized_action_is_checked_for_current_process() {
+    let Some(fixture) = fixture(&[(Action::Install, (true, false, &[]))]).await else {
+        return;
+    };
+    let subject = Subject::current_process().unwrap();
+
+    let authorization = fixture
+        .agent
+        .check_authorization(Action::Install, &subject, false)
+        .await
+        .unwrap();
+    assert_eq!(authorization, Authorization::Authorized);
+    fixture
+        .agent
+        .authorize(Action::Install, &subject)
+        .await
+        .unwrap();
+
+    let calls = fixture.calls.lock().unwrap();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].kind, "unix-process");
+    assert_eq!(calls[0].action_id, "com.fluxara.Store.install");
+    assert_eq!(
+        calls[0].subject["pid"].downcast_ref::<u32>().unwrap(),
+        std::process::id()
+    );
+    assert!(
+        calls[0].subject["start-time"]
+            .downcast_ref::<u64>()
+            .unwrap()
+            > 0
+    );
+    assert!(calls[0].subject.contains_key("uid"));
+    // Only authorize lets polkit ask for a password
+    assert_eq!(calls[0].flags, 0);
+    assert_eq!(calls[1].flags, 1);
+}
+
+#[tokio::test]
+async fn each_operation_has_its_own_action() {
+    let Some(fixture) = fixture(&[(Action::Remove, (true, false, &[]))]).await else {
+        return;
+    };
+    let subject = Subject::bus_name(":1.42");
+
+    fixture
+        .agent
+        .authorize(Action::Remove, &subject)
+        .await
+        .unwrap();
+    let error = fixture
+        .agent
+        .authorize(Action::RepoEdit, &subject)
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Not authorized to change repositories");
+
+    let calls = fixture.calls.lock().unwrap();
+    assert_eq!(calls[0].kind, "system-bus-name");
+    assert_eq!(
+        calls[0].subject["name"].downcast_ref::<&str>().unwrap(),
+        ":1.42"
+    );
+    assert_eq!(calls[1].action_id, "com.fluxara.Store.repo-edit");
+}
+
+#[tokio::test]
+async fn challenge_is_not_authorization() {
+    let Some(fixture) = fixture(&[(Action::DriverInstall, (false, true, &[]))]).await else {
+        return;
+    };
+    let subject = Subject::current_process().unwrap();
+
+    let authorization = fixture
+        .agent
+        .check_authorization(Action::DriverInstall, &subject, false)
+        .await
+        .unwrap();
+    assert_eq!(authorization, Authorization::Challenge);
+    assert!(!authorization.is_authorized());
+    assert!(fixture
+        .agent
+        .authorize(Action::DriverInstall, &subject)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn unattended_action_never_asks_for_a_password() {
+    let Some(fixture) = fixture(&[(Action::Unattended, (true, false, &[]))]).await else {
+        return;
+    };
+
+    fixture
+        .agent
+        .authorize(Action::Unattended, &Subject::bus_name(":1.7"))
+        .await
+        .unwrap();
+
+    let calls = fixture.calls.lock().unwrap();
+    assert_eq!(calls[0].action_id, "com.fluxara.Store.unattended");
+    assert_eq!(calls[0].flags, 0);
+}
+
+#[tokio::test]
+async fn dismissed_authentication_is_reported() {
+    let dismissed: &[(&str, &str)] = &[("polkit.dismissed", "true")];
+    let Some(fixture) = fixture(&[(Action::Maintenance, (false, true, dismissed))]).await else {
+        return;
+    };
+
+    let error = fixture
+        .agent
+        .authorize(Action::Maintenance, &Subject::current_process().unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Authentication to run system maintenance was cancelled"
+    );
+}
+
+#[tokio::test]
+async fn missing_authority_is_an_error() {
+    let Some(bus) = private_bus() else {
+        return;
+    };
+    let client = zbus::connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let agent = PolkitAgent::with_connection(client);
+
+    let error = agent
+        .check_authorization(Action::Update, &Subject::current_process().unwrap(), false)
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", error).contains("com.fluxara.Store.update"));
+}
+
+#[test]
+fn policy_file_defines_every_action() {
+    let policy = include_str!("../data/com.fluxara.Store.policy");
+    for action in Action::ALL {
+        assert!(
+            policy.contains(&format!("<action id=\"{}\">", action.id())),
+            "{} missing from the policy file",
+            action
+        );
+    }
+    assert_eq!(policy.matches("<action id=").count(), Action::ALL.len());
+}
+
+#[test]
+fn rules_file_only_grants_the_unattended_action() {
+    let rules = include_str!("../data/com.fluxara.Store.rules");
+    assert!(rules.contains(&format!("\"{}\"", Action::Unattended.id())));
+    for action in Action::ALL {
+        if action != Action::Unattended {
+            assert!(
+                !rules.contains(action.id()),
+                "{} granted by the rules",
+                action
+            );
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use fluxara_core::{InstallScope, PackageSource};
+use fluxara_polkit_agent::requires_elevation;
+
+#[test]
+fn user_flatpak_installation_needs_no_elevation() {
+    assert!(!requires_elevation(
+        PackageSource::Flatpak,
+        InstallScope::User
+    ));
+    assert!(requires_elevation(
+        PackageSource::Flatpak,
+        InstallScope::System
+    ));
+}
+
+#[test]
+fn system_package_managers_need_elevation_in_any_scope() {
+    for source in [
+        PackageSource::Apt,
+        PackageSource::Pacman,
+        PackageSource::Snap,
+    ] {
+        for scope in [InstallScope::User, InstallScope::System] {
+            assert!(requires_elevation(source, scope), "{} {}", source, scope);
+        }
+    }
+}
+
+#[test]
+fn aur_packages_are_built_as_the_user() {
+    assert!(!requires_elevation(
+        PackageSource::Aur,
+        InstallScope::System
+    ));
+}
@@ -1,8 +1,14 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use fluxara_core::{InstallPlan, Package, PackageManager, PackageSource, UpdateInfo};
+use fluxara_core::{
+    Confinement, InstallPlan, Package, PackageDetails, PackageManager, PackageSource, UpdateInfo,
+};
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
 
+const SOURCES_LIST: &str = "/etc/apt/sources.list";
+const SOURCES_LIST_DIR: &str = "/etc/apt/sources.list.d";
+
 pub struct AptProvider;
 
 impl Default for AptProvider {
@@ -31,10 +37,176 @@ impl AptProvider {
 
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
+
+    fn run_apt_mark(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("apt-mark")
+            .args(args)
+            .output()
+            .context("Failed to execute apt-mark command")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "apt-mark command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// One-line `deb` entries from `sources.list` and `sources.list.d/*.list`
+    pub fn list_repositories(&self) -> Result<Vec<String>> {
+        let mut files = vec![std::path::PathBuf::from(SOURCES_LIST)];
+        if let Ok(entries) = std::fs::read_dir(SOURCES_LIST_DIR) {
+            let mut extra: Vec<_> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "list"))
+                .collect();
+            extra.sort();
+            files.extend(extra);
+        }
+
+        let repositories = files
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .flat_map(|content| {
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| line.starts_with("deb ") || line.starts_with("deb-src "))
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Ok(repositories)
+    }
+
+    /// Add a one-line source entry to Fluxara's list file and refresh the package index
+    pub fn add_repository(&self, entry: &str) -> Result<()> {
+        let path = std::path::Path::new(SOURCES_LIST_DIR).join("fluxara.list");
+        let mut content = std::fs::read_to_string(&path).unwrap_or_default();
+        if content.lines().any(|line| line.trim() == entry) {
+            return Ok(());
+        }
+        content.push_str(entry);
+        content.push('\n');
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        self.run_command(&["update"])?;
+        Ok(())
+    }
+
+    /// Remove a source entry added with [`AptProvider::add_repository`] and refresh the package index
+    pub fn remove_repository(&self, entry: &str) -> Result<()> {
+        let path = std::path::Path::new(SOURCES_LIST_DIR).join("fluxara.list");
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        if !content.lines().any(|line| line.trim() == entry) {
+            anyhow::bail!("{} was not added by Fluxara", entry);
+        }
+        let remaining: String = content
+            .lines()
+            .filter(|line| line.trim() != entry)
+            .map(|line| format!("{}\n", line))
+            .collect();
+        std::fs::write(&path, remaining)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        self.run_command(&["update"])?;
+        Ok(())
+    }
+
+    fn run_apt_cache(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("apt-cache")
+            .args(args)
+            .env("LC_ALL", "C")
+            .output()
+            .context("Failed to execute apt-cache command")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "apt-cache command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Parse the first stanza of `apt-cache show` output
+    fn parse_stanza(output: &str) -> HashMap<String, String> {
+        output
+            .lines()
+            .take_while(|line| !line.trim().is_empty())
+            .filter(|line| !line.starts_with(' '))
+            .filter_map(|line| line.split_once(": "))
+            .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+            .collect()
+    }
+
+    /// Return (installed version, suite of the candidate) from `apt-cache policy`
+    fn parse_policy(output: &str) -> (Option<String>, Option<String>) {
+        let field = |name: &str| {
+            output
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix(name))
+                .map(|value| value.trim().to_string())
+                .find(|value| value != "(none)")
+        };
+        let installed = field("Installed:");
+        let candidate = field("Candidate:");
+
+        let mut lines = output
+            .lines()
+            .map(|line| line.trim_start_matches([' ', '*']));
+        let suite = candidate.and_then(|candidate| {
+            lines.find(|line| line.split_whitespace().next() == Some(candidate.as_str()))?;
+            // Origin line: "500 http://archive.ubuntu.com/ubuntu jammy-updates/main amd64 Packages"
+            lines
+                .next()
+                .and_then(|origin| origin.split_whitespace().nth(2))
+                .map(str::to_string)
+        });
+
+        (installed, suite)
+    }
+
+    /// Package names listed by `apt-mark showhold`, one per line
+    fn parse_holds(output: &str) -> Vec<String> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// License from the Debian machine-readable copyright file of an installed package
+    fn read_license(package_id: &str) -> Option<String> {
+        std::fs::read_to_string(format!("/usr/share/doc/{}/copyright", package_id))
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("License:"))
+            .map(|license| license.trim().to_string())
+    }
 }
 
 #[async_trait]
 impl PackageManager for AptProvider {
+    fn source(&self) -> PackageSource {
+        PackageSource::Apt
+    }
+
+    fn is_available(&self) -> bool {
+        Command::new("which")
+            .arg("apt-get")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
     async fn search(&self, query: &str) -> Result<Vec<Package>> {
         // Stub implementation - would use apt-cache search
         let output = Command::new("apt-cache")
@@ -82,13 +254,120 @@ impl PackageManager for AptProvider {
     }
 
     async fn list_installed(&self) -> Result<Vec<Package>> {
-        // Stub implementation
-        Ok(vec![])
+        let output = Command::new("dpkg-query")
+            .args(["-W", "-f=${Package}\t${Version}\t${db:Status-Abbrev}\n"])
+            .output()
+            .context("Failed to execute dpkg-query")?;
+
+        let packages = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split('\t').collect();
+                // Only "ii" (installed) entries; removed packages keep config-file records
+                if parts.len() < 3 || !parts[2].starts_with("ii") {
+                    return None;
+                }
+                Some(Package {
+                    id: parts[0].to_string(),
+                    name: parts[0].to_string(),
+                    version: Some(parts[1].to_string()),
+                    description: None,
+                    icon_url: None,
+                    source: PackageSource::Apt,
+                    installed: true,
+                })
+            })
+            .collect();
+
+        Ok(packages)
+    }
+
+    async fn list_available(&self) -> Result<Vec<Package>> {
+        let installed: HashMap<String, Option<String>> = self
+            .list_installed()
+            .await?
+            .into_iter()
+            .map(|p| (p.id, p.version))
+            .collect();
+
+        // One stanza per available package version, newest first
+        let output = self.run_apt_cache(&["dumpavail"])?;
+
+        let mut seen = HashSet::new();
+        let packages = output
+            .split("\n\n")
+            .map(Self::parse_stanza)
+            .filter_map(|fields| {
+                let name = fields.get("Package")?;
+                if !seen.insert(name.clone()) {
+                    return None;
+                }
+                Some(Package {
+                    id: name.clone(),
+                    name: name.clone(),
+                    version: installed
+                        .get(name)
+                        .cloned()
+                        .flatten()
+                        .or_else(|| fields.get("Version").cloned()),
+                    description: fields.get("Description").cloned(),
+                    icon_url: None,
+                    source: PackageSource::Apt,
+                    installed: installed.contains_key(name),
+                })
+            })
+            .collect();
+
+        Ok(packages)
+    }
+
+    async fn list_explicit(&self) -> Result<Vec<Package>> {
+        let manual = self.run_apt_mark(&["showmanual"])?;
+        let manual: HashSet<&str> = manual.lines().map(str::trim).collect();
+
+        let mut packages = self.list_installed().await?;
+        packages.retain(|p| manual.contains(p.id.as_str()));
+        Ok(packages)
     }
 
     async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
-        // Stub implementation
-        Ok(vec![])
+        let output = Command::new("apt")
+            .args(["list", "--upgradable"])
+            .env("LC_ALL", "C")
+            .output()
+            .context("Failed to execute apt command")?;
+        let held = self.list_holds().await.unwrap_or_default();
+
+        // Lines look like `curl/stable 7.88.1-10+deb12u5 amd64 [upgradable from: 7.88.1-10+deb12u4]`,
+        // listing every suite offering the version before the first space
+        let updates = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (name, rest) = line.split_once('/')?;
+                let parts: Vec<&str> = rest.split_whitespace().collect();
+                let security = parts
+                    .first()?
+                    .split(',')
+                    .any(|suite| suite.ends_with("-security") || suite.ends_with("/updates"));
+                let current = line
+                    .split("upgradable from: ")
+                    .nth(1)?
+                    .trim_end_matches(']');
+                if held.iter().any(|h| h == name) {
+                    return None;
+                }
+                Some(UpdateInfo {
+                    package_id: name.to_string(),
+                    current_version: current.to_string(),
+                    new_version: parts.get(1)?.to_string(),
+                    source: PackageSource::Apt,
+                    size_bytes: None,
+                    security,
+                })
+            })
+            .collect();
+
+        Ok(updates)
     }
 
     async fn get_install_plan(&self, package_id: &str) -> Result<InstallPlan> {
@@ -100,4 +379,101 @@ impl PackageManager for AptProvider {
             requires_root: true,
         })
     }
+
+    async fn details(&self, package_id: &str) -> Result<Option<PackageDetails>> {
+        let Ok(show) = self.run_apt_cache(&["show", "--no-all-versions", package_id]) else {
+            return Ok(None);
+        };
+        let fields = Self::parse_stanza(&show);
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let policy = self
+            .run_apt_cache(&["policy", package_id])
+            .unwrap_or_default();
+        let (installed, suite) = Self::parse_policy(&policy);
+
+        Ok(Some(PackageDetails {
+            id: package_id.to_string(),
+            name: fields
+                .get("Package")
+                .cloned()
+                .unwrap_or_else(|| package_id.to_string()),
+            source: PackageSource::Apt,
+            version: fields.get("Version").cloned(),
+            // Installed-Size is given in KiB
+            size_bytes: fields
+                .get("Installed-Size")
+                .and_then(|size| size.parse::<u64>().ok())
+                .map(|kib| kib * 1024),
+            license: Self::read_license(package_id),
+            confinement: Confinement::Unconfined,
+            permissions: vec![],
+            channel: suite,
+            installed: installed.is_some(),
+        }))
+    }
+
+    async fn install_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["install", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn remove_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["remove", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["install", "--only-upgrade", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn download_updates(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["install", "--only-upgrade", "--download-only", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn install_version(&self, package_id: &str, version: &str) -> Result<()> {
+        let target = format!("{}={}", package_id, version);
+        self.run_command(&["install", "-y", "--allow-downgrades", &target])?;
+        Ok(())
+    }
+
+    async fn hold(&self, package_id: &str) -> Result<()> {
+        self.run_apt_mark(&["hold", package_id])?;
+        Ok(())
+    }
+
+    async fn unhold(&self, package_id: &str) -> Result<()> {
+        self.run_apt_mark(&["unhold", package_id])?;
+        Ok(())
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        Ok(Self::parse_holds(&self.run_apt_mark(&["showhold"])?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn showhold_lists_one_package_per_line() {
+        assert_eq!(
+            AptProvider::parse_holds("linux-image-generic\nfirefox \n\n"),
+            ["linux-image-generic", "firefox"]
+        );
+        assert!(AptProvider::parse_holds("").is_empty());
+    }
 }
@@ -1,9 +1,16 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use fluxara_core::{InstallPlan, Package, PackageManager, PackageSource, UpdateInfo};
+use fluxara_core::{
+    units, Confinement, InstallPlan, InstallScope, Package, PackageDetails, PackageManager,
+    PackageSource, RepoInfo, UpdateInfo,
+};
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
 
-pub struct FlatpakProvider;
+pub struct FlatpakProvider {
+    /// Installation to work on; `None` leaves the choice to flatpak
+    scope: Option<InstallScope>,
+}
 
 impl Default for FlatpakProvider {
     fn default() -> Self {
@@ -13,12 +20,28 @@ impl Default for FlatpakProvider {
 
 impl FlatpakProvider {
     pub fn new() -> Self {
-        Self
+        Self { scope: None }
+    }
+
+    /// Provider working on the user or the system installation only
+    pub fn with_scope(scope: InstallScope) -> Self {
+        Self { scope: Some(scope) }
+    }
+
+    pub fn scope(&self) -> Option<InstallScope> {
+        self.scope
     }
 
     fn run_command(&self, args: &[&str]) -> Result<String> {
-        let output = Command::new("flatpak")
-            .args(args)
+        let mut command = Command::new("flatpak");
+        if let Some((subcommand, rest)) = args.split_first() {
+            command.arg(subcommand);
+            if let Some(scope) = self.scope {
+                command.arg(format!("--{}", scope));
+            }
+            command.args(rest);
+        }
+        let output = command
             .output()
             .context("Failed to execute flatpak command")?;
 
@@ -31,10 +54,143 @@ impl FlatpakProvider {
 
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
+
+    /// Parse `Key: value` lines from `flatpak info` / `flatpak remote-info`
+    fn parse_fields(output: &str) -> HashMap<String, String> {
+        output
+            .lines()
+            .filter_map(|line| line.split_once(": "))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect()
+    }
+
+    /// Parse the `[Context]` group of Flatpak metadata into `key=value` permissions
+    fn parse_permissions(metadata: &str) -> Vec<String> {
+        let mut in_context = false;
+        let mut permissions = Vec::new();
+
+        for line in metadata.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_context = line == "[Context]";
+            } else if in_context && line.contains('=') {
+                permissions.push(line.trim_end_matches(';').to_string());
+            }
+        }
+
+        permissions
+    }
+
+    fn build_details(
+        &self,
+        package_id: &str,
+        info: &str,
+        metadata: &str,
+        installed: bool,
+    ) -> PackageDetails {
+        let fields = Self::parse_fields(info);
+        let name = info
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.contains(": "))
+            .and_then(|line| line.split(" - ").next())
+            .unwrap_or(package_id)
+            .to_string();
+        let channel = match (fields.get("Origin"), fields.get("Branch")) {
+            (Some(origin), Some(branch)) => Some(format!("{}/{}", origin, branch)),
+            (origin, branch) => origin.or(branch).cloned(),
+        };
+
+        PackageDetails {
+            id: package_id.to_string(),
+            name,
+            source: PackageSource::Flatpak,
+            version: fields.get("Version").cloned(),
+            size_bytes: fields
+                .get("Installed")
+                .or_else(|| fields.get("Installed size"))
+                .and_then(|size| units::parse_size(size)),
+            license: fields.get("License").cloned(),
+            confinement: Confinement::Sandboxed,
+            permissions: Self::parse_permissions(metadata),
+            channel,
+            installed,
+        }
+    }
+
+    /// List configured Flatpak remotes
+    pub fn list_remotes(&self) -> Result<Vec<RepoInfo>> {
+        let output = self.run_command(&["remotes", "--columns=name,url,options"])?;
+
+        let remotes = output
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split('\t').collect();
+                let name = parts.first()?.trim();
+                if name.is_empty() {
+                    return None;
+                }
+                Some(RepoInfo {
+                    name: name.to_string(),
+                    url: parts
+                        .get(1)
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_default(),
+                    enabled: !parts.get(2).is_some_and(|opts| opts.contains("disabled")),
+                    speed_mbps: None,
+                })
+            })
+            .collect();
+
+        Ok(remotes)
+    }
+
+    /// Patterns listed by `flatpak mask`, without its headings
+    fn parse_masks(output: &str) -> Vec<String> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| {
+                !line.is_empty() && !line.starts_with("No masked") && !line.ends_with(':')
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Whether a `flatpak mask` pattern such as `org.gnome.*` covers an app id
+    fn mask_matches(mask: &str, app_id: &str) -> bool {
+        match mask.strip_suffix('*') {
+            Some(prefix) => app_id.starts_with(prefix),
+            None => mask == app_id || mask.starts_with(&format!("app/{}/", app_id)),
+        }
+    }
+
+    /// Add a remote unless one with the same name exists
+    pub fn add_remote(&self, name: &str, url: &str) -> Result<()> {
+        self.run_command(&["remote-add", "--if-not-exists", name, url])?;
+        Ok(())
+    }
+
+    /// Remove a remote
+    pub fn remove_remote(&self, name: &str) -> Result<()> {
+        self.run_command(&["remote-delete", name])?;
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl PackageManager for FlatpakProvider {
+    fn source(&self) -> PackageSource {
+        PackageSource::Flatpak
+    }
+
+    fn is_available(&self) -> bool {
+        Command::new("which")
+            .arg("flatpak")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
     async fn search(&self, query: &str) -> Result<Vec<Package>> {
         let output = self.run_command(&["search", query])?;
 
@@ -105,9 +261,86 @@ impl PackageManager for FlatpakProvider {
         Ok(packages)
     }
 
+    async fn list_available(&self) -> Result<Vec<Package>> {
+        let installed: HashMap<String, Package> = self
+            .list_installed()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| (p.id.clone(), p))
+            .collect();
+
+        let output = self.run_command(&[
+            "remote-ls",
+            "--app",
+            "--columns=application,name,version,description",
+        ])?;
+
+        let mut seen = HashSet::new();
+        let mut packages: Vec<Package> = Vec::new();
+        for line in output.lines() {
+            let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let Some(id) = parts.first().filter(|id| !id.is_empty()) else {
+                continue;
+            };
+            // The same app may be offered by several remotes
+            if !seen.insert(id.to_string()) {
+                continue;
+            }
+            let local = installed.get(*id);
+            packages.push(Package {
+                id: id.to_string(),
+                name: parts.get(1).unwrap_or(id).to_string(),
+                version: local
+                    .and_then(|p| p.version.clone())
+                    .or_else(|| parts.get(2).map(|v| v.to_string())),
+                description: parts.get(3).map(|d| d.to_string()),
+                icon_url: None,
+                source: PackageSource::Flatpak,
+                installed: local.is_some(),
+            });
+        }
+
+        Ok(packages)
+    }
+
     async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
-        // Stub implementation
-        Ok(vec![])
+        let installed: HashMap<String, String> = self
+            .run_command(&["list", "--app", "--columns=application,version"])?
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(id, version)| (id.trim().to_string(), version.trim().to_string()))
+            .collect();
+        let masks = self.list_holds().await.unwrap_or_default();
+
+        let output = self.run_command(&[
+            "remote-ls",
+            "--updates",
+            "--app",
+            "--columns=application,version,download-size",
+        ])?;
+
+        let updates = output
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+                let id = *parts.first().filter(|id| !id.is_empty())?;
+                // `flatpak update` skips masked refs, so they are not pending updates
+                if masks.iter().any(|mask| Self::mask_matches(mask, id)) {
+                    return None;
+                }
+                Some(UpdateInfo {
+                    package_id: id.to_string(),
+                    current_version: installed.get(id).cloned().unwrap_or_default(),
+                    new_version: parts.get(1).map(|v| v.to_string()).unwrap_or_default(),
+                    source: PackageSource::Flatpak,
+                    size_bytes: parts.get(2).and_then(|size| units::parse_size(size)),
+                    security: false,
+                })
+            })
+            .collect();
+
+        Ok(updates)
     }
 
     async fn get_install_plan(&self, package_id: &str) -> Result<InstallPlan> {
@@ -119,4 +352,105 @@ impl PackageManager for FlatpakProvider {
             requires_root: false,
         })
     }
+
+    async fn details(&self, package_id: &str) -> Result<Option<PackageDetails>> {
+        // Installed apps are described by their local deployment
+        if let Ok(info) = self.run_command(&["info", package_id]) {
+            let metadata = self
+                .run_command(&["info", "--show-metadata", package_id])
+                .unwrap_or_default();
+            return Ok(Some(self.build_details(package_id, &info, &metadata, true)));
+        }
+
+        for remote in self.list_remotes()?.iter().filter(|r| r.enabled) {
+            if let Ok(info) = self.run_command(&["remote-info", &remote.name, package_id]) {
+                let metadata = self
+                    .run_command(&["remote-info", "--show-metadata", &remote.name, package_id])
+                    .unwrap_or_default();
+                return Ok(Some(
+                    self.build_details(package_id, &info, &metadata, false),
+                ));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn install_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["install", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn remove_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["uninstall", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["update", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn download_updates(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["update", "--no-deploy", "-y"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn hold(&self, package_id: &str) -> Result<()> {
+        self.run_command(&["mask", package_id])?;
+        Ok(())
+    }
+
+    async fn unhold(&self, package_id: &str) -> Result<()> {
+        self.run_command(&["mask", "--remove", package_id])?;
+        Ok(())
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        Ok(Self::parse_masks(&self.run_command(&["mask"])?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_are_listed_without_headings() {
+        assert_eq!(
+            FlatpakProvider::parse_masks(
+                "Masked patterns:\n  org.gnome.*\n  org.mozilla.firefox\n"
+            ),
+            ["org.gnome.*", "org.mozilla.firefox"]
+        );
+        assert!(FlatpakProvider::parse_masks("No masked patterns\n").is_empty());
+    }
+
+    #[test]
+    fn masks_cover_matching_apps() {
+        assert!(FlatpakProvider::mask_matches(
+            "org.gnome.*",
+            "org.gnome.Maps"
+        ));
+        assert!(FlatpakProvider::mask_matches(
+            "org.gnome.Maps",
+            "org.gnome.Maps"
+        ));
+        assert!(FlatpakProvider::mask_matches(
+            "app/org.gnome.Maps/x86_64/stable",
+            "org.gnome.Maps"
+        ));
+        assert!(!FlatpakProvider::mask_matches(
+            "org.gnome.Maps",
+            "org.gnome.Mapsy"
+        ));
+    }
 }
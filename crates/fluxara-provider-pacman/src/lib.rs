@@ -1,8 +1,15 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use fluxara_core::{InstallPlan, Package, PackageManager, PackageSource, UpdateInfo};
+use fluxara_core::{
+    units, Confinement, InstallPlan, Package, PackageDetails, PackageManager, PackageSource,
+    UpdateInfo,
+};
+use std::collections::HashMap;
 use std::process::Command;
 
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+const HOLDS_CONF: &str = "/etc/pacman.d/fluxara-holds.conf";
+
 pub struct PacmanProvider {
     is_manjaro: bool,
 }
@@ -29,6 +36,7 @@ impl PacmanProvider {
     fn run_command(&self, args: &[&str]) -> Result<String> {
         let output = Command::new("pacman")
             .args(args)
+            .env("LC_ALL", "C")
             .output()
             .context("Failed to execute pacman command")?;
 
@@ -42,16 +50,83 @@ impl PacmanProvider {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
+    /// Locate a previously downloaded package archive, e.g. `name-1.2-1-x86_64.pkg.tar.zst`
+    fn find_cached(package_id: &str, version: &str) -> Option<std::path::PathBuf> {
+        let prefix = format!("{}-{}-", package_id, version);
+        std::fs::read_dir("/var/cache/pacman/pkg")
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(&prefix)
+                            && name.contains(".pkg.tar")
+                            && !name.ends_with(".sig")
+                            // The version must not continue past the prefix, as in `-1.2-10-`
+                            && name[prefix.len()..].split('-').count() == 1
+                    })
+            })
+    }
+
     pub fn is_manjaro(&self) -> bool {
         self.is_manjaro
     }
-}
 
-#[async_trait]
-impl PackageManager for PacmanProvider {
-    async fn search(&self, query: &str) -> Result<Vec<Package>> {
-        let output = self.run_command(&["-Ss", query])?;
+    /// Packages listed in Fluxara's `IgnorePkg` include
+    fn read_holds() -> Vec<String> {
+        Self::parse_holds(&std::fs::read_to_string(HOLDS_CONF).unwrap_or_default())
+    }
+
+    /// Rewrite the `IgnorePkg` include and make sure pacman.conf loads it
+    fn write_holds(holds: &[String]) -> Result<()> {
+        std::fs::write(HOLDS_CONF, Self::holds_conf(holds))
+            .with_context(|| format!("Failed to write {}", HOLDS_CONF))?;
+
+        let pacman_conf = std::fs::read_to_string(PACMAN_CONF)
+            .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+        if let Some(updated) = Self::include_holds(&pacman_conf) {
+            std::fs::write(PACMAN_CONF, updated)
+                .with_context(|| format!("Failed to write {}", PACMAN_CONF))?;
+        }
+
+        Ok(())
+    }
+
+    /// Package names of the `IgnorePkg` lines of a pacman config file
+    fn parse_holds(content: &str) -> Vec<String> {
+        content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("IgnorePkg"))
+            .filter_map(|rest| rest.trim_start().strip_prefix('='))
+            .flat_map(|names| names.split_whitespace().map(str::to_string))
+            .collect()
+    }
+
+    /// Content of the include holding `holds`
+    fn holds_conf(holds: &[String]) -> String {
+        let mut content =
+            String::from("# Managed by Fluxara; use `fluxara hold` and `fluxara unhold`\n");
+        if !holds.is_empty() {
+            content.push_str(&format!("IgnorePkg = {}\n", holds.join(" ")));
+        }
+        content
+    }
 
+    /// `pacman_conf` with the holds include added, or `None` when it is
+    /// there already
+    fn include_holds(pacman_conf: &str) -> Option<String> {
+        let include = format!("Include = {}", HOLDS_CONF);
+        if pacman_conf.lines().any(|line| line.trim() == include) {
+            return None;
+        }
+        // Include lines are read in the section they appear in, so it goes under [options]
+        Some(pacman_conf.replacen("[options]", &format!("[options]\n{}", include), 1))
+    }
+
+    /// Parse `-Ss` output: a `repo/name version [installed]` line followed by an indented description
+    fn parse_search(output: &str) -> Vec<Package> {
         let mut packages = Vec::new();
         let lines: Vec<&str> = output.lines().collect();
 
@@ -67,13 +142,65 @@ impl PackageManager for PacmanProvider {
                         description: chunk.get(1).map(|s| s.trim().to_string()),
                         icon_url: None,
                         source: PackageSource::Pacman,
-                        installed: false,
+                        installed: first_line.contains("[installed"),
                     });
                 }
             }
         }
 
-        Ok(packages)
+        packages
+    }
+
+    /// Parse `name version` lines from `pacman -Q`
+    fn parse_query(output: &str) -> Vec<Package> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() >= 2 {
+                    Some(Package {
+                        id: parts[0].to_string(),
+                        name: parts[0].to_string(),
+                        version: Some(parts[1].to_string()),
+                        description: None,
+                        icon_url: None,
+                        source: PackageSource::Pacman,
+                        installed: true,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Parse `Key : value` lines from `pacman -Si` / `pacman -Qi`
+    fn parse_info(output: &str) -> HashMap<String, String> {
+        output
+            .lines()
+            .filter_map(|line| line.split_once(" : "))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl PackageManager for PacmanProvider {
+    fn source(&self) -> PackageSource {
+        PackageSource::Pacman
+    }
+
+    fn is_available(&self) -> bool {
+        Command::new("which")
+            .arg("pacman")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Package>> {
+        let output = self.run_command(&["-Ss", query])?;
+        Ok(Self::parse_search(&output))
     }
 
     async fn install(&self, package_id: &str) -> Result<()> {
@@ -93,33 +220,49 @@ impl PackageManager for PacmanProvider {
 
     async fn list_installed(&self) -> Result<Vec<Package>> {
         let output = self.run_command(&["-Q"])?;
+        Ok(Self::parse_query(&output))
+    }
+
+    async fn list_available(&self) -> Result<Vec<Package>> {
+        // Without a search term -Ss lists every package of the sync databases
+        let output = self.run_command(&["-Ss"])?;
+        Ok(Self::parse_search(&output))
+    }
 
-        let packages = output
+    async fn list_explicit(&self) -> Result<Vec<Package>> {
+        let output = self.run_command(&["-Qe"])?;
+        Ok(Self::parse_query(&output))
+    }
+
+    async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
+        // Exits with status 1 when nothing is upgradable, so read stdout directly
+        let output = Command::new("pacman")
+            .arg("-Qu")
+            .env("LC_ALL", "C")
+            .output()
+            .context("Failed to execute pacman command")?;
+
+        // Lines look like `linux 6.6.1-1 -> 6.6.2-1`, with `[ignored]` for IgnorePkg entries
+        let updates = String::from_utf8_lossy(&output.stdout)
             .lines()
+            .filter(|line| !line.ends_with("[ignored]"))
             .filter_map(|line| {
                 let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    Some(Package {
-                        id: parts[0].to_string(),
-                        name: parts[0].to_string(),
-                        version: Some(parts[1].to_string()),
-                        description: None,
-                        icon_url: None,
-                        source: PackageSource::Pacman,
-                        installed: true,
-                    })
-                } else {
-                    None
+                if parts.len() < 4 || parts[2] != "->" {
+                    return None;
                 }
+                Some(UpdateInfo {
+                    package_id: parts[0].to_string(),
+                    current_version: parts[1].to_string(),
+                    new_version: parts[3].to_string(),
+                    source: PackageSource::Pacman,
+                    size_bytes: None,
+                    security: false,
+                })
             })
             .collect();
 
-        Ok(packages)
-    }
-
-    async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
-        // Stub implementation
-        Ok(vec![])
+        Ok(updates)
     }
 
     async fn get_install_plan(&self, package_id: &str) -> Result<InstallPlan> {
@@ -131,4 +274,120 @@ impl PackageManager for PacmanProvider {
             requires_root: true,
         })
     }
+
+    async fn details(&self, package_id: &str) -> Result<Option<PackageDetails>> {
+        let Ok(info) = self.run_command(&["-Si", package_id]) else {
+            return Ok(None);
+        };
+        let fields = Self::parse_info(&info);
+        let installed = self.run_command(&["-Q", package_id]).is_ok();
+
+        Ok(Some(PackageDetails {
+            id: package_id.to_string(),
+            name: fields
+                .get("Name")
+                .cloned()
+                .unwrap_or_else(|| package_id.to_string()),
+            source: PackageSource::Pacman,
+            version: fields.get("Version").cloned(),
+            size_bytes: fields
+                .get("Installed Size")
+                .and_then(|size| units::parse_size(size)),
+            license: fields.get("Licenses").cloned(),
+            confinement: Confinement::Unconfined,
+            permissions: vec![],
+            channel: fields.get("Repository").cloned(),
+            installed,
+        }))
+    }
+
+    async fn install_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["-S", "--noconfirm"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn remove_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["-R", "--noconfirm"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["-S", "--noconfirm"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn download_updates(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["-Sw", "--noconfirm"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn install_version(&self, package_id: &str, version: &str) -> Result<()> {
+        let archive = Self::find_cached(package_id, version).with_context(|| {
+            format!(
+                "{} {} is not in the pacman package cache",
+                package_id, version
+            )
+        })?;
+        self.run_command(&["-U", "--noconfirm", &archive.to_string_lossy()])?;
+        Ok(())
+    }
+
+    async fn hold(&self, package_id: &str) -> Result<()> {
+        let mut holds = Self::read_holds();
+        if !holds.iter().any(|h| h == package_id) {
+            holds.push(package_id.to_string());
+        }
+        Self::write_holds(&holds)
+    }
+
+    async fn unhold(&self, package_id: &str) -> Result<()> {
+        let mut holds = Self::read_holds();
+        holds.retain(|h| h != package_id);
+        Self::write_holds(&holds)
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        Ok(Self::read_holds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_are_read_back_from_the_include() {
+        let holds = vec!["linux".to_string(), "mesa".to_string()];
+        let content = PacmanProvider::holds_conf(&holds);
+        assert_eq!(PacmanProvider::parse_holds(&content), holds);
+        assert!(PacmanProvider::parse_holds(&PacmanProvider::holds_conf(&[])).is_empty());
+
+        let hand_written = "# comment\n  IgnorePkg=linux\nIgnorePkgs = nope\nIgnorePkg = a b\n";
+        assert_eq!(
+            PacmanProvider::parse_holds(hand_written),
+            ["linux", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn include_is_added_under_options_once() {
+        let pacman_conf =
+            "[options]\nHoldPkg = pacman glibc\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+
+        let updated = PacmanProvider::include_holds(pacman_conf).unwrap();
+        assert_eq!(
+            updated,
+            "[options]\nInclude = /etc/pacman.d/fluxara-holds.conf\nHoldPkg = pacman glibc\n\n\
+             [core]\nInclude = /etc/pacman.d/mirrorlist\n"
+        );
+        assert_eq!(PacmanProvider::include_holds(&updated), None);
+    }
 }
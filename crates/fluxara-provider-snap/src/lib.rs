@@ -0,0 +1,337 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use fluxara_core::{
+    units, Confinement, InstallPlan, Package, PackageDetails, PackageManager, PackageSource,
+    UpdateInfo,
+};
+use std::collections::HashMap;
+use std::process::Command;
+
+pub struct SnapProvider;
+
+impl Default for SnapProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnapProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn run_command(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("snap")
+            .args(args)
+            .env("LC_ALL", "C")
+            .output()
+            .context("Failed to execute snap command")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Snap command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Parse a channel line such as `latest/stable: 120.0 2023-11-20 (3358) 250MB classic`
+    ///
+    /// Returns (version, size, confinement); closed channels (`^` or `--`) yield `None`.
+    fn parse_channel(line: &str) -> Option<(String, Option<u64>, Confinement)> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let version = parts.first().filter(|v| !matches!(**v, "^" | "--"))?;
+        let size = parts.iter().rev().nth(1).and_then(|s| units::parse_size(s));
+        let confinement = match parts.last() {
+            Some(&"classic") => Confinement::Classic,
+            Some(&"devmode") => Confinement::Devmode,
+            _ => Confinement::Strict,
+        };
+        Some((version.to_string(), size, confinement))
+    }
+
+    /// Whether the snap uses classic confinement, which `snap install` only
+    /// accepts with `--classic`
+    async fn is_classic(&self, package_id: &str) -> bool {
+        matches!(
+            self.details(package_id).await,
+            Ok(Some(PackageDetails {
+                confinement: Confinement::Classic,
+                ..
+            }))
+        )
+    }
+}
+
+#[async_trait]
+impl PackageManager for SnapProvider {
+    fn source(&self) -> PackageSource {
+        PackageSource::Snap
+    }
+
+    fn is_available(&self) -> bool {
+        Command::new("which")
+            .arg("snap")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Package>> {
+        let output = self.run_command(&["find", query])?;
+
+        let packages = output
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() >= 2 {
+                    Some(Package {
+                        id: parts[0].to_string(),
+                        name: parts[0].to_string(),
+                        version: Some(parts[1].to_string()),
+                        description: parts.get(4..).map(|s| s.join(" ")),
+                        icon_url: None,
+                        source: PackageSource::Snap,
+                        installed: false,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(packages)
+    }
+
+    async fn install(&self, package_id: &str) -> Result<()> {
+        if self.is_classic(package_id).await {
+            self.run_command(&["install", "--classic", package_id])?;
+        } else {
+            self.run_command(&["install", package_id])?;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, package_id: &str) -> Result<()> {
+        self.run_command(&["remove", package_id])?;
+        Ok(())
+    }
+
+    async fn update(&self, package_id: &str) -> Result<()> {
+        self.run_command(&["refresh", package_id])?;
+        Ok(())
+    }
+
+    async fn list_installed(&self) -> Result<Vec<Package>> {
+        let output = self.run_command(&["list"])?;
+
+        let packages = output
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() >= 2 {
+                    Some(Package {
+                        id: parts[0].to_string(),
+                        name: parts[0].to_string(),
+                        version: Some(parts[1].to_string()),
+                        description: None,
+                        icon_url: None,
+                        source: PackageSource::Snap,
+                        installed: true,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(packages)
+    }
+
+    async fn list_updates(&self) -> Result<Vec<UpdateInfo>> {
+        let installed: HashMap<String, String> = self
+            .list_installed()
+            .await?
+            .into_iter()
+            .filter_map(|p| Some((p.id, p.version?)))
+            .collect();
+
+        // Prints "All snaps up to date." on stderr and nothing on stdout when idle
+        let output = self.run_command(&["refresh", "--list"])?;
+
+        let updates = output
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() < 2 {
+                    return None;
+                }
+                Some(UpdateInfo {
+                    package_id: parts[0].to_string(),
+                    current_version: installed.get(parts[0]).cloned().unwrap_or_default(),
+                    new_version: parts[1].to_string(),
+                    source: PackageSource::Snap,
+                    size_bytes: parts.get(3).and_then(|s| units::parse_size(s)),
+                    security: false,
+                })
+            })
+            .collect();
+
+        Ok(updates)
+    }
+
+    async fn get_install_plan(&self, package_id: &str) -> Result<InstallPlan> {
+        Ok(InstallPlan {
+            package_id: package_id.to_string(),
+            source: PackageSource::Snap,
+            version: None,
+            dependencies: vec![],
+            requires_root: true,
+        })
+    }
+
+    async fn details(&self, package_id: &str) -> Result<Option<PackageDetails>> {
+        let Ok(info) = self.run_command(&["info", package_id]) else {
+            return Ok(None);
+        };
+
+        let fields: HashMap<&str, &str> = info
+            .lines()
+            .filter(|line| !line.starts_with(' '))
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key, value.trim()))
+            .collect();
+        let channels: HashMap<&str, &str> = info
+            .lines()
+            .filter(|line| line.starts_with("  "))
+            .filter_map(|line| line.trim().split_once(": "))
+            .collect();
+
+        let installed = fields.get("installed").copied();
+        let channel = fields
+            .get("tracking")
+            .copied()
+            .unwrap_or("latest/stable")
+            .to_string();
+
+        // Installed snaps report on the local revision, others on the default channel
+        let (version, size_bytes, confinement) = installed
+            .or_else(|| channels.get(channel.as_str()).copied())
+            .and_then(Self::parse_channel)
+            .map(|(version, size, confinement)| (Some(version), size, confinement))
+            .unwrap_or((None, None, Confinement::Strict));
+
+        let permissions = if installed.is_some() {
+            self.run_command(&["connections", package_id])
+                .map(|output| {
+                    output
+                        .lines()
+                        .skip(1)
+                        .filter_map(|line| line.split_whitespace().next())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            vec![]
+        };
+
+        Ok(Some(PackageDetails {
+            id: package_id.to_string(),
+            name: fields
+                .get("name")
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| package_id.to_string()),
+            source: PackageSource::Snap,
+            version,
+            size_bytes,
+            license: fields
+                .get("license")
+                .filter(|license| **license != "unset")
+                .map(|license| license.to_string()),
+            confinement,
+            permissions,
+            channel: Some(channel),
+            installed: installed.is_some(),
+        }))
+    }
+
+    async fn install_packages(&self, package_ids: &[String]) -> Result<()> {
+        // `--classic` is refused alongside several snap names, so classic
+        // snaps are installed one at a time
+        let mut strict = vec![];
+        for package_id in package_ids {
+            if self.is_classic(package_id).await {
+                self.run_command(&["install", "--classic", package_id])?;
+            } else {
+                strict.push(package_id.as_str());
+            }
+        }
+        if !strict.is_empty() {
+            let mut args = vec!["install"];
+            args.extend(strict);
+            self.run_command(&args)?;
+        }
+        Ok(())
+    }
+
+    async fn remove_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["remove"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn update_packages(&self, package_ids: &[String]) -> Result<()> {
+        let mut args = vec!["refresh"];
+        args.extend(package_ids.iter().map(String::as_str));
+        self.run_command(&args)?;
+        Ok(())
+    }
+
+    async fn install_version(&self, package_id: &str, version: &str) -> Result<()> {
+        // Snaps keep earlier revisions around; revert to the one carrying this version
+        let output = self.run_command(&["list", "--all", package_id])?;
+        let revision = output
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .find(|parts| parts.len() >= 3 && parts[0] == package_id && parts[1] == version)
+            .map(|parts| parts[2].to_string())
+            .with_context(|| {
+                format!(
+                    "No retained revision of {} has version {}",
+                    package_id, version
+                )
+            })?;
+        self.run_command(&["revert", package_id, "--revision", &revision])?;
+        Ok(())
+    }
+
+    async fn hold(&self, package_id: &str) -> Result<()> {
+        self.run_command(&["refresh", "--hold", package_id])?;
+        Ok(())
+    }
+
+    async fn unhold(&self, package_id: &str) -> Result<()> {
+        self.run_command(&["refresh", "--unhold", package_id])?;
+        Ok(())
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        // The Notes column of `snap list` reads "held" for snaps excluded from refreshes
+        let output = self.run_command(&["list"])?;
+        Ok(output
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|parts| parts.last().is_some_and(|notes| notes.contains("held")))
+            .map(|parts| parts[0].to_string())
+            .collect())
+    }
+}
@@ -1,4 +1,11 @@
-use fluxara_core::Config;
+use fluxara_core::{Config, PackageManager, ProviderRegistry};
+use fluxara_provider_apt::AptProvider;
+use fluxara_provider_flatpak::FlatpakProvider;
+use fluxara_provider_pacman::PacmanProvider;
+use fluxara_provider_snap::SnapProvider;
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
 
 pub struct FluxaraApp {
     config: Config,
@@ -22,3 +29,46 @@ impl FluxaraApp {
         self.config.save()
     }
 }
+
+/// Every package provider the store knows about; unavailable ones are skipped by the registry
+///
+/// Changes that need administrator rights go through the privileged helper.
+pub fn provider_registry() -> ProviderRegistry {
+    let scope = Config::load().unwrap_or_default().install.flatpak_scope;
+    let providers: [Box<dyn PackageManager>; 4] = [
+        Box::new(FlatpakProvider::with_scope(scope)),
+        Box::new(AptProvider::new()),
+        Box::new(PacmanProvider::new()),
+        Box::new(SnapProvider::new()),
+    ];
+
+    let mut registry = ProviderRegistry::new();
+    for provider in providers {
+        registry.register(fluxara_polkit_agent::route(provider, scope));
+    }
+    registry
+}
+
+/// Runtime for work that needs tokio, such as talking to the privileged
+/// helper; the GTK main loop is not one
+pub fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to start the tokio runtime")
+    })
+}
+
+/// Run `future` on the tokio [`runtime`] and await its output from the main context
+pub async fn spawn<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match runtime().spawn(future).await {
+        Ok(output) => output,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
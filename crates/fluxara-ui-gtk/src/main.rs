@@ -1,4 +1,5 @@
 mod app;
+mod review;
 mod window;
 
 use gtk4::prelude::*;
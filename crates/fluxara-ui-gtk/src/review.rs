@@ -0,0 +1,197 @@
+use adw::prelude::*;
+use fluxara_appstream::{OdrsClient, DESCRIPTION_MAX_CHARS, SUMMARY_MAX_CHARS};
+use fluxara_core::{AppReview, Config};
+use gtk4::{self as gtk, gio, glib, Orientation};
+use libadwaita as adw;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Modal dialog composing a review of an installed app for ODRS
+pub struct ReviewDialog {
+    window: adw::Window,
+}
+
+impl ReviewDialog {
+    pub fn new(
+        parent: Option<&gtk::Window>,
+        app_id: &str,
+        name: &str,
+        version: Option<String>,
+    ) -> Self {
+        let window = adw::Window::builder()
+            .modal(true)
+            .default_width(480)
+            .title(format!("Review {}", name))
+            .build();
+        window.set_transient_for(parent);
+
+        let header_bar = adw::HeaderBar::new();
+        header_bar.set_show_end_title_buttons(false);
+        let cancel_button = gtk::Button::with_label("Cancel");
+        header_bar.pack_start(&cancel_button);
+        let send_button = gtk::Button::with_label("Send");
+        send_button.add_css_class("suggested-action");
+        send_button.set_sensitive(false);
+        header_bar.pack_end(&send_button);
+
+        let content = gtk::Box::new(Orientation::Vertical, 12);
+        content.set_margin_top(24);
+        content.set_margin_bottom(24);
+        content.set_margin_start(24);
+        content.set_margin_end(24);
+
+        let heading = gtk::Label::new(Some("How would you rate this app?"));
+        heading.add_css_class("title-4");
+        content.append(&heading);
+
+        let rating = Rc::new(Cell::new(0u32));
+        let stars = gtk::Box::new(Orientation::Horizontal, 6);
+        stars.set_halign(gtk::Align::Center);
+        let star_buttons: Rc<Vec<gtk::Button>> = Rc::new(
+            (1..=5)
+                .map(|value| {
+                    let button = gtk::Button::from_icon_name("non-starred-symbolic");
+                    button.add_css_class("flat");
+                    button.set_tooltip_text(Some(&format!("{} of 5 stars", value)));
+                    stars.append(&button);
+                    button
+                })
+                .collect(),
+        );
+        content.append(&stars);
+
+        let summary_entry = gtk::Entry::new();
+        summary_entry.set_placeholder_text(Some("Summary"));
+        summary_entry.set_max_length(SUMMARY_MAX_CHARS as i32);
+        content.append(&summary_entry);
+
+        let description_view = gtk::TextView::new();
+        description_view.set_wrap_mode(gtk::WrapMode::WordChar);
+        description_view.set_top_margin(6);
+        description_view.set_bottom_margin(6);
+        description_view.set_left_margin(6);
+        description_view.set_right_margin(6);
+        let description_scroll = gtk::ScrolledWindow::builder()
+            .child(&description_view)
+            .min_content_height(160)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .build();
+        description_scroll.add_css_class("card");
+        content.append(&description_scroll);
+
+        let reviewer_entry = gtk::Entry::new();
+        reviewer_entry.set_placeholder_text(Some("Your name (leave empty to post anonymously)"));
+        if let Ok(real_name) = glib::real_name().into_string() {
+            if real_name != "Unknown" {
+                reviewer_entry.set_text(&real_name);
+            }
+        }
+        content.append(&reviewer_entry);
+
+        let error_label = gtk::Label::new(None);
+        error_label.add_css_class("error");
+        error_label.set_wrap(true);
+        error_label.set_visible(false);
+        content.append(&error_label);
+
+        // Send is only offered once the review would pass validation
+        let update_send = {
+            let send_button = send_button.clone();
+            let rating = rating.clone();
+            let summary_entry = summary_entry.clone();
+            let buffer = description_view.buffer();
+            Rc::new(move || {
+                let summary = summary_entry.text();
+                let description = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+                send_button.set_sensitive(
+                    rating.get() > 0
+                        && !summary.trim().is_empty()
+                        && !description.trim().is_empty()
+                        && description.chars().count() <= DESCRIPTION_MAX_CHARS,
+                );
+            })
+        };
+
+        for (value, button) in (1..=5).zip(star_buttons.iter()) {
+            let rating = rating.clone();
+            let star_buttons = star_buttons.clone();
+            let update_send = update_send.clone();
+            button.connect_clicked(move |_| {
+                rating.set(value);
+                for (star, button) in (1..=5).zip(star_buttons.iter()) {
+                    button.set_icon_name(if star <= value {
+                        "starred-symbolic"
+                    } else {
+                        "non-starred-symbolic"
+                    });
+                }
+                update_send();
+            });
+        }
+        {
+            let update_send = update_send.clone();
+            summary_entry.connect_changed(move |_| update_send());
+        }
+        description_view
+            .buffer()
+            .connect_changed(move |_| update_send());
+
+        {
+            let window = window.clone();
+            cancel_button.connect_clicked(move |_| window.close());
+        }
+
+        let app_id = app_id.to_string();
+        let window_clone = window.clone();
+        send_button.connect_clicked(move |button| {
+            let buffer = description_view.buffer();
+            let review = AppReview {
+                review_id: None,
+                app_id: app_id.clone(),
+                rating: rating.get(),
+                summary: summary_entry.text().to_string(),
+                description: buffer
+                    .text(&buffer.start_iter(), &buffer.end_iter(), false)
+                    .to_string(),
+                reviewer: reviewer_entry.text().to_string(),
+                date: String::new(),
+                version: version.clone(),
+                own: true,
+            };
+
+            button.set_sensitive(false);
+            error_label.set_visible(false);
+            let button = button.clone();
+            let window = window_clone.clone();
+            let error_label = error_label.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let result = gio::spawn_blocking(move || {
+                    let odrs = OdrsClient::from_config(&Config::load().unwrap_or_default());
+                    odrs.submit_review(&review).map_err(|e| format!("{:#}", e))
+                })
+                .await
+                .unwrap_or_else(|_| Err("Review submission stopped unexpectedly".to_string()));
+
+                match result {
+                    Ok(()) => window.close(),
+                    Err(message) => {
+                        error_label.set_label(&message);
+                        error_label.set_visible(true);
+                        button.set_sensitive(true);
+                    }
+                }
+            });
+        });
+
+        let layout = gtk::Box::new(Orientation::Vertical, 0);
+        layout.append(&header_bar);
+        layout.append(&content);
+        window.set_content(Some(&layout));
+
+        Self { window }
+    }
+
+    pub fn present(&self) {
+        self.window.present();
+    }
+}
@@ -1,8 +1,37 @@
-use crate::app::FluxaraApp;
+use crate::app::{self, FluxaraApp};
+use crate::review::ReviewDialog;
+use adw::prelude::*;
 use adw::subclass::prelude::*;
+use fluxara_appstream::{AppStreamManager, Collection, Component, IconKind, OdrsClient, Release};
+use fluxara_core::config::ParentalConfig;
+use fluxara_core::{
+    units, App, AppRating, Config, DriverInfo, History, OperationKind, Origin, Transaction,
+    UpdateInfo,
+};
+use fluxara_drivers::DriverManager;
+use fluxara_media::MediaCache;
+use fluxara_polkit_agent::HelperClient;
 use gtk4::prelude::*;
-use gtk4::{self as gtk, Orientation};
+use gtk4::{self as gtk, gio, glib, Orientation};
 use libadwaita as adw;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
+
+/// Apps shown in each home page carousel
+const CAROUSEL_SIZE: usize = 12;
+
+/// Icon and screenshot cache shared by every page, if the cache directory is usable
+fn media_cache() -> Option<Arc<MediaCache>> {
+    static MEDIA: OnceLock<Option<Arc<MediaCache>>> = OnceLock::new();
+    MEDIA
+        .get_or_init(|| {
+            MediaCache::from_config(&Config::load().unwrap_or_default())
+                .map(Arc::new)
+                .ok()
+        })
+        .clone()
+}
 
 pub struct FluxaraWindow {
     window: adw::ApplicationWindow,
@@ -79,15 +108,325 @@ impl FluxaraWindow {
         let scrolled = gtk::ScrolledWindow::new();
         scrolled.set_vexpand(true);
 
+        let content = gtk::Box::new(Orientation::Vertical, 18);
+        scrolled.set_child(Some(&content));
+
+        // Search results and category listings, hidden until there are some
         let list_box = gtk::ListBox::new();
         list_box.add_css_class("boxed-list");
-        scrolled.set_child(Some(&list_box));
+        list_box.set_visible(false);
+        content.append(&list_box);
+
+        let browse = gtk::Box::new(Orientation::Vertical, 18);
+        content.append(&browse);
 
         page.append(&scrolled);
 
+        let list = list_box.clone();
+        search_entry.connect_activate(move |entry| {
+            let query = entry.text().to_string();
+            let list = list.clone();
+            list.set_visible(!query.trim().is_empty());
+            glib::MainContext::default().spawn_local(async move {
+                Self::load_search_results(&list, &query).await;
+            });
+        });
+
+        let list = list_box.clone();
+        search_entry.connect_search_changed(move |entry| {
+            if entry.text().is_empty() {
+                list.set_visible(false);
+            }
+        });
+
+        glib::MainContext::default().spawn_local(async move {
+            // Parsing the catalogs and fetching ratings take a moment, so they
+            // stay off the main thread
+            let Ok((appstream, ratings)) = gio::spawn_blocking(|| {
+                let appstream = AppStreamManager::new();
+                appstream.catalog();
+                let odrs = OdrsClient::from_config(&Config::load().unwrap_or_default());
+                let ratings = odrs.get_ratings().unwrap_or_default();
+                (appstream, ratings)
+            })
+            .await
+            else {
+                return;
+            };
+            Self::fill_browse_page(&browse, &list_box, Rc::new(appstream), &ratings);
+        });
+
         page
     }
 
+    /// Category tiles, then carousels of new, updated, popular and curated apps
+    fn fill_browse_page(
+        browse: &gtk::Box,
+        list_box: &gtk::ListBox,
+        appstream: Rc<AppStreamManager>,
+        ratings: &HashMap<String, AppRating>,
+    ) {
+        let categories = appstream.category_counts();
+        if !categories.is_empty() {
+            let heading = gtk::Label::new(Some("Categories"));
+            heading.add_css_class("title-3");
+            heading.set_halign(gtk::Align::Start);
+            browse.append(&heading);
+
+            let tiles = gtk::FlowBox::new();
+            tiles.set_selection_mode(gtk::SelectionMode::None);
+            tiles.set_homogeneous(true);
+            tiles.set_max_children_per_line(6);
+            tiles.set_column_spacing(12);
+            tiles.set_row_spacing(12);
+            for (category, count) in categories {
+                let tile = gtk::Button::with_label(&format!(
+                    "{}\n{} apps",
+                    Self::category_label(category),
+                    count
+                ));
+                tile.add_css_class("card");
+                let list = list_box.clone();
+                let appstream = appstream.clone();
+                tile.connect_clicked(move |_| {
+                    while let Some(row) = list.first_child() {
+                        list.remove(&row);
+                    }
+                    for component in appstream.browse(category) {
+                        list.append(&Self::create_component_row(component));
+                    }
+                    list.set_visible(true);
+                });
+                tiles.insert(&tile, -1);
+            }
+            browse.append(&tiles);
+        }
+
+        let collections = Collection::load_default().unwrap_or_else(|e| {
+            eprintln!("Failed to load collections: {}", e);
+            vec![]
+        });
+        let mut sections = vec![
+            ("New".to_string(), appstream.new_apps(CAROUSEL_SIZE)),
+            (
+                "Recently Updated".to_string(),
+                appstream.recently_updated(CAROUSEL_SIZE),
+            ),
+            (
+                "Popular".to_string(),
+                appstream.popular(ratings, CAROUSEL_SIZE),
+            ),
+        ];
+        for collection in &collections {
+            sections.push((
+                collection.title.clone(),
+                appstream.collection_apps(collection),
+            ));
+        }
+
+        for (title, components) in sections {
+            if components.is_empty() {
+                continue;
+            }
+            let heading = gtk::Label::new(Some(&title));
+            heading.add_css_class("title-3");
+            heading.set_halign(gtk::Align::Start);
+            browse.append(&heading);
+            browse.append(&Self::create_carousel(&components));
+        }
+    }
+
+    fn create_carousel(components: &[&Component]) -> gtk::Box {
+        let section = gtk::Box::new(Orientation::Vertical, 6);
+
+        let carousel = adw::Carousel::new();
+        carousel.set_spacing(12);
+        for component in components {
+            carousel.append(&Self::create_app_card(component));
+        }
+        section.append(&carousel);
+
+        let dots = adw::CarouselIndicatorDots::new();
+        dots.set_carousel(Some(&carousel));
+        section.append(&dots);
+
+        section
+    }
+
+    fn create_app_card(component: &Component) -> gtk::Box {
+        let card = gtk::Box::new(Orientation::Vertical, 6);
+        card.add_css_class("card");
+        card.set_size_request(240, 160);
+        card.set_margin_top(6);
+        card.set_margin_bottom(6);
+
+        card.append(&Self::component_icon(component, 64));
+
+        let name = gtk::Label::new(Some(&component.name));
+        name.add_css_class("heading");
+        card.append(&name);
+
+        if let Some(summary) = &component.summary {
+            let summary = gtk::Label::new(Some(summary));
+            summary.add_css_class("dim-label");
+            summary.set_wrap(true);
+            summary.set_justify(gtk::Justification::Center);
+            summary.set_max_width_chars(30);
+            card.append(&summary);
+        }
+
+        card
+    }
+
+    fn create_component_row(component: &Component) -> adw::ActionRow {
+        let row = adw::ActionRow::new();
+        row.set_title(&component.name);
+        if let Some(summary) = &component.summary {
+            row.set_subtitle(summary);
+        }
+        row.add_prefix(&Self::component_icon(component, 32));
+        row
+    }
+
+    /// Icon of a component: a catalog or cached icon file when there is one,
+    /// otherwise its theme icon until a remote icon has been downloaded
+    fn component_icon(component: &Component, size: i32) -> gtk::Image {
+        let name = component
+            .icons
+            .iter()
+            .find(|icon| icon.kind == IconKind::Stock)
+            .map_or("application-x-executable", |icon| icon.value.as_str());
+        let image = gtk::Image::from_icon_name(name);
+        image.set_pixel_size(size);
+
+        let Some(media) = media_cache() else {
+            return image;
+        };
+        // Icons are fetched at twice the size so they stay sharp on HiDPI screens
+        let pixels = size as u32 * 2;
+        if let Some(path) = media.local_icon(component, pixels) {
+            image.set_from_file(Some(&path));
+            return image;
+        }
+        if component
+            .icons
+            .iter()
+            .any(|icon| icon.kind == IconKind::Remote)
+        {
+            let component = component.clone();
+            let image = image.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let downloaded =
+                    gio::spawn_blocking(move || media.icon(&component, pixels).ok().flatten())
+                        .await;
+                if let Ok(Some(path)) = downloaded {
+                    image.set_from_file(Some(&path));
+                }
+            });
+        }
+        image
+    }
+
+    fn category_label(category: &str) -> &str {
+        match category {
+            "AudioVideo" => "Audio & Video",
+            other => other,
+        }
+    }
+
+    /// Search every source and show one card per app rather than per package
+    async fn load_search_results(list_box: &gtk::ListBox, query: &str) {
+        while let Some(row) = list_box.first_child() {
+            list_box.remove(&row);
+        }
+        if query.trim().is_empty() {
+            return;
+        }
+
+        let query = query.to_string();
+        let mut packages = app::spawn(async move {
+            let registry = app::provider_registry();
+            let mut packages = Vec::new();
+            for provider in registry.available() {
+                if let Ok(found) = provider.search(&query).await {
+                    packages.extend(found);
+                }
+            }
+            packages
+        })
+        .await;
+
+        // Loading the policy and parsing the catalogs read files, so they stay
+        // off the main thread like the browse page
+        let Ok(apps) = gio::spawn_blocking(move || {
+            // Apps the parental controls policy forbids are not offered at all;
+            // the helper refuses to install them either way
+            let config = Config::load().unwrap_or_default();
+            let parental = ParentalConfig::system().unwrap_or_else(|e| {
+                eprintln!("Failed to load the parental controls policy: {:#}", e);
+                ParentalConfig::default()
+            });
+            let appstream = AppStreamManager::new();
+            appstream.retain_allowed(&parental, &mut packages);
+            if let Some(media) = media_cache() {
+                media.fill_icon_urls(appstream.catalog(), &mut packages, config.media.icon_size);
+            }
+            appstream.identity_resolver().group(packages)
+        })
+        .await
+        else {
+            return;
+        };
+
+        for app in apps {
+            list_box.append(&Self::create_app_row(&app));
+        }
+    }
+
+    fn create_app_row(app: &App) -> adw::ActionRow {
+        let row = adw::ActionRow::new();
+        row.set_title(&app.name);
+        if let Some(summary) = &app.summary {
+            row.set_subtitle(summary);
+        }
+        let icon = match &app.icon_url {
+            Some(path) => gtk::Image::from_file(path),
+            None => gtk::Image::from_icon_name("application-x-executable"),
+        };
+        icon.set_pixel_size(32);
+        row.add_prefix(&icon);
+
+        for package in &app.packages {
+            let badge = gtk::Label::new(Some(&package.source.to_string()));
+            badge.add_css_class("caption");
+            badge.add_css_class(if package.installed {
+                "success"
+            } else {
+                "dim-label"
+            });
+            badge.set_tooltip_text(Some(&package.id));
+            row.add_suffix(&badge);
+        }
+
+        // Only apps in use can be reviewed, against the installed version
+        if let Some(installed) = app.packages.iter().find(|p| p.installed) {
+            let review_button = gtk::Button::from_icon_name("document-edit-symbolic");
+            review_button.add_css_class("flat");
+            review_button.set_valign(gtk::Align::Center);
+            review_button.set_tooltip_text(Some("Write a review"));
+            let app_id = app.id.clone();
+            let name = app.name.clone();
+            let version = installed.version.clone();
+            review_button.connect_clicked(move |button| {
+                let parent = button.root().and_downcast::<gtk::Window>();
+                ReviewDialog::new(parent.as_ref(), &app_id, &name, version.clone()).present();
+            });
+            row.add_suffix(&review_button);
+        }
+
+        row
+    }
+
     fn create_updates_page() -> gtk::Box {
         let page = gtk::Box::new(Orientation::Vertical, 12);
         page.set_margin_top(24);
@@ -112,9 +451,185 @@ impl FluxaraWindow {
 
         page.append(&scrolled);
 
+        let list = list_box.clone();
+        glib::MainContext::default().spawn_local(async move {
+            Self::load_updates(&list).await;
+        });
+
+        update_all_button.connect_clicked(move |button| {
+            button.set_sensitive(false);
+            let button = button.clone();
+            let list = list_box.clone();
+            glib::MainContext::default().spawn_local(async move {
+                Self::apply_updates().await;
+                Self::load_updates(&list).await;
+                button.set_sensitive(true);
+            });
+        });
+
         page
     }
 
+    /// Updates of every source, gathered on the tokio runtime
+    async fn collect_updates() -> Vec<UpdateInfo> {
+        app::spawn(async {
+            let registry = app::provider_registry();
+            let mut updates = Vec::new();
+            for provider in registry.available() {
+                if let Ok(found) = provider.list_updates().await {
+                    updates.extend(found);
+                }
+            }
+            updates
+        })
+        .await
+    }
+
+    async fn load_updates(list_box: &gtk::ListBox) {
+        while let Some(row) = list_box.first_child() {
+            list_box.remove(&row);
+        }
+
+        let updates = Self::collect_updates().await;
+        // Release notes come from the parsed catalogs, so they are looked up
+        // off the main thread
+        let Ok(updates) = gio::spawn_blocking(move || {
+            let appstream = AppStreamManager::new();
+            updates
+                .into_iter()
+                .map(|update| {
+                    let notes = appstream.release_notes(&update);
+                    (update, notes)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        else {
+            return;
+        };
+
+        // Held packages stay listed so their hold can be released from here
+        let config = Config::load().unwrap_or_default();
+        for (update, notes) in updates {
+            let held = config.updates.is_held(&update.package_id);
+            list_box.append(&Self::create_update_row(&update, held, &notes));
+        }
+    }
+
+    /// Apply every update that is not held, recording the transaction in the history
+    async fn apply_updates() {
+        let config = Config::load().unwrap_or_default();
+        let mut updates = Self::collect_updates().await;
+        config.updates.retain_unheld(&mut updates);
+
+        let mut transaction = Transaction::new();
+        for update in &updates {
+            transaction.add(OperationKind::Update, &update.package_id, update.source);
+        }
+
+        let history = match History::open() {
+            Ok(history) => history,
+            Err(e) => {
+                eprintln!("Failed to open the transaction history: {}", e);
+                return;
+            }
+        };
+        // Elevated changes go through the helper, whose client needs tokio
+        let (_, entry) = app::spawn(async move {
+            transaction
+                .execute_recorded(&app::provider_registry(), &history, Origin::User)
+                .await
+        })
+        .await;
+        if let Err(e) = entry {
+            eprintln!("Failed to record transaction history: {}", e);
+        }
+    }
+
+    fn create_update_row(update: &UpdateInfo, held: bool, notes: &[Release]) -> adw::ExpanderRow {
+        let row = adw::ExpanderRow::new();
+        row.set_title(&update.package_id);
+        row.set_subtitle(&format!(
+            "{} → {} ({})",
+            update.current_version, update.new_version, update.source
+        ));
+
+        let hold_button = gtk::ToggleButton::with_label("Hold");
+        hold_button.set_active(held);
+        hold_button.set_valign(gtk::Align::Center);
+        hold_button.set_tooltip_text(Some("Keep this package at its installed version"));
+
+        let package_id = update.package_id.clone();
+        let source = update.source;
+        hold_button.connect_toggled(move |button| {
+            let held = button.is_active();
+            let mut config = Config::load().unwrap_or_default();
+            config.updates.holds.retain(|hold| hold != &package_id);
+            if held {
+                config.updates.holds.push(package_id.clone());
+            }
+            if let Err(e) = config.save() {
+                eprintln!("Failed to save holds: {}", e);
+            }
+
+            // Also hold it in the backend, as `fluxara hold` does, so that
+            // updates run outside Fluxara respect it; the helper does this
+            // for system packages
+            let package_id = package_id.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let id = package_id.clone();
+                let result: anyhow::Result<()> = app::spawn(async move {
+                    let registry = app::provider_registry();
+                    let Some(provider) = registry.get(source) else {
+                        return Ok(());
+                    };
+                    if held {
+                        provider.hold(&id).await
+                    } else if provider.list_holds().await?.contains(&id) {
+                        provider.unhold(&id).await
+                    } else {
+                        Ok(())
+                    }
+                })
+                .await;
+                if let Err(e) = result {
+                    eprintln!(
+                        "Failed to {} {} in {}: {:#}",
+                        if held { "hold" } else { "release" },
+                        package_id,
+                        source,
+                        e
+                    );
+                }
+            });
+        });
+        row.add_action(&hold_button);
+
+        // Release notes unfold below the row
+        row.set_enable_expansion(!notes.is_empty());
+        for release in notes {
+            let heading = match release.date {
+                Some(date) => format!("{} ({})", release.version, date),
+                None => release.version.clone(),
+            };
+            let notes_row = adw::ActionRow::new();
+            notes_row.set_title(&heading);
+            if let Some(description) = &release.description {
+                notes_row.set_subtitle(description);
+            }
+            if release
+                .urgency
+                .as_deref()
+                .is_some_and(|u| u == "high" || u == "critical")
+            {
+                notes_row.add_css_class("warning");
+            }
+            row.add_row(&notes_row);
+        }
+
+        row
+    }
+
     fn create_drivers_page() -> gtk::Box {
         let page = gtk::Box::new(Orientation::Vertical, 12);
         page.set_margin_top(24);
@@ -138,9 +653,79 @@ impl FluxaraWindow {
 
         page.append(&scrolled);
 
+        detect_button.connect_clicked(move |button| {
+            button.set_sensitive(false);
+            let button = button.clone();
+            let list = list_box.clone();
+            glib::MainContext::default().spawn_local(async move {
+                Self::load_drivers(&list).await;
+                button.set_sensitive(true);
+            });
+        });
+
         page
     }
 
+    async fn load_drivers(list_box: &gtk::ListBox) {
+        while let Some(row) = list_box.first_child() {
+            list_box.remove(&row);
+        }
+
+        let drivers =
+            gio::spawn_blocking(|| DriverManager::new().check_proprietary_drivers()).await;
+        match drivers {
+            Ok(Ok(drivers)) if drivers.is_empty() => {
+                let row = adw::ActionRow::new();
+                row.set_title("No additional drivers available");
+                list_box.append(&row);
+            }
+            Ok(Ok(drivers)) => {
+                for driver in &drivers {
+                    list_box.append(&Self::create_driver_row(driver));
+                }
+            }
+            Ok(Err(e)) => eprintln!("Failed to detect drivers: {}", e),
+            Err(_) => eprintln!("Driver detection panicked"),
+        }
+    }
+
+    fn create_driver_row(driver: &DriverInfo) -> adw::ActionRow {
+        let row = adw::ActionRow::new();
+        row.set_title(&driver.name);
+        row.set_subtitle(&driver.description);
+
+        let install_button = gtk::Button::with_label("Install");
+        install_button.set_valign(gtk::Align::Center);
+        install_button.set_sensitive(!driver.packages.is_empty());
+
+        let name = driver.name.clone();
+        install_button.connect_clicked(move |button| {
+            button.set_sensitive(false);
+            let button = button.clone();
+            let name = name.clone();
+            glib::MainContext::default().spawn_local(async move {
+                // Driver packages belong to root; the helper installs them
+                let result = app::spawn(async move {
+                    HelperClient::system()
+                        .await?
+                        .install_driver(&name, |_| {})
+                        .await
+                })
+                .await;
+                match result {
+                    Ok(()) => button.set_label("Installed"),
+                    Err(e) => {
+                        eprintln!("Failed to install driver: {}", e);
+                        button.set_sensitive(true);
+                    }
+                }
+            });
+        });
+        row.add_suffix(&install_button);
+
+        row
+    }
+
     fn create_maintenance_page() -> gtk::Box {
         let page = gtk::Box::new(Orientation::Vertical, 12);
         page.set_margin_top(24);
@@ -165,6 +750,27 @@ impl FluxaraWindow {
 
         page.append(&group);
 
+        let status = gtk::Label::new(None);
+        status.add_css_class("dim-label");
+        page.append(&status);
+
+        clean_cache_button.connect_clicked(move |button| {
+            button.set_sensitive(false);
+            let button = button.clone();
+            let status = status.clone();
+            glib::MainContext::default().spawn_local(async move {
+                // Package caches belong to root; the helper cleans them
+                let result =
+                    app::spawn(async { HelperClient::system().await?.clean_cache(|_| {}).await })
+                        .await;
+                match result {
+                    Ok(freed) => status.set_text(&format!("Freed {}", units::format_size(freed))),
+                    Err(e) => status.set_text(&format!("Failed to clean the cache: {}", e)),
+                }
+                button.set_sensitive(true);
+            });
+        });
+
         page
     }
 
@@ -186,8 +792,16 @@ impl FluxaraWindow {
         tray_row.set_title("Enable Tray Icon");
         tray_row.set_subtitle("Show system tray icon for background updates");
         let tray_switch = gtk::Switch::new();
-        tray_switch.set_active(true);
+        tray_switch.set_active(Config::load().unwrap_or_default().ui.tray_enabled);
         tray_switch.set_valign(gtk::Align::Center);
+        // The daemon notices the saved setting and shows or hides its icon
+        tray_switch.connect_active_notify(|switch| {
+            let mut config = Config::load().unwrap_or_default();
+            config.ui.tray_enabled = switch.is_active();
+            if let Err(e) = config.save() {
+                eprintln!("Failed to save tray setting: {}", e);
+            }
+        });
         tray_row.add_suffix(&tray_switch);
         preferences_group.add(&tray_row);
 
@@ -0,0 +1,36 @@
+//! Private D-Bus fixture shared by the integration tests of the daemon, the
+//! helper and the PolicyKit agent, each including it with `#[path]`
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+/// Private bus, stopped when dropped
+pub struct Bus {
+    daemon: Child,
+    pub address: String,
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// Start a private bus; `None` when `dbus-daemon` is not installed
+pub fn private_bus() -> Option<Bus> {
+    let mut daemon = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address=1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut address = String::new();
+    BufReader::new(daemon.stdout.take()?)
+        .read_line(&mut address)
+        .ok()?;
+    Some(Bus {
+        daemon,
+        address: address.trim().to_string(),
+    })
+}